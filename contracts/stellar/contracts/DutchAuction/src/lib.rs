@@ -1,15 +1,14 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Bytes, BytesN, Env};
+#![allow(non_snake_case)]
+pub mod math;
 
-mod relayer {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\relayer.wasm");
-}
+use math::Rounding;
 
-mod escrow_factory {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowfactory.wasm");
-}
+use fusionx_types::interfaces::{EscrowFactoryClient, RelayerClient};
+use fusionx_types::{Error, Order, OrderInput};
+use soroban_sdk::{contract, contractimpl, contracttype, panic_with_error, Address, BytesN, Env};
 
-const MAX_AUCTION_TIME: u128 = 10 * 60; // 10 minutes in seconds
+const MAX_AUCTION_TIME: u64 = 10 * 60; // 10 minutes in seconds
 const AUCTION_START_BUFFER: u128 = 2 * 60; // 2 minutes in seconds
 
 #[contract]
@@ -24,52 +23,39 @@ pub enum DataKey {
     Order(BytesN<32>), // order ID
 }
 
-#[derive(Clone)]
-#[contracttype]
-pub struct Order {
-    pub maker: Address,
-    pub tokenIn: Address,
-    pub tokenOut: BytesN<32>,
-    pub amountIn: u128,
-    pub amountOut: u128,
-    pub minAmountOut: u128,
-    pub maxAmountOut: u128,
-    pub resolverAssigned: Address,
-    pub startTime: u128,
-    pub hashLock: BytesN<32>,
-}
-
-#[derive(Clone)]
-#[contracttype]
-pub struct OrderInput {
-    pub orderId: BytesN<32>,
-    pub maker: Address,
-    pub tokenIn: Address,
-    pub tokenOut: BytesN<32>,
-    pub amountIn: u128,
-    pub minAmountOut: u128,
-    pub maxAmountOut: u128,
-    pub hashLock: BytesN<32>,
-}
-
 #[contractimpl]
 impl DutchAuction {
     pub fn initialize(env: Env, owner: Address) {
         env.storage().persistent().set(&DataKey::Owner, &owner);
     }
 
-    pub fn start_auction(env: Env, order_input: OrderInput) {
+    /// Decimals come from the relayer's token registry.
+    pub fn start_auction(
+        env: Env,
+        order_input: OrderInput,
+        token_in_decimals: u32,
+        token_out_decimals: u32,
+    ) {
         Self::only_relayer(env.clone());
+        Self::validate_amounts(
+            env.clone(),
+            &order_input,
+            token_in_decimals,
+            token_out_decimals,
+        );
+
         let block_time: u128 = env.ledger().timestamp().into();
         let order = Order {
             maker: order_input.maker,
             tokenIn: order_input.tokenIn,
             tokenOut: order_input.tokenOut,
+            tokenInDecimals: token_in_decimals,
+            tokenOutDecimals: token_out_decimals,
             amountIn: order_input.amountIn,
             amountOut: 0,
             minAmountOut: order_input.minAmountOut,
             maxAmountOut: order_input.maxAmountOut,
-            resolverAssigned: Self::zero_address(&env),
+            resolverAssigned: None,
             startTime: block_time + AUCTION_START_BUFFER,
             hashLock: order_input.hashLock,
         };
@@ -79,47 +65,54 @@ impl DutchAuction {
             .set(&DataKey::Order(order_input.orderId), &order);
     }
 
-    pub fn fillOrder(env: Env, caller: Address, order_id: BytesN<32>) {
+    /// Assigns the order to `caller` at the current price and locks the
+    /// maker's tokens. Reverts if the price has moved above `max_amount_out`,
+    /// so a fill landing later than planned can't commit to more.
+    pub fn fillOrder(env: Env, caller: Address, order_id: BytesN<32>, max_amount_out: u128) {
+        caller.require_auth();
         Self::only_resolver(env.clone(), caller.clone());
         let block_time: u128 = env.ledger().timestamp().into();
         let mut order = env
             .storage()
             .persistent()
             .get::<DataKey, Order>(&DataKey::Order(order_id.clone()))
-            .unwrap_or_else(|| {
-                panic!("Order not found");
-            });
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound));
         if order.startTime > block_time {
-            panic!("Auction has not started yet");
+            panic_with_error!(&env, Error::AuctionNotStarted);
         }
         let amount_out = Self::get_amount_out(env.clone(), order_id.clone());
+        if amount_out > max_amount_out {
+            panic_with_error!(&env, Error::SlippageExceeded);
+        }
         order.amountOut = amount_out;
+        order.resolverAssigned = Some(caller.clone());
         env.storage()
             .persistent()
             .set(&DataKey::Order(order_id.clone()), &order);
 
         // deploy escrow src
         let escrow_factory_contract =
-            escrow_factory::Client::new(&env.clone(), &Self::get_escrow_factory(env.clone()));
+            EscrowFactoryClient::new(&env.clone(), &Self::get_escrow_factory(env.clone()));
 
         let escrow_src_address = escrow_factory_contract.deploy_src(
             &order_id.clone(),
             &order.hashLock.clone(),
             &order.tokenIn.clone(),
-            &order.amountIn.clone(),
+            &order.amountIn,
             &order.maker.clone(),
             &caller.clone(), //executive_resolver
             &env.current_contract_address().clone(),
         );
 
         // move funds from relayer to escrow
-        let relayer = relayer::Client::new(&env.clone(), &Self::get_relayer(env.clone()));
+        let relayer = RelayerClient::new(&env.clone(), &Self::get_relayer(env.clone()));
 
         relayer.move_tokens_to_escrow(
+            &order_id,
             &order.maker.clone(),
             &order.tokenIn.clone(),
             &escrow_src_address.clone(),
-            &order.amountIn.clone(),
+            &order.amountIn,
         );
     }
 
@@ -128,35 +121,94 @@ impl DutchAuction {
             .storage()
             .persistent()
             .get::<DataKey, Order>(&DataKey::Order(order_id))
-            .unwrap_or_else(|| {
-                panic!("Order not found");
-            });
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound));
 
-        let start_time: u128 = order.startTime;
-        let end_time: u128 = start_time + MAX_AUCTION_TIME;
+        // the price is flat at maxAmountOut until the auction starts
         let block_time: u128 = env.ledger().timestamp().into();
-        let current_time: u128 = block_time.max(start_time).min(end_time);
+        let elapsed = block_time.saturating_sub(order.startTime) as u64;
+
+        math::amount_out(
+            order.maxAmountOut,
+            order.minAmountOut,
+            elapsed,
+            MAX_AUCTION_TIME,
+        )
+        .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidAmount))
+    }
 
-        let amount_out = (order.maxAmountOut * (end_time - current_time)
-            + order.minAmountOut * (current_time - start_time))
-            / (end_time - start_time);
+    /// Current amount out scaled to 18 decimals.
+    pub fn get_normalized_amount_out(env: Env, order_id: BytesN<32>) -> u128 {
+        let order = Self::get_order(env.clone(), order_id.clone());
+        let amount_out = Self::get_amount_out(env.clone(), order_id);
+        math::normalize(amount_out, order.tokenOutDecimals, Rounding::Down)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidAmount))
+    }
+
+    /// Current price in tokenOut per tokenIn, with 18 decimals.
+    pub fn get_price(env: Env, order_id: BytesN<32>) -> u128 {
+        let order = Self::get_order(env.clone(), order_id.clone());
+        let amount_out = Self::get_amount_out(env.clone(), order_id);
+        Self::price(env, &order, amount_out)
+    }
 
-        amount_out
+    /// (lowest, highest) price of the auction, with 18 decimals.
+    pub fn get_price_range(env: Env, order_id: BytesN<32>) -> (u128, u128) {
+        let order = Self::get_order(env.clone(), order_id);
+        (
+            Self::price(env.clone(), &order, order.minAmountOut),
+            Self::price(env, &order, order.maxAmountOut),
+        )
     }
 
-    fn zero_address(env: &Env) -> Address {
-        let zero_bytes = Bytes::from_array(env, &[0u8; 32]);
-        Address::from_string_bytes(&zero_bytes)
+    fn price(env: Env, order: &Order, amount_out: u128) -> u128 {
+        math::price(
+            order.amountIn,
+            order.tokenInDecimals,
+            amount_out,
+            order.tokenOutDecimals,
+            Rounding::Down,
+        )
+        .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidAmount))
+    }
+
+    // amounts must be transferable as i128 and stay in range once normalized
+    fn validate_amounts(
+        env: Env,
+        order_input: &OrderInput,
+        token_in_decimals: u32,
+        token_out_decimals: u32,
+    ) {
+        let valid = order_input.amountIn > 0
+            && order_input.amountIn <= i128::MAX as u128
+            && order_input.minAmountOut <= order_input.maxAmountOut
+            && math::price(
+                order_input.amountIn,
+                token_in_decimals,
+                order_input.maxAmountOut,
+                token_out_decimals,
+                Rounding::Down,
+            )
+            .is_some();
+        if !valid {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
     }
 
     fn only_resolver(env: Env, caller: Address) {
-        let relayer = relayer::Client::new(&env.clone(), &Self::get_relayer(env));
+        let relayer = RelayerClient::new(&env, &Self::get_relayer(env.clone()));
         let value = relayer.is_resolver(&caller);
         if !value {
-            panic!("Only resolvers can call this function");
+            panic_with_error!(&env, Error::NotResolver);
         }
     }
 
+    pub fn get_order(env: Env, order_id: BytesN<32>) -> Order {
+        env.storage()
+            .persistent()
+            .get::<DataKey, Order>(&DataKey::Order(order_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound))
+    }
+
     pub fn get_relayer(env: Env) -> Address {
         env.storage().persistent().get(&DataKey::Relayer).unwrap()
     }
@@ -0,0 +1,113 @@
+//! Checked fixed-point math for the auction price curve. Nothing here can
+//! overflow: invalid inputs return `None` instead of panicking.
+
+/// Decimals amounts are scaled to when comparing tokens across chains.
+pub const NORMALIZED_DECIMALS: u32 = 18;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// `a * b / denominator` with a 256 bit intermediate product. `None` if
+/// `denominator` is zero or the result does not fit in `u128`.
+pub fn mul_div(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let (hi, lo) = mul_wide(a, b);
+    // the quotient only fits in 128 bits if the high half is below the denominator
+    if hi >= denominator {
+        return None;
+    }
+    let (quotient, remainder) = div_wide(hi, lo, denominator);
+    if rounding == Rounding::Up && remainder != 0 {
+        return quotient.checked_add(1);
+    }
+    Some(quotient)
+}
+
+/// Price `elapsed` seconds into an auction that falls linearly from
+/// `max_amount_out` to `min_amount_out` over `duration` seconds, clamped to
+/// the end of the auction.
+///
+/// The decrease is rounded down, so the maker never receives less than the
+/// exact curve. `None` if `min_amount_out > max_amount_out` or `duration` is
+/// zero.
+pub fn amount_out(
+    max_amount_out: u128,
+    min_amount_out: u128,
+    elapsed: u64,
+    duration: u64,
+) -> Option<u128> {
+    let range = max_amount_out.checked_sub(min_amount_out)?;
+    let decrease = mul_div(
+        range,
+        elapsed.min(duration) as u128,
+        duration as u128,
+        Rounding::Down,
+    )?;
+    // decrease <= range, so this cannot underflow
+    Some(max_amount_out - decrease)
+}
+
+/// Scales `amount` of a token with `decimals` to [`NORMALIZED_DECIMALS`].
+/// `None` if the scaled amount does not fit in `u128`.
+pub fn normalize(amount: u128, decimals: u32, rounding: Rounding) -> Option<u128> {
+    if decimals <= NORMALIZED_DECIMALS {
+        amount.checked_mul(pow10(NORMALIZED_DECIMALS - decimals)?)
+    } else {
+        mul_div(amount, 1, pow10(decimals - NORMALIZED_DECIMALS)?, rounding)
+    }
+}
+
+/// Units of the out token per unit of the in token, with
+/// [`NORMALIZED_DECIMALS`] decimals. `None` if `amount_in` is zero or the
+/// price does not fit in `u128`.
+pub fn price(
+    amount_in: u128,
+    decimals_in: u32,
+    amount_out: u128,
+    decimals_out: u32,
+    rounding: Rounding,
+) -> Option<u128> {
+    let amount_in = normalize(amount_in, decimals_in, rounding)?;
+    let amount_out = normalize(amount_out, decimals_out, rounding)?;
+    mul_div(amount_out, pow10(NORMALIZED_DECIMALS)?, amount_in, rounding)
+}
+
+pub fn pow10(exp: u32) -> Option<u128> {
+    10u128.checked_pow(exp)
+}
+
+// full 256 bit product as (high, low) halves
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+    let lo = (p00 & MASK) | (mid << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+// (hi, lo) / d by shift and subtract, requires hi < d
+fn div_wide(hi: u128, lo: u128, d: u128) -> (u128, u128) {
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= d {
+            remainder = remainder.wrapping_sub(d);
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
@@ -1,22 +1,12 @@
 #![no_std]
+use fusionx_types::interfaces::{EscrowDestClient, EscrowSrcClient, RelayerClient};
+use fusionx_types::{Error, Immutables};
 use soroban_sdk::{
-    contract, contractimpl, contracttype, crypto::Hash, token, Address, Bytes, BytesN, Env, String,
+    contract, contractimpl, contracttype, panic_with_error, token, Address, BytesN, Env,
 };
 
 const SECURITY_DEPOSIT: u128 = 500_000_000; // 1 XLM in stroops (assuming XLM as security deposit)
 
-mod escrow_src {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowsrc.wasm");
-    pub type EscrowSrcClient<'a> = Client<'a>;
-}
-use escrow_src::EscrowSrcClient;
-
-mod escrow_dest {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowdest.wasm");
-    pub type EscrowDestClient<'a> = Client<'a>;
-}
-use escrow_dest::EscrowDestClient;
-
 #[contract]
 pub struct EscrowFactory;
 
@@ -25,20 +15,40 @@ pub struct EscrowFactory;
 pub enum DataKey {
     DutchAuction,
     Relayer,
+    NativeToken,        // token the security deposit is paid in (XLM)
+    EscrowSrcWasmHash,  // wasm hash of the uploaded escrow src contract
+    EscrowDestWasmHash, // wasm hash of the uploaded escrow dest contract
     OrderIdToEscrow(BytesN<32>),
     EscrowToOrderId(Address),
 }
 
 #[contractimpl]
 impl EscrowFactory {
-    pub fn initialize(env: Env, dutch_auction: Address, relayer: Address) {
+    pub fn initialize(
+        env: Env,
+        dutch_auction: Address,
+        relayer: Address,
+        native_token: Address,
+        escrow_src_wasm_hash: BytesN<32>,
+        escrow_dest_wasm_hash: BytesN<32>,
+    ) {
         env.storage()
             .instance()
             .set(&DataKey::DutchAuction, &dutch_auction);
         env.storage().instance().set(&DataKey::Relayer, &relayer);
+        env.storage()
+            .instance()
+            .set(&DataKey::NativeToken, &native_token);
+        env.storage()
+            .instance()
+            .set(&DataKey::EscrowSrcWasmHash, &escrow_src_wasm_hash);
+        env.storage()
+            .instance()
+            .set(&DataKey::EscrowDestWasmHash, &escrow_dest_wasm_hash);
     }
 
     // this function would be called by dutch auction
+    #[allow(clippy::too_many_arguments)]
     pub fn deploy_src(
         env: Env,
         order_id: BytesN<32>,
@@ -49,27 +59,27 @@ impl EscrowFactory {
         executive_resolver: Address,
         caller: Address,
     ) -> Address {
+        Self::only_dutch_auction(env.clone(), caller);
         executive_resolver.require_auth();
         Self::check_security_deposit(env.clone(), executive_resolver.clone());
 
-        let escrow_src_wasm = include_bytes!("G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowsrc.wasm");
-        let escrow_src_wasm_bytes = Bytes::from_array(&env, escrow_src_wasm);
-        let wasm_hash = env.deployer().upload_contract_wasm(escrow_src_wasm_bytes);
+        let wasm_hash = Self::get_escrow_src_wasm_hash(env.clone());
         let escrow_contract_address = env
             .deployer()
             .with_current_contract(order_id.clone())
-            .deploy(wasm_hash);
+            .deploy_v2(wasm_hash, (env.current_contract_address(),));
         let escrow_client = EscrowSrcClient::new(&env, &escrow_contract_address);
 
-        escrow_client.initialize(
-            &order_id,
-            &hash_lock,
-            &token_in,
-            &amount_in,
-            &maker,
-            &executive_resolver,
-            &Self::get_relayer(env.clone()),
-        );
+        escrow_client.initialize(&Immutables {
+            order_id: order_id.clone(),
+            hash_lock,
+            token: token_in,
+            amount: amount_in,
+            maker,
+            executive_resolver,
+            relayer: Self::get_relayer(env.clone()),
+            native_token: Self::get_native_token(env.clone()),
+        });
 
         // transfer security deposit to the escrow contract
         Self::transfer_security_deposit(env.clone(), escrow_contract_address.clone());
@@ -82,6 +92,7 @@ impl EscrowFactory {
         escrow_contract_address
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn deploy_dest(
         env: Env,
         order_id: BytesN<32>,
@@ -91,29 +102,47 @@ impl EscrowFactory {
         maker: Address,
         executive_resolver: Address,
     ) -> Address {
+        // only resolvers, so nobody else can take the order's escrow address
+        // ahead of the one that filled it
+        if !RelayerClient::new(&env, &Self::get_relayer(env.clone()))
+            .is_resolver(&executive_resolver)
+        {
+            panic_with_error!(&env, Error::NotResolver);
+        }
         executive_resolver.require_auth();
         Self::check_security_deposit(env.clone(), executive_resolver.clone());
 
-        let escrow_dest_wasm = include_bytes!("G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowdest.wasm");
-        let escrow_dest_wasm_bytes = Bytes::from_array(&env, escrow_dest_wasm);
-        let wasm_hash = env.deployer().upload_contract_wasm(escrow_dest_wasm_bytes);
-
+        let wasm_hash = Self::get_escrow_dest_wasm_hash(env.clone());
         let escrow_contract_address = env
             .deployer()
             .with_current_contract(order_id.clone())
-            .deploy(wasm_hash);
+            .deploy_v2(wasm_hash, (env.current_contract_address(),));
 
         let escrow_client = EscrowDestClient::new(&env, &escrow_contract_address);
 
-        escrow_client.initialize(
-            &order_id,
-            &hash_lock,
-            &token_out,
-            &amount_out,
-            &maker,
+        escrow_client.initialize(&Immutables {
+            order_id: order_id.clone(),
+            hash_lock,
+            token: token_out.clone(),
+            amount: amount_out,
+            maker,
+            executive_resolver: executive_resolver.clone(),
+            relayer: Self::get_relayer(env.clone()),
+            native_token: Self::get_native_token(env.clone()),
+        });
+
+        // lock the resolver's token_out in the escrow, which has to receive all
+        // of it to pay it out again
+        let token_out_client = token::Client::new(&env, &token_out);
+        let locked = token_out_client.balance(&escrow_contract_address);
+        token_out_client.transfer(
             &executive_resolver,
-            &Self::get_relayer(env.clone()),
+            &escrow_contract_address,
+            &(amount_out as i128),
         );
+        if token_out_client.balance(&escrow_contract_address) - locked != amount_out as i128 {
+            panic_with_error!(&env, Error::EscrowUnderfunded);
+        }
 
         //transfer security deposit to the escrow contract
         Self::transfer_security_deposit(env.clone(), escrow_contract_address.clone());
@@ -128,12 +157,7 @@ impl EscrowFactory {
     }
 
     fn transfer_security_deposit(env: Env, escrow_contract_address: Address) {
-        let native_token_address = Address::from_string(&String::from_str(
-            &env,
-            "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-        ));
-
-        let native_token_client = token::Client::new(&env, &native_token_address);
+        let native_token_client = token::Client::new(&env, &Self::get_native_token(env.clone()));
         // Transfer security deposit from caller to this factory contract
         native_token_client.transfer(
             &env.current_contract_address(),
@@ -143,16 +167,11 @@ impl EscrowFactory {
     }
 
     fn check_security_deposit(env: Env, resolver: Address) {
-        let native_token_address = Address::from_string(&String::from_str(
-            &env,
-            "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-        ));
-
-        let native_token_client = token::Client::new(&env, &native_token_address);
+        let native_token_client = token::Client::new(&env, &Self::get_native_token(env.clone()));
         // Check if caller has sufficient balance for security deposit
         let relayer_balance = native_token_client.balance(&resolver);
         if relayer_balance < (SECURITY_DEPOSIT as i128) {
-            panic!("Insufficient balance for security deposit");
+            panic_with_error!(&env, Error::InsufficientSecurityDeposit);
         }
 
         // Transfer security deposit from caller to this factory contract first
@@ -170,8 +189,9 @@ impl EscrowFactory {
             .get(&DataKey::DutchAuction)
             .unwrap();
         if caller != dutch_auction {
-            panic!("Only Dutch Auction can call this function");
+            panic_with_error!(&env, Error::NotDutchAuction);
         }
+        caller.require_auth();
     }
 
     fn set_orderid_and_escrow(env: Env, order_id: BytesN<32>, escrow_address: Address) {
@@ -183,29 +203,57 @@ impl EscrowFactory {
             .set(&DataKey::EscrowToOrderId(escrow_address.clone()), &order_id);
     }
 
+    /// Address the escrow of `order_id` is (or will be) deployed at, which
+    /// makers sign as the recipient of a `permit_transfer_from`.
+    pub fn get_escrow_address(env: Env, order_id: BytesN<32>) -> Address {
+        env.deployer()
+            .with_current_contract(order_id)
+            .deployed_address()
+    }
+
     pub fn get_orderid_from_escrow(env: Env, escrow_address: Address) -> BytesN<32> {
         env.storage()
             .persistent()
             .get::<DataKey, BytesN<32>>(&DataKey::EscrowToOrderId(escrow_address))
-            .unwrap_or_else(|| {
-                panic!("Escrow address not found");
-            })
+            .unwrap_or_else(|| panic_with_error!(&env, Error::EscrowNotFound))
     }
     pub fn get_escrow_from_orderid(env: Env, order_id: BytesN<32>) -> Address {
         env.storage()
             .persistent()
             .get::<DataKey, Address>(&DataKey::OrderIdToEscrow(order_id))
-            .unwrap_or_else(|| {
-                panic!("Order ID not found");
-            })
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound))
     }
 
     pub fn get_relayer(env: Env) -> Address {
         env.storage()
             .instance()
             .get::<DataKey, Address>(&DataKey::Relayer)
-            .unwrap_or_else(|| {
-                panic!("Relayer not set");
-            })
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    /// Native tokens the executive resolver locks in every escrow it deploys.
+    pub fn get_security_deposit(_env: Env) -> u128 {
+        SECURITY_DEPOSIT
+    }
+
+    pub fn get_native_token(env: Env) -> Address {
+        env.storage()
+            .instance()
+            .get::<DataKey, Address>(&DataKey::NativeToken)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    pub fn get_escrow_src_wasm_hash(env: Env) -> BytesN<32> {
+        env.storage()
+            .instance()
+            .get::<DataKey, BytesN<32>>(&DataKey::EscrowSrcWasmHash)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    pub fn get_escrow_dest_wasm_hash(env: Env) -> BytesN<32> {
+        env.storage()
+            .instance()
+            .get::<DataKey, BytesN<32>>(&DataKey::EscrowDestWasmHash)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
     }
 }
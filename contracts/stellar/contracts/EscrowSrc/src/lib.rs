@@ -1,10 +1,12 @@
 #![no_std]
+use fusionx_types::interfaces::RelayerClient;
+use fusionx_types::{
+    Error, EscrowCancelled, EscrowWithdrawal, Immutables, Settlement, SettlementKind,
+};
 use soroban_sdk::{
-    contract, contractimpl, contracttype, crypto::Hash, token, Address, Bytes, BytesN, Env, String,
+    contract, contractimpl, contracttype, panic_with_error, symbol_short, token, Address, Bytes,
+    BytesN, Env,
 };
-mod relayer {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\relayer.wasm");
-}
 
 #[contract]
 pub struct EscrowSrc;
@@ -20,6 +22,9 @@ pub enum DataKey {
     TokenIn,           // Address of the token to be released
     AmountIn,          // Amount of tokens to be released
     Maker,             // Address of the maker
+    NativeToken,       // Address of the security deposit token
+    Settlement,        // How and when the escrow was settled
+    Factory,           // Factory that deployed the escrow
 }
 
 const SRC_FINALITY_LOCK: u128 = 2 * 60;
@@ -29,43 +34,51 @@ const SRC_RESOLVER_CANCEL: u128 = SRC_ANYONE_UNLOCK_PERIOD + 2 * 60;
 
 #[contractimpl]
 impl EscrowSrc {
-    pub fn initialize(
-        env: Env,
-        order_id: BytesN<32>,
-        hash_lock: BytesN<32>,
-        token_in: Address,
-        amount_in: u128,
-        maker: Address,
-        executive_resolver: Address,
-        relayer: Address,
-    ) {
+    pub fn __constructor(env: Env, factory: Address) {
+        env.storage().persistent().set(&DataKey::Factory, &factory);
+    }
+
+    /// Stores the immutables once, only the deploying factory can call it.
+    pub fn initialize(env: Env, immutables: Immutables) {
+        Self::only_factory_once(env.clone());
         let deployed_at: u128 = env.ledger().timestamp().into();
         env.storage()
             .persistent()
             .set(&DataKey::DeployedAt, &deployed_at);
         env.storage()
             .persistent()
-            .set(&DataKey::HashLock, &hash_lock);
-        env.storage().persistent().set(&DataKey::OrderId, &order_id);
-        env.storage().persistent().set(&DataKey::TokenIn, &token_in);
+            .set(&DataKey::HashLock, &immutables.hash_lock);
+        env.storage()
+            .persistent()
+            .set(&DataKey::OrderId, &immutables.order_id);
+        env.storage()
+            .persistent()
+            .set(&DataKey::TokenIn, &immutables.token);
 
         env.storage()
             .persistent()
-            .set(&DataKey::AmountIn, &amount_in);
-        env.storage().persistent().set(&DataKey::Maker, &maker);
+            .set(&DataKey::AmountIn, &immutables.amount);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Maker, &immutables.maker);
         env.storage()
             .persistent()
-            .set(&DataKey::ExecutiveResolver, &executive_resolver);
+            .set(&DataKey::ExecutiveResolver, &immutables.executive_resolver);
 
-        env.storage().persistent().set(&DataKey::Relayer, &relayer);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Relayer, &immutables.relayer);
+        env.storage()
+            .persistent()
+            .set(&DataKey::NativeToken, &immutables.native_token);
     }
 
     pub fn withdraw(env: Env, secret: BytesN<32>, caller: Address) {
         caller.require_auth();
-        Self::onlyAfter(env.clone(), SRC_FINALITY_LOCK);
-        Self::onlyBefore(env.clone(), SRC_RESOLVER_UNLOCK_PERIOD);
-        Self::validateSecret(env.clone(), secret);
-        Self::onlyExecutiveResolver(env.clone(), caller.clone());
+        Self::only_after(env.clone(), SRC_FINALITY_LOCK);
+        Self::only_before(env.clone(), SRC_RESOLVER_UNLOCK_PERIOD);
+        Self::validate_secret(env.clone(), secret.clone());
+        Self::only_executive_resolver(env.clone(), caller.clone());
         Self::withdraw_token(
             env.clone(),
             Self::get_token_in(env.clone()),
@@ -73,12 +86,14 @@ impl EscrowSrc {
             Self::get_amount_in(env.clone()),
         );
         Self::transfer_security_deposit(env.clone(), caller.clone());
+        Self::record_settlement(env.clone(), SettlementKind::Withdrawn, caller.clone());
+        Self::publish_withdrawal(env.clone(), secret, caller);
     }
 
     pub fn public_withdraw(env: Env, secret: BytesN<32>, caller: Address) {
-        Self::onlyAfter(env.clone(), SRC_RESOLVER_UNLOCK_PERIOD);
-        Self::onlyBefore(env.clone(), SRC_ANYONE_UNLOCK_PERIOD);
-        Self::validateSecret(env.clone(), secret);
+        Self::only_after(env.clone(), SRC_RESOLVER_UNLOCK_PERIOD);
+        Self::only_before(env.clone(), SRC_ANYONE_UNLOCK_PERIOD);
+        Self::validate_secret(env.clone(), secret.clone());
         Self::only_resolver(env.clone(), caller.clone());
         Self::withdraw_token(
             env.clone(),
@@ -87,12 +102,18 @@ impl EscrowSrc {
             Self::get_amount_in(env.clone()),
         );
         Self::transfer_security_deposit(env.clone(), caller.clone());
+        Self::record_settlement(env.clone(), SettlementKind::PublicWithdrawn, caller);
+        Self::publish_withdrawal(
+            env.clone(),
+            secret,
+            Self::get_executive_resolver(env.clone()),
+        );
     }
 
     pub fn cancel(env: Env, caller: Address) {
-        Self::onlyAfter(env.clone(), SRC_ANYONE_UNLOCK_PERIOD);
-        Self::onlyBefore(env.clone(), SRC_RESOLVER_CANCEL);
-        Self::onlyExecutiveResolver(env.clone(), caller.clone());
+        Self::only_after(env.clone(), SRC_ANYONE_UNLOCK_PERIOD);
+        Self::only_before(env.clone(), SRC_RESOLVER_CANCEL);
+        Self::only_executive_resolver(env.clone(), caller.clone());
 
         Self::withdraw_token(
             env.clone(),
@@ -101,9 +122,11 @@ impl EscrowSrc {
             Self::get_amount_in(env.clone()),
         );
         Self::transfer_security_deposit(env.clone(), caller.clone());
+        Self::record_settlement(env.clone(), SettlementKind::Cancelled, caller);
+        Self::publish_cancel(env.clone(), Self::get_maker(env.clone()));
     }
     pub fn public_cancel(env: Env, caller: Address) {
-        Self::onlyAfter(env.clone(), SRC_RESOLVER_CANCEL);
+        Self::only_after(env.clone(), SRC_RESOLVER_CANCEL);
         Self::only_resolver(env.clone(), caller.clone());
         Self::withdraw_token(
             env.clone(),
@@ -112,19 +135,37 @@ impl EscrowSrc {
             Self::get_amount_in(env.clone()),
         );
         Self::transfer_security_deposit(env.clone(), caller.clone());
+        Self::record_settlement(env.clone(), SettlementKind::PublicCancelled, caller);
+        Self::publish_cancel(env.clone(), Self::get_maker(env.clone()));
     }
 
     // internal functions
+    // written once, it's what the relayer slashes over
+    fn record_settlement(env: Env, kind: SettlementKind, caller: Address) {
+        if env.storage().persistent().has(&DataKey::Settlement) {
+            panic_with_error!(&env, Error::AlreadySettled);
+        }
+        let settlement = Settlement {
+            kind,
+            at: env.ledger().timestamp(),
+            caller,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Settlement, &settlement);
+    }
+
     fn withdraw_token(env: Env, token: Address, to: Address, amount: u128) {
         let token_client = token::Client::new(&env, &token);
         token_client.transfer(&env.current_contract_address(), &to, &(amount as i128));
     }
 
     fn transfer_security_deposit(env: Env, to: Address) {
-        let native_token_contract_id: Address = Address::from_string(&String::from_str(
-            &env,
-            "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-        ));
+        let native_token_contract_id: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::NativeToken)
+            .unwrap();
 
         let token_client = token::Client::new(&env, &native_token_contract_id);
 
@@ -146,37 +187,68 @@ impl EscrowSrc {
     fn get_amount_in(env: Env) -> u128 {
         env.storage().persistent().get(&DataKey::AmountIn).unwrap()
     }
-    fn get_maker(env: Env) -> Address {
-        env.storage().persistent().get(&DataKey::Maker).unwrap()
+
+    fn publish_withdrawal(env: Env, secret: BytesN<32>, to: Address) {
+        let event_data = EscrowWithdrawal {
+            order_id: Self::get_order_id(env.clone()),
+            secret,
+            to,
+            amount: Self::get_amount_in(env.clone()),
+        };
+        env.events().publish(
+            (symbol_short!("escrow"), symbol_short!("withdraw")),
+            event_data,
+        );
     }
 
-    fn get_executive_resolver(env: Env) -> Address {
-        env.storage()
-            .persistent()
-            .get(&DataKey::ExecutiveResolver)
-            .unwrap()
+    fn publish_cancel(env: Env, to: Address) {
+        let event_data = EscrowCancelled {
+            order_id: Self::get_order_id(env.clone()),
+            to,
+            amount: Self::get_amount_in(env.clone()),
+        };
+        env.events().publish(
+            (symbol_short!("escrow"), symbol_short!("cancel")),
+            event_data,
+        );
     }
 
-    fn validateSecret(env: Env, secret: BytesN<32>) {
+    fn get_order_id(env: Env) -> BytesN<32> {
+        env.storage().persistent().get(&DataKey::OrderId).unwrap()
+    }
+
+    fn validate_secret(env: Env, secret: BytesN<32>) {
         let hash_lock: BytesN<32> = env.storage().persistent().get(&DataKey::HashLock).unwrap();
         let _secret: Bytes = secret.into();
         let secret_bytes: BytesN<32> = env.crypto().keccak256(&_secret).into();
         if hash_lock != secret_bytes {
-            panic!("Invalid secret");
+            panic_with_error!(&env, Error::InvalidSecret);
         }
     }
 
-    fn onlyExecutiveResolver(env: Env, caller: Address) {
+    fn only_executive_resolver(env: Env, caller: Address) {
         let executive_resolver: Address = env
             .storage()
             .persistent()
             .get(&DataKey::ExecutiveResolver)
             .unwrap();
         if caller != executive_resolver {
-            panic!("Unauthorized caller");
+            panic_with_error!(&env, Error::UnauthorizedCaller);
+        }
+    }
+    fn only_factory_once(env: Env) {
+        if env.storage().persistent().has(&DataKey::HashLock) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
         }
+        let factory: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Factory)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized));
+        factory.require_auth();
     }
-    fn onlyAfter(env: Env, start: u128) {
+
+    fn only_after(env: Env, start: u128) {
         let deployed_at: u128 = env
             .storage()
             .persistent()
@@ -184,10 +256,10 @@ impl EscrowSrc {
             .unwrap_or(0);
         let block_timestamp: u128 = env.ledger().timestamp().into();
         if block_timestamp < deployed_at + start {
-            panic!("Invalid time");
+            panic_with_error!(&env, Error::InvalidTime);
         }
     }
-    fn onlyBefore(env: Env, stop: u128) {
+    fn only_before(env: Env, stop: u128) {
         let deployed_at: u128 = env
             .storage()
             .persistent()
@@ -195,15 +267,15 @@ impl EscrowSrc {
             .unwrap_or(0);
         let block_timestamp: u128 = env.ledger().timestamp().into();
         if block_timestamp >= deployed_at + stop {
-            panic!("Invalid time");
+            panic_with_error!(&env, Error::InvalidTime);
         }
     }
 
     fn only_resolver(env: Env, caller: Address) {
-        let relayer = relayer::Client::new(&env.clone(), &Self::get_relayer(env));
+        let relayer = RelayerClient::new(&env, &Self::get_relayer(env.clone()));
         let value = relayer.is_resolver(&caller);
         if !value {
-            panic!("Only resolvers can call this function");
+            panic_with_error!(&env, Error::NotResolver);
         }
     }
 
@@ -211,4 +283,20 @@ impl EscrowSrc {
     pub fn get_relayer(env: Env) -> Address {
         env.storage().persistent().get(&DataKey::Relayer).unwrap()
     }
+
+    pub fn get_executive_resolver(env: Env) -> Address {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ExecutiveResolver)
+            .unwrap()
+    }
+
+    pub fn get_maker(env: Env) -> Address {
+        env.storage().persistent().get(&DataKey::Maker).unwrap()
+    }
+
+    /// How the escrow was settled, `None` while its tokens are locked.
+    pub fn get_settlement(env: Env) -> Option<Settlement> {
+        env.storage().persistent().get(&DataKey::Settlement)
+    }
 }
@@ -0,0 +1,54 @@
+use soroban_sdk::contracterror;
+
+/// Error codes shared by all FusionX contracts.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    OrderNotFound = 2,
+    AuctionNotStarted = 3,
+    NotResolver = 4,     // caller is not a whitelisted resolver
+    NotDutchAuction = 5, // caller is not the dutch auction contract
+    InsufficientSecurityDeposit = 6,
+    EscrowNotFound = 7,
+    InvalidSecret = 8,      // secret does not match the hash lock
+    UnauthorizedCaller = 9, // caller is not the executive resolver
+    InvalidTime = 10,       // called outside of the allowed timelock window
+    NonceUsed = 11,         // permit nonce already used or invalidated
+    InvalidAmount = 12,
+    InsufficientBalance = 13,
+    InsufficientAllowance = 14,
+    TokenNotRegistered = 15, // token has no decimals in the relayer registry
+    InvalidDecimals = 16,
+    PermitExpired = 17,    // permit deadline has passed
+    InvalidPublicKey = 18, // permit key does not belong to the owner
+    InvalidExpirationLedger = 19,
+    WrappedAssetNotDeployed = 20,
+    PermitSignerNotSet = 21, // owner has no secp256k1 or secp256r1 permit signer
+    InvalidClientData = 22,  // webauthn client data is not a get over the permit digest
+    TrustlineMissing = 23,   // maker can't receive the classic asset, no authorized trustline
+    EscrowUnderfunded = 24,  // escrow received less than it pays out, e.g. a fee-on-transfer token
+    FaucetDisabled = 25,
+    FaucetCooldown = 26,   // address already got a drip within the cooldown
+    NotOperator = 27,      // caller is neither an operator nor the owner of the resolver
+    CallNotAllowed = 28,   // batched call to a contract function the owner did not allow
+    BelowReserve = 29,     // withdrawal would leave less native token than the reserve
+    SlippageExceeded = 30, // price moved past the caller's limit, on a fill or a swap
+    InvalidPath = 31,      // swap path needs at least two tokens
+    RouterNotSet = 32,
+    NotBorrower = 33,            // caller is not a resolver the vault lends to
+    UtilizationCapExceeded = 34, // loan would lend out more of the vault than allowed
+    InsufficientLiquidity = 35,  // not enough idle assets, the rest is lent out or queued
+    LoanNotFound = 36,
+    VaultNotSet = 37,
+    StakingNotConfigured = 38,
+    NoMisbehaviour = 39, // escrow is locked or was settled in its resolver's window
+    AlreadySlashed = 40, // stake was already slashed over this escrow
+    UntrustedFactory = 41, // escrow factory the relayer doesn't vouch for
+    AlreadyInitialized = 42,
+    VaultInsolvent = 43, // shares are left that no assets back
+    AlreadySettled = 44,
+    InvalidAuthenticatorData = 45, // webauthn assertion without the user present and verified
+    StakeOutstanding = 46,         // staking token can't change while stake is bonded or unbonding
+}
@@ -0,0 +1,49 @@
+use soroban_sdk::{contracttype, Address, BytesN};
+
+use crate::types::SettlementKind;
+
+/// Published by the relayer under `("signal", "secret")` once a resolver has
+/// deployed both escrows and the maker can share the secret.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SignalSecretShare {
+    pub escrow_src: BytesN<32>,
+    pub escrow_dest: BytesN<32>,
+    pub order_id: BytesN<32>,
+    pub resolver: Address,
+}
+
+/// Published by an escrow under `("escrow", "withdraw")` when the locked
+/// tokens are released with the secret.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EscrowWithdrawal {
+    pub order_id: BytesN<32>,
+    pub secret: BytesN<32>,
+    pub to: Address, // receiver of the locked tokens
+    pub amount: u128,
+}
+
+/// Published by an escrow under `("escrow", "cancel")` when the locked tokens
+/// are returned.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EscrowCancelled {
+    pub order_id: BytesN<32>,
+    pub to: Address, // receiver of the locked tokens
+    pub amount: u128,
+}
+
+/// Published by the relayer under `("stake", "slash")` when a resolver's
+/// stake is slashed over an escrow it left to a public path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StakeSlashed {
+    pub resolver: Address,
+    pub escrow: Address,
+    pub order_id: BytesN<32>,
+    pub kind: SettlementKind, // public path that had to step in
+    pub amount: i128,
+    pub maker: Address, // paid the slashed stake, unless it couldn't take it
+    pub paid: bool,
+}
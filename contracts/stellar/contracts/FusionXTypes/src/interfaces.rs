@@ -0,0 +1,271 @@
+//! Cross-contract interfaces. Each trait generates a `*Client` that callers
+//! use instead of importing the callee's wasm.
+#![allow(clippy::too_many_arguments)]
+
+use soroban_sdk::{contractclient, Address, BytesN, Env, Vec};
+
+use crate::{
+    Immutables, Order, OrderInput, PermitSigner, Settlement, TokenTransfer, WebAuthnSignature,
+};
+
+#[contractclient(name = "RelayerClient")]
+pub trait RelayerInterface {
+    fn is_resolver(env: Env, resolver: Address) -> bool;
+    fn move_tokens_to_escrow(
+        env: Env,
+        order_id: BytesN<32>,
+        maker: Address,
+        token: Address,
+        src_escrow: Address,
+        amount_in: u128,
+    );
+    fn signal_share_secret(
+        env: Env,
+        escrow_src: BytesN<32>,
+        escrow_dest: BytesN<32>,
+        order_id: BytesN<32>,
+        resolver: Address,
+    );
+    fn get_owner(env: Env) -> Address;
+    fn get_dutch_auction(env: Env) -> Address;
+    fn get_token_decimals(env: Env, token: Address) -> u32;
+    fn get_dest_token_decimals(env: Env, token: BytesN<32>) -> u32;
+    fn bond(env: Env, from: Address, resolver: Address, amount: i128);
+    fn unbond(env: Env, resolver: Address, amount: i128);
+    fn withdraw_unbonded(env: Env, resolver: Address) -> i128;
+}
+
+#[contractclient(name = "DutchAuctionClient")]
+pub trait DutchAuctionInterface {
+    fn start_auction(
+        env: Env,
+        order_input: OrderInput,
+        token_in_decimals: u32,
+        token_out_decimals: u32,
+    );
+    fn fillOrder(env: Env, caller: Address, order_id: BytesN<32>, max_amount_out: u128);
+    fn get_amount_out(env: Env, order_id: BytesN<32>) -> u128;
+    fn get_order(env: Env, order_id: BytesN<32>) -> Order;
+    fn get_normalized_amount_out(env: Env, order_id: BytesN<32>) -> u128;
+    fn get_price(env: Env, order_id: BytesN<32>) -> u128;
+    fn get_price_range(env: Env, order_id: BytesN<32>) -> (u128, u128);
+    fn get_relayer(env: Env) -> Address;
+    fn get_escrow_factory(env: Env) -> Address;
+}
+
+#[contractclient(name = "EscrowFactoryClient")]
+pub trait EscrowFactoryInterface {
+    fn deploy_src(
+        env: Env,
+        order_id: BytesN<32>,
+        hash_lock: BytesN<32>,
+        token_in: Address,
+        amount_in: u128,
+        maker: Address,
+        executive_resolver: Address,
+        caller: Address,
+    ) -> Address;
+    fn deploy_dest(
+        env: Env,
+        order_id: BytesN<32>,
+        hash_lock: BytesN<32>,
+        token_out: Address,
+        amount_out: u128,
+        maker: Address,
+        executive_resolver: Address,
+    ) -> Address;
+    fn get_escrow_address(env: Env, order_id: BytesN<32>) -> Address;
+    fn get_orderid_from_escrow(env: Env, escrow_address: Address) -> BytesN<32>;
+    fn get_escrow_from_orderid(env: Env, order_id: BytesN<32>) -> Address;
+    fn get_relayer(env: Env) -> Address;
+    fn get_native_token(env: Env) -> Address;
+    fn get_security_deposit(env: Env) -> u128;
+}
+
+#[contractclient(name = "EscrowSrcClient")]
+pub trait EscrowSrcInterface {
+    fn initialize(env: Env, immutables: Immutables);
+    fn withdraw(env: Env, secret: BytesN<32>, caller: Address);
+    fn public_withdraw(env: Env, secret: BytesN<32>, caller: Address);
+    fn cancel(env: Env, caller: Address);
+    fn public_cancel(env: Env, caller: Address);
+    fn get_relayer(env: Env) -> Address;
+    fn get_executive_resolver(env: Env) -> Address;
+    fn get_maker(env: Env) -> Address;
+    fn get_settlement(env: Env) -> Option<Settlement>;
+}
+
+#[contractclient(name = "EscrowDestClient")]
+pub trait EscrowDestInterface {
+    fn initialize(env: Env, immutables: Immutables);
+    fn withdraw(env: Env, secret: BytesN<32>, caller: Address);
+    fn public_withdraw(env: Env, secret: BytesN<32>, caller: Address);
+    fn cancel(env: Env, caller: Address);
+    fn get_relayer(env: Env) -> Address;
+    fn get_executive_resolver(env: Env) -> Address;
+    fn get_maker(env: Env) -> Address;
+    fn get_settlement(env: Env) -> Option<Settlement>;
+}
+
+#[contractclient(name = "WrappedTokensClient")]
+pub trait WrappedTokensInterface {
+    fn permit(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    );
+    fn domain_separator(env: Env) -> BytesN<32>;
+    fn permit_digest(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+    ) -> BytesN<32>;
+    fn permit_transfer_from(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        to: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    );
+    fn permit_batch_transfer_from(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        transfers: Vec<TokenTransfer>,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    );
+    #[allow(clippy::too_many_arguments)]
+    fn check_permit_transfer(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        transfers: Vec<TokenTransfer>,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    );
+    fn permit_transfer_digest(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        transfers: Vec<TokenTransfer>,
+        nonce: u64,
+        deadline: u64,
+    ) -> BytesN<32>;
+    fn permit_secp256k1(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        signature: BytesN<64>,
+        recovery_id: u32,
+    );
+    fn permit_secp256r1(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        signature: WebAuthnSignature,
+    );
+    fn evm_permit_digest(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+    ) -> BytesN<32>;
+    fn set_permit_signer(env: Env, owner: Address, signer: PermitSigner);
+    fn remove_permit_signer(env: Env, owner: Address);
+    fn get_permit_signer(env: Env, owner: Address) -> PermitSigner;
+    fn invalidate_nonce(env: Env, owner: Address, nonce: u64);
+    fn invalidate_unordered_nonces(env: Env, owner: Address, word_pos: u64, mask: u128);
+    fn nonce_bitmap(env: Env, owner: Address, word_pos: u64) -> u128;
+    fn is_nonce_used(env: Env, owner: Address, nonce: u64) -> bool;
+    fn deposit(env: Env, token: Address, amount: u128, caller: Address);
+    fn withdraw(env: Env, token: Address, amount: u128, caller: Address);
+    fn approve(env: Env, token: Address, amount: u128, to: Address, caller: Address);
+    fn transfer(env: Env, token: Address, amount: u128, to: Address, caller: Address);
+    fn transfer_from(
+        env: Env,
+        token: Address,
+        amount: u128,
+        from: Address,
+        to: Address,
+        caller: Address,
+    );
+    fn get_allowance(env: Env, token: Address, owner: Address, spender: Address) -> u128;
+    fn get_balance(env: Env, token: Address, user: Address) -> u128;
+    fn deploy_wrapped_asset(env: Env, token: Address) -> Address;
+    fn get_wrapped_asset(env: Env, token: Address) -> Address;
+    fn asset_approve(
+        env: Env,
+        token: Address,
+        from: Address,
+        spender: Address,
+        amount: u128,
+        expiration_ledger: u32,
+    );
+    fn asset_transfer(env: Env, token: Address, from: Address, to: Address, amount: u128);
+    fn asset_transfer_from(
+        env: Env,
+        token: Address,
+        spender: Address,
+        from: Address,
+        to: Address,
+        amount: u128,
+    );
+    fn asset_burn(env: Env, token: Address, from: Address, amount: u128);
+    fn asset_burn_from(env: Env, token: Address, spender: Address, from: Address, amount: u128);
+}
+
+/// The subset of a Soroswap router the Resolver swaps through. `to` pays
+/// `amount_in` to the first pair of `path` and receives the output.
+#[contractclient(name = "AmmRouterClient")]
+pub trait AmmRouterInterface {
+    fn swap_exact_tokens_for_tokens(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        deadline: u64,
+    ) -> Vec<i128>;
+    fn router_pair_for(env: Env, token_a: Address, token_b: Address) -> Address;
+}
+
+/// The borrower side of the LiquidityVault, used by the Resolver.
+#[contractclient(name = "LiquidityVaultClient")]
+pub trait LiquidityVaultInterface {
+    fn get_asset(env: Env) -> Address;
+    fn borrow(env: Env, borrower: Address, order_id: BytesN<32>, amount: i128);
+    fn owed(env: Env, borrower: Address, order_id: BytesN<32>) -> i128;
+    fn repay(env: Env, borrower: Address, order_id: BytesN<32>) -> i128;
+    fn get_loan(env: Env, borrower: Address, order_id: BytesN<32>) -> i128;
+}
@@ -0,0 +1,19 @@
+#![no_std]
+#![allow(non_snake_case)]
+
+//! Types and contract interfaces shared by the FusionX Stellar contracts.
+//!
+//! Contracts call each other through the clients generated from the traits in
+//! [`interfaces`], so the workspace builds without any prebuilt wasm.
+
+mod errors;
+mod events;
+pub mod interfaces;
+mod types;
+
+pub use errors::Error;
+pub use events::{EscrowCancelled, EscrowWithdrawal, SignalSecretShare, StakeSlashed};
+pub use types::{
+    Immutables, Order, OrderInput, PermitSigner, Settlement, SettlementKind, TokenTransfer,
+    WebAuthnSignature, MAX_DECIMALS,
+};
@@ -0,0 +1,97 @@
+use soroban_sdk::{contracttype, Address, Bytes, BytesN};
+
+/// Largest decimals a token can have, `10^38` is the largest power of ten
+/// that fits in a `u128`.
+pub const MAX_DECIMALS: u32 = 38;
+
+/// Order as submitted by the relayer to start a dutch auction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OrderInput {
+    pub orderId: BytesN<32>,
+    pub maker: Address,
+    pub tokenIn: Address,
+    pub tokenOut: BytesN<32>, // token on the destination chain
+    pub amountIn: u128,
+    pub minAmountOut: u128,
+    pub maxAmountOut: u128,
+    pub hashLock: BytesN<32>,
+}
+
+/// Order as stored by the dutch auction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Order {
+    pub maker: Address,
+    pub tokenIn: Address,
+    pub tokenOut: BytesN<32>,
+    pub tokenInDecimals: u32,
+    pub tokenOutDecimals: u32,
+    pub amountIn: u128,
+    pub amountOut: u128, // set when the order is filled
+    pub minAmountOut: u128,
+    pub maxAmountOut: u128,
+    pub resolverAssigned: Option<Address>, // set when the order is filled
+    pub startTime: u128,
+    pub hashLock: BytesN<32>,
+}
+
+/// Parameters an escrow is initialized with by the escrow factory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Immutables {
+    pub order_id: BytesN<32>,
+    pub hash_lock: BytesN<32>,       // keccak256 of the secret
+    pub token: Address,              // token locked in the escrow
+    pub amount: u128,                // amount of `token` locked in the escrow
+    pub maker: Address,              // maker of the order
+    pub executive_resolver: Address, // resolver that filled the order
+    pub relayer: Address,            // relayer contract, used for resolver checks
+    pub native_token: Address,       // token the security deposit is paid in
+}
+
+/// Which path paid out an escrow. The public ones mean its executive resolver
+/// missed its exclusive window.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum SettlementKind {
+    Withdrawn,
+    PublicWithdrawn,
+    Cancelled,
+    PublicCancelled,
+}
+
+/// How and when an escrow was settled, kept by the escrow.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Settlement {
+    pub kind: SettlementKind,
+    pub at: u64,
+    pub caller: Address,
+}
+
+/// One leg of a signed transfer, `amount` of the underlying `token` to `to`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TokenTransfer {
+    pub token: Address,
+    pub to: Address,
+    pub amount: u128,
+}
+
+/// Non-Stellar key an owner lets sign permits on its behalf.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PermitSigner {
+    Secp256k1(BytesN<20>), // EVM address
+    Secp256r1(BytesN<65>), // SEC-1 uncompressed P-256 public key of a passkey
+}
+
+/// WebAuthn assertion of a passkey over a permit digest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WebAuthnSignature {
+    pub authenticator_data: Bytes,
+    pub client_data_json: Bytes, // challenge is the base64url permit digest
+    pub signature: BytesN<64>,   // r || s, with s in the lower half of the order
+}
@@ -0,0 +1,443 @@
+#![no_std]
+
+//! Fill capital pooled from liquidity providers. LPs deposit the vault's
+//! asset for shares, approved Resolver contracts borrow it per order to fund
+//! destination escrows and repay once the source side paid them. A loan is
+//! repaid with a fixed fee on its principal, which raises the value of a
+//! share. What a fill earned or lost beyond that is the operator's, the vault
+//! doesn't take the borrower's word for it. A loan not repaid by its due time
+//! can be written off, the LPs bear the loss and the borrower is cut off.
+
+use fusionx_types::Error;
+use soroban_sdk::{
+    contract, contractimpl, contracttype, panic_with_error, token, Address, BytesN, Env, Vec,
+};
+
+const BPS: i128 = 10_000;
+
+/// Withdrawal requests per page of the queue.
+pub const PAGE_SIZE: u32 = 100;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum DataKey {
+    Owner,                     // owner address
+    Asset,                     // token lent out and held for LPs
+    Fee,                       // bps of the principal a loan is repaid with on top
+    MaxUtilization,            // bps of the vault's assets that can be lent out
+    LoanDuration,              // seconds from a loan's first borrow to its due time
+    Borrower(Address),         // resolver contract -> bool allowed to borrow
+    Shares(Address),           // lp -> shares
+    TotalShares,               // shares of all LPs, queued ones included
+    Idle,                      // assets held and not lent out
+    Borrowed,                  // principal of all open loans
+    Loan(Address, BytesN<32>), // (borrower, order_id) -> Loan
+    QueuePage(u32),            // page -> queued withdrawals from page * PAGE_SIZE on
+    QueueHead,                 // index of the oldest unpaid withdrawal
+    QueueTail,                 // index the next queued withdrawal gets
+    QueuedShares,              // shares of all queued withdrawals
+}
+
+/// Shares an LP asked to redeem while the vault was lent out. They keep
+/// earning and losing with the vault until they are paid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WithdrawalRequest {
+    pub lp: Address,
+    pub shares: i128,
+}
+
+/// A loan open for an order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Loan {
+    pub principal: i128,
+    pub due: u64, // written off if still open after this
+}
+
+#[contract]
+pub struct LiquidityVault;
+
+#[contractimpl]
+impl LiquidityVault {
+    pub fn initialize(
+        env: Env,
+        owner: Address,
+        asset: Address,
+        fee_bps: u32,
+        max_utilization_bps: u32,
+        loan_duration: u64,
+    ) {
+        env.storage().persistent().set(&DataKey::Owner, &owner);
+        env.storage().persistent().set(&DataKey::Asset, &asset);
+        Self::write_bps(&env, DataKey::Fee, fee_bps);
+        Self::write_bps(&env, DataKey::MaxUtilization, max_utilization_bps);
+        env.storage()
+            .persistent()
+            .set(&DataKey::LoanDuration, &loan_duration);
+    }
+
+    // Liquidity providers
+
+    /// Pays `amount` of the asset in for shares at the current share value.
+    pub fn deposit(env: Env, lp: Address, amount: i128) -> i128 {
+        lp.require_auth();
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        let total_assets = Self::total_assets(env.clone());
+        let total_shares = Self::total_shares(env.clone());
+        let shares = if total_shares == 0 {
+            amount
+        } else if total_assets == 0 {
+            // the new assets would back the old shares too
+            panic_with_error!(&env, Error::VaultInsolvent)
+        } else {
+            amount * total_shares / total_assets
+        };
+        if shares == 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+
+        let this = env.current_contract_address();
+        token::Client::new(&env, &Self::get_asset(env.clone())).transfer(&lp, &this, &amount);
+        Self::add(&env, DataKey::Idle, amount);
+        Self::add(&env, DataKey::Shares(lp), shares);
+        Self::add(&env, DataKey::TotalShares, shares);
+        shares
+    }
+
+    /// Redeems `shares` right away, out of what is neither lent out nor owed
+    /// to queued withdrawals. Returns the assets paid.
+    pub fn withdraw(env: Env, lp: Address, shares: i128) -> i128 {
+        lp.require_auth();
+        Self::take_shares(&env, &lp, shares);
+        let amount = Self::share_value(env.clone(), shares);
+        if amount > Self::available(env.clone()) {
+            panic_with_error!(&env, Error::InsufficientLiquidity);
+        }
+        Self::burn(&env, &lp, shares, amount);
+        amount
+    }
+
+    /// Queues `shares` for redemption once loans are repaid, for when
+    /// `withdraw` can't pay them now.
+    pub fn queue_withdrawal(env: Env, lp: Address, shares: i128) {
+        lp.require_auth();
+        Self::take_shares(&env, &lp, shares);
+        let tail = Self::queue_tail(env.clone());
+        let page = tail / PAGE_SIZE;
+        let mut requests = Self::get_queue(env.clone(), page);
+        requests.push_back(WithdrawalRequest { lp, shares });
+        env.storage()
+            .persistent()
+            .set(&DataKey::QueuePage(page), &requests);
+        env.storage()
+            .persistent()
+            .set(&DataKey::QueueTail, &(tail + 1));
+        Self::add(&env, DataKey::QueuedShares, shares);
+    }
+
+    /// Pays up to `max` queued withdrawals in order, stopping at the first
+    /// the idle assets don't cover. Anyone can call it, returns how many
+    /// were paid.
+    pub fn process_queue(env: Env, max: u32) -> u32 {
+        let mut head = Self::queue_head(env.clone());
+        let tail = Self::queue_tail(env.clone());
+        let mut paid = 0;
+        while paid < max && head < tail {
+            let request =
+                Self::get_queue(env.clone(), head / PAGE_SIZE).get_unchecked(head % PAGE_SIZE);
+            let amount = Self::share_value(env.clone(), request.shares);
+            if amount > Self::read(&env, DataKey::Idle) {
+                break;
+            }
+            Self::add(&env, DataKey::QueuedShares, -request.shares);
+            Self::burn(&env, &request.lp, request.shares, amount);
+            head += 1;
+            paid += 1;
+            // a page is dropped once all of it is paid
+            if head.is_multiple_of(PAGE_SIZE) {
+                env.storage()
+                    .persistent()
+                    .remove(&DataKey::QueuePage(head / PAGE_SIZE - 1));
+            }
+        }
+        env.storage().persistent().set(&DataKey::QueueHead, &head);
+        paid
+    }
+
+    // Borrowers
+
+    /// Lends `amount` to `borrower` for `order_id`, adding to an open loan
+    /// for the same order. The loan is due `get_loan_duration` after it was
+    /// first taken.
+    pub fn borrow(env: Env, borrower: Address, order_id: BytesN<32>, amount: i128) {
+        borrower.require_auth();
+        if !Self::is_borrower(env.clone(), borrower.clone()) {
+            panic_with_error!(&env, Error::NotBorrower);
+        }
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if amount > Self::available(env.clone()) {
+            panic_with_error!(&env, Error::InsufficientLiquidity);
+        }
+        let borrowed = Self::read(&env, DataKey::Borrowed) + amount;
+        let cap = Self::get_max_utilization(env.clone()) as i128;
+        if borrowed * BPS > cap * Self::total_assets(env.clone()) {
+            panic_with_error!(&env, Error::UtilizationCapExceeded);
+        }
+
+        let key = DataKey::Loan(borrower.clone(), order_id);
+        let mut loan = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| Loan {
+                principal: 0,
+                due: env.ledger().timestamp() + Self::get_loan_duration(env.clone()),
+            });
+        loan.principal += amount;
+        env.storage().persistent().set(&key, &loan);
+        Self::add(&env, DataKey::Borrowed, amount);
+        Self::add(&env, DataKey::Idle, -amount);
+        let this = env.current_contract_address();
+        token::Client::new(&env, &Self::get_asset(env.clone())).transfer(&this, &borrower, &amount);
+    }
+
+    /// What the borrower pays back for `order_id`, the principal and the fee
+    /// on it.
+    pub fn owed(env: Env, borrower: Address, order_id: BytesN<32>) -> i128 {
+        let principal = Self::get_loan(env.clone(), borrower, order_id);
+        principal + principal * Self::get_fee(env) as i128 / BPS
+    }
+
+    /// Closes the loan for `order_id` by paying what is `owed`. Returns the
+    /// amount paid.
+    pub fn repay(env: Env, borrower: Address, order_id: BytesN<32>) -> i128 {
+        borrower.require_auth();
+        let principal = Self::get_loan(env.clone(), borrower.clone(), order_id.clone());
+        let amount = Self::owed(env.clone(), borrower.clone(), order_id.clone());
+
+        let this = env.current_contract_address();
+        token::Client::new(&env, &Self::get_asset(env.clone())).transfer(&borrower, &this, &amount);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Loan(borrower, order_id));
+        Self::add(&env, DataKey::Borrowed, -principal);
+        Self::add(&env, DataKey::Idle, amount);
+        amount
+    }
+
+    /// Writes off the loan for `order_id` once it is past due. Its principal
+    /// is taken off the vault's assets, lowering the value of a share, and
+    /// `borrower` can't borrow again until the owner allows it. Anyone can
+    /// call it, returns the principal written off.
+    pub fn write_off(env: Env, borrower: Address, order_id: BytesN<32>) -> i128 {
+        let loan = Self::read_loan(&env, &borrower, &order_id);
+        if env.ledger().timestamp() <= loan.due {
+            panic_with_error!(&env, Error::InvalidTime);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Loan(borrower.clone(), order_id));
+        Self::add(&env, DataKey::Borrowed, -loan.principal);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Borrower(borrower), &false);
+        loan.principal
+    }
+
+    // Owner
+
+    pub fn set_borrower(env: Env, borrower: Address, allowed: bool) {
+        Self::only_owner(&env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Borrower(borrower), &allowed);
+    }
+
+    /// Fee of loans repaid from now on, open ones included.
+    pub fn set_fee(env: Env, bps: u32) {
+        Self::only_owner(&env);
+        Self::write_bps(&env, DataKey::Fee, bps);
+    }
+
+    pub fn set_max_utilization(env: Env, bps: u32) {
+        Self::only_owner(&env);
+        Self::write_bps(&env, DataKey::MaxUtilization, bps);
+    }
+
+    /// Duration of loans taken from now on, open ones keep their due time.
+    pub fn set_loan_duration(env: Env, seconds: u64) {
+        Self::only_owner(&env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::LoanDuration, &seconds);
+    }
+
+    pub fn transfer_ownership(env: Env, new_owner: Address) {
+        Self::only_owner(&env);
+        env.storage().persistent().set(&DataKey::Owner, &new_owner);
+    }
+
+    // Views
+
+    pub fn get_owner(env: Env) -> Address {
+        env.storage().persistent().get(&DataKey::Owner).unwrap()
+    }
+
+    pub fn get_asset(env: Env) -> Address {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Asset)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    pub fn get_fee(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::Fee).unwrap_or(0)
+    }
+
+    pub fn get_max_utilization(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MaxUtilization)
+            .unwrap_or(0)
+    }
+
+    pub fn get_loan_duration(env: Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::LoanDuration)
+            .unwrap_or(0)
+    }
+
+    pub fn is_borrower(env: Env, borrower: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Borrower(borrower))
+            .unwrap_or(false)
+    }
+
+    /// Principal of the open loan for `order_id`.
+    pub fn get_loan(env: Env, borrower: Address, order_id: BytesN<32>) -> i128 {
+        Self::read_loan(&env, &borrower, &order_id).principal
+    }
+
+    /// When the open loan for `order_id` can be written off.
+    pub fn get_loan_due(env: Env, borrower: Address, order_id: BytesN<32>) -> u64 {
+        Self::read_loan(&env, &borrower, &order_id).due
+    }
+
+    pub fn shares_of(env: Env, lp: Address) -> i128 {
+        Self::read(&env, DataKey::Shares(lp))
+    }
+
+    pub fn total_shares(env: Env) -> i128 {
+        Self::read(&env, DataKey::TotalShares)
+    }
+
+    /// Idle assets and the principal lent out.
+    pub fn total_assets(env: Env) -> i128 {
+        Self::read(&env, DataKey::Idle) + Self::read(&env, DataKey::Borrowed)
+    }
+
+    pub fn borrowed(env: Env) -> i128 {
+        Self::read(&env, DataKey::Borrowed)
+    }
+
+    /// Assets `shares` redeem for now.
+    pub fn share_value(env: Env, shares: i128) -> i128 {
+        let total_shares = Self::total_shares(env.clone());
+        if total_shares == 0 {
+            return 0;
+        }
+        shares * Self::total_assets(env) / total_shares
+    }
+
+    /// Idle assets not owed to queued withdrawals, what can be lent or
+    /// withdrawn right away.
+    pub fn available(env: Env) -> i128 {
+        let queued = Self::share_value(env.clone(), Self::read(&env, DataKey::QueuedShares));
+        (Self::read(&env, DataKey::Idle) - queued).max(0)
+    }
+
+    /// Bps of the vault's assets lent out.
+    pub fn utilization(env: Env) -> u32 {
+        let total_assets = Self::total_assets(env.clone());
+        if total_assets == 0 {
+            return 0;
+        }
+        (Self::borrowed(env) * BPS / total_assets) as u32
+    }
+
+    /// Queued withdrawals from `page * PAGE_SIZE` on, in the order they are
+    /// paid. The ones before `queue_head` are paid already, pages paid in
+    /// full are empty.
+    pub fn get_queue(env: Env, page: u32) -> Vec<WithdrawalRequest> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::QueuePage(page))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    pub fn queue_head(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::QueueHead)
+            .unwrap_or(0)
+    }
+
+    pub fn queue_tail(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::QueueTail)
+            .unwrap_or(0)
+    }
+
+    fn only_owner(env: &Env) {
+        Self::get_owner(env.clone()).require_auth();
+    }
+
+    fn read_loan(env: &Env, borrower: &Address, order_id: &BytesN<32>) -> Loan {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Loan(borrower.clone(), order_id.clone()))
+            .unwrap_or_else(|| panic_with_error!(env, Error::LoanNotFound))
+    }
+
+    fn write_bps(env: &Env, key: DataKey, bps: u32) {
+        if bps as i128 > BPS {
+            panic_with_error!(env, Error::InvalidAmount);
+        }
+        env.storage().persistent().set(&key, &bps);
+    }
+
+    fn take_shares(env: &Env, lp: &Address, shares: i128) {
+        if shares <= 0 {
+            panic_with_error!(env, Error::InvalidAmount);
+        }
+        if shares > Self::shares_of(env.clone(), lp.clone()) {
+            panic_with_error!(env, Error::InsufficientBalance);
+        }
+        Self::add(env, DataKey::Shares(lp.clone()), -shares);
+    }
+
+    // retires shares already taken from the LP and pays them out
+    fn burn(env: &Env, lp: &Address, shares: i128, amount: i128) {
+        Self::add(env, DataKey::TotalShares, -shares);
+        Self::add(env, DataKey::Idle, -amount);
+        let this = env.current_contract_address();
+        token::Client::new(env, &Self::get_asset(env.clone())).transfer(&this, lp, &amount);
+    }
+
+    fn read(env: &Env, key: DataKey) -> i128 {
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+
+    fn add(env: &Env, key: DataKey, amount: i128) {
+        let value = Self::read(env, key.clone()) + amount;
+        env.storage().persistent().set(&key, &value);
+    }
+}
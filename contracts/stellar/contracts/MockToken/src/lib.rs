@@ -1,122 +1,311 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env};
+use fusionx_types::Error;
+use soroban_sdk::{
+    contract, contractimpl, contracttype, panic_with_error, symbol_short, token::TokenInterface,
+    Address, Env, String,
+};
 
+/// Admin-controlled SEP-41 token for local networks and tests. Beyond the
+/// token interface it has the admin functions of a Stellar Asset Contract
+/// (`mint`, `set_admin`, `admin`), so `token::StellarAssetClient` drives it too.
 #[contract]
 pub struct MockToken;
 
-pub const NAME: &str = "MyToken";
-pub const SYMBOL: &str = "MTK";
-pub const DECIMALS: u8 = 18;
-
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
 pub enum DataKey {
-    Balance(Address),            // (token, user) -> balance
-    Allowance(Address, Address), // (token, owner, spender) -> allowance
+    Admin,
+    Metadata,
     TotalSupply,
+    Balance(Address),            // user -> balance
+    Allowance(Address, Address), // (owner, spender) -> allowance
+    Faucet,
+    LastDrip(Address), // user -> timestamp of its last faucet drip
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Metadata {
+    pub decimals: u32,
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Limits of `faucet`, set by the admin.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FaucetConfig {
+    pub max_amount: i128, // largest single drip
+    pub cooldown: u64,    // seconds an address waits between drips
+}
+
+/// An allowance and the last ledger it can be spent in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
 }
 
 #[contractimpl]
 impl MockToken {
-    pub fn mint(env: Env, to: Address, value: u128) {
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(to), &value);
+    pub fn __constructor(env: Env, admin: Address, decimals: u32, name: String, symbol: String) {
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(
+            &DataKey::Metadata,
+            &Metadata {
+                decimals,
+                name,
+                symbol,
+            },
+        );
     }
 
-    pub fn approve(env: Env, amount: u128, to: Address, caller: Address) {
-        caller.require_auth();
+    /// Creates `amount` new tokens for `to`, only the admin can mint.
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        Self::check_amount(&env, amount);
 
-        let user_balance = Self::get_balance(env.clone(), caller.clone());
+        Self::credit(&env, &to, amount);
+        Self::set_total_supply(&env, Self::total_supply(env.clone()) + amount);
+        env.events()
+            .publish((symbol_short!("mint"), admin, to), amount);
+    }
+
+    pub fn set_admin(env: Env, new_admin: Address) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.events()
+            .publish((symbol_short!("set_admin"), admin), new_admin);
+    }
 
-        if amount > user_balance {
-            panic!("Invalid amount");
+    /// Turns on the faucet with `config`, or updates its limits.
+    pub fn set_faucet(env: Env, config: FaucetConfig) {
+        Self::admin(env.clone()).require_auth();
+        if config.max_amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
         }
+        env.storage().instance().set(&DataKey::Faucet, &config);
+    }
 
+    pub fn disable_faucet(env: Env) {
+        Self::admin(env.clone()).require_auth();
+        env.storage().instance().remove(&DataKey::Faucet);
+    }
+
+    pub fn get_faucet(env: Env) -> FaucetConfig {
         env.storage()
-            .persistent()
-            .set(&DataKey::Allowance(caller, to), &amount);
+            .instance()
+            .get(&DataKey::Faucet)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::FaucetDisabled))
     }
 
-    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
-        let _amount = amount as u128;
-        if _amount == 0 {
-            panic!("Invalid amount");
+    /// Mints up to `max_amount` to `to` without the admin, once per cooldown
+    /// per address. Meant for shared test networks, so QA can fund itself.
+    pub fn faucet(env: Env, to: Address, amount: i128) {
+        to.require_auth();
+        let config = Self::get_faucet(env.clone());
+        Self::check_amount(&env, amount);
+        if amount > config.max_amount {
+            panic_with_error!(&env, Error::InvalidAmount);
         }
-
-        // Check allowance
-        let current_allowance = Self::get_allowance(env.clone(), from.clone(), spender.clone());
-
-        if _amount > current_allowance {
-            panic!("Invalid allowance");
+        let now = env.ledger().timestamp();
+        if now < Self::next_drip(env.clone(), to.clone()) {
+            panic_with_error!(&env, Error::FaucetCooldown);
         }
+        env.storage()
+            .persistent()
+            .set(&DataKey::LastDrip(to.clone()), &now);
 
-        // Check balance
-        let from_balance = Self::get_balance(env.clone(), from.clone());
+        Self::credit(&env, &to, amount);
+        Self::set_total_supply(&env, Self::total_supply(env.clone()) + amount);
+        env.events().publish((symbol_short!("faucet"), to), amount);
+    }
 
-        if _amount > from_balance {
-            panic!("Invalid balance");
+    /// Earliest timestamp `to` can use the faucet again.
+    pub fn next_drip(env: Env, to: Address) -> u64 {
+        let last_drip: Option<u64> = env.storage().persistent().get(&DataKey::LastDrip(to));
+        match last_drip {
+            Some(last_drip) => last_drip.saturating_add(Self::get_faucet(env).cooldown),
+            None => 0,
         }
+    }
 
-        // Update allowance
-        let new_allowance = current_allowance - _amount;
+    pub fn admin(env: Env) -> Address {
         env.storage()
-            .persistent()
-            .set(&DataKey::Allowance(from.clone(), spender), &new_allowance);
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
 
-        // Update from balance
-        let new_from_balance = from_balance - _amount;
+    pub fn total_supply(env: Env) -> i128 {
         env.storage()
-            .persistent()
-            .set(&DataKey::Balance(from.clone()), &new_from_balance);
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0)
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for MockToken {
+    /// Spendable allowance, zero once it has expired.
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        let allowance = Self::read_allowance(&env, &from, &spender);
+        if allowance.expiration_ledger < env.ledger().sequence() {
+            return 0;
+        }
+        allowance.amount
+    }
 
-        // Update to balance
-        let to_balance = Self::get_balance(env.clone(), to.clone());
-        let new_to_balance = to_balance + _amount;
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        if amount < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            panic_with_error!(&env, Error::InvalidExpirationLedger);
+        }
+        env.storage().persistent().set(
+            &DataKey::Allowance(from.clone(), spender.clone()),
+            &AllowanceValue {
+                amount,
+                expiration_ledger,
+            },
+        );
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
         env.storage()
             .persistent()
-            .set(&DataKey::Balance(to), &new_to_balance);
+            .get(&DataKey::Balance(id))
+            .unwrap_or(0)
     }
 
-    pub fn transfer(env: Env, amount: u128, to: Address, caller: Address) {
-        caller.require_auth();
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::check_nonnegative_amount(&env, amount);
+        Self::debit(&env, &from, amount);
+        Self::credit(&env, &to, amount);
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
 
-        if amount == 0 {
-            panic!("Invalid amount");
-        }
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::check_nonnegative_amount(&env, amount);
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::debit(&env, &from, amount);
+        Self::credit(&env, &to, amount);
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
 
-        let sender_balance = Self::get_balance(env.clone(), caller.clone());
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        Self::check_nonnegative_amount(&env, amount);
+        Self::burn_balance(&env, &from, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
 
-        if amount > sender_balance {
-            panic!("Invalid balance");
-        }
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        Self::check_nonnegative_amount(&env, amount);
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::burn_balance(&env, &from, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        Self::metadata(&env).decimals
+    }
+
+    fn name(env: Env) -> String {
+        Self::metadata(&env).name
+    }
 
-        // Update sender balance
-        let new_sender_balance = sender_balance - amount;
+    fn symbol(env: Env) -> String {
+        Self::metadata(&env).symbol
+    }
+}
+
+// The balance and allowance helpers are public so token fixtures can build on
+// MockToken's storage and override single operations.
+impl MockToken {
+    fn metadata(env: &Env) -> Metadata {
         env.storage()
-            .persistent()
-            .set(&DataKey::Balance(caller), &new_sender_balance);
+            .instance()
+            .get(&DataKey::Metadata)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
+
+    pub fn check_amount(env: &Env, amount: i128) {
+        if amount <= 0 {
+            panic_with_error!(env, Error::InvalidAmount);
+        }
+    }
 
-        // Update recipient balance
-        let recipient_balance = Self::get_balance(env.clone(), to.clone());
-        let new_recipient_balance = recipient_balance + amount;
+    /// Transfers and burns of zero go through, like on a Stellar Asset Contract.
+    pub fn check_nonnegative_amount(env: &Env, amount: i128) {
+        if amount < 0 {
+            panic_with_error!(env, Error::InvalidAmount);
+        }
+    }
+
+    fn read_allowance(env: &Env, from: &Address, spender: &Address) -> AllowanceValue {
         env.storage()
             .persistent()
-            .set(&DataKey::Balance(to), &new_recipient_balance);
+            .get(&DataKey::Allowance(from.clone(), spender.clone()))
+            .unwrap_or(AllowanceValue {
+                amount: 0,
+                expiration_ledger: 0,
+            })
+    }
+
+    pub fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let allowance = Self::allowance(env.clone(), from.clone(), spender.clone());
+        if amount > allowance {
+            panic_with_error!(env, Error::InsufficientAllowance);
+        }
+        let expiration_ledger = Self::read_allowance(env, from, spender).expiration_ledger;
+        env.storage().persistent().set(
+            &DataKey::Allowance(from.clone(), spender.clone()),
+            &AllowanceValue {
+                amount: allowance - amount,
+                expiration_ledger,
+            },
+        );
     }
 
-    pub fn get_balance(env: Env, user: Address) -> u128 {
+    pub fn burn_balance(env: &Env, from: &Address, amount: i128) {
+        Self::debit(env, from, amount);
+        Self::set_total_supply(env, Self::total_supply(env.clone()) - amount);
+    }
+
+    fn set_total_supply(env: &Env, supply: i128) {
+        env.storage().instance().set(&DataKey::TotalSupply, &supply);
+    }
+
+    pub fn credit(env: &Env, to: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), to.clone());
         env.storage()
             .persistent()
-            .get(&DataKey::Balance(user))
-            .unwrap_or(0)
+            .set(&DataKey::Balance(to.clone()), &(balance + amount));
     }
 
-    pub fn get_allowance(env: Env, owner: Address, spender: Address) -> u128 {
+    pub fn debit(env: &Env, from: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), from.clone());
+        if amount > balance {
+            panic_with_error!(env, Error::InsufficientBalance);
+        }
         env.storage()
             .persistent()
-            .get(&DataKey::Allowance(owner, spender))
-            .unwrap_or(0)
+            .set(&DataKey::Balance(from.clone()), &(balance - amount));
     }
 }
@@ -1,46 +1,46 @@
 #![no_std]
+pub mod staking;
 
-use soroban_sdk::xdr::FromXdr;
+use fusionx_types::interfaces::{
+    DutchAuctionClient, EscrowFactoryClient, EscrowSrcClient, WrappedTokensClient,
+};
+use fusionx_types::{
+    Error, OrderInput, SettlementKind, SignalSecretShare, StakeSlashed, TokenTransfer,
+    WebAuthnSignature, MAX_DECIMALS,
+};
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, token, Address, BytesN, Env,
+    contract, contractimpl, contracttype, panic_with_error, symbol_short, token, vec, Address,
+    BytesN, Env,
 };
-
-mod dutch_auction {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\dutchauction.wasm");
-}
-
-mod wrapped_tokens {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\wrappedtoken.wasm");
-}
+use staking::{SlashTotals, Stake, StakingConfig};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
 pub enum DataKey {
-    Resolvers(Address), // address -> bool whitelisted resolvers
-    DutchAuction,       // dutch auction contract address
-    Owner,              // owner address
-}
-
-#[derive(Clone)]
-#[contracttype]
-pub struct SignalSecretShare {
-    pub escrow_src: BytesN<32>,
-    pub escrow_dest: BytesN<32>,
-    pub order_id: BytesN<32>,
-    pub resolver: Address,
+    Resolvers(Address),            // address -> bool whitelisted resolvers
+    DutchAuction,                  // dutch auction contract address
+    WrappedTokens,                 // wrapped tokens contract address
+    Owner,                         // owner address
+    TokenDecimals(Address),        // stellar token -> decimals
+    DestTokenDecimals(BytesN<32>), // destination chain token -> decimals
+    SignedTransfer(BytesN<32>),    // order id -> maker's signed transfer into the escrow
+    StakingConfig,                 // token, minimum and slashing of resolver stake
+    Stake(Address),                // resolver -> bonded and unbonding stake
+    TotalStake,                    // bonded and unbonding stake of all resolvers
+    Slashes(Address),              // resolver -> count and sum of the slashes it took
+    Slashed(Address),              // escrow -> bool already slashed over
+    TrustedFactory(Address),       // escrow factory -> bool escrows count as proof
 }
 
-#[derive(Clone)]
+/// Maker's `permit_transfer_from` signature over the order's amount into its
+/// source escrow, spent when the order is filled.
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
-pub struct OrderInput {
-    pub orderId: BytesN<32>,
-    pub maker: Address,
-    pub tokenIn: BytesN<32>,
-    pub tokenOut: BytesN<32>,
-    pub amountIn: u128,
-    pub minAmountOut: u128,
-    pub maxAmountOut: u128,
-    pub hashLock: BytesN<32>,
+pub struct SignedTransfer {
+    pub nonce: u64,
+    pub deadline: u64,
+    pub public_key: BytesN<32>,
+    pub signature: BytesN<64>,
 }
 
 #[contract]
@@ -48,11 +48,14 @@ pub struct Relayer;
 
 #[contractimpl]
 impl Relayer {
-    pub fn initialize(env: Env, owner: Address, dutch_auction: Address) {
+    pub fn initialize(env: Env, owner: Address, dutch_auction: Address, wrapped_tokens: Address) {
         env.storage().persistent().set(&DataKey::Owner, &owner);
         env.storage()
             .persistent()
             .set(&DataKey::DutchAuction, &dutch_auction);
+        env.storage()
+            .persistent()
+            .set(&DataKey::WrappedTokens, &wrapped_tokens);
     }
 
     pub fn add_resolver(env: Env, resolver: Address) {
@@ -70,44 +73,288 @@ impl Relayer {
             .set(&DataKey::Resolvers(resolver), &false);
     }
 
+    /// Whitelisted by the owner or bonded at least the minimum stake.
     pub fn is_resolver(env: Env, resolver: Address) -> bool {
         env.storage()
             .persistent()
-            .get(&DataKey::Resolvers(resolver))
+            .get(&DataKey::Resolvers(resolver.clone()))
+            .unwrap_or(false)
+            || staking::is_bonded(&env, &resolver)
+    }
+
+    /// The token can only change while no stake is held in the old one.
+    pub fn set_staking_config(env: Env, config: StakingConfig) {
+        Self::only_owner(env.clone());
+        if config.min_stake <= 0 || config.slash_amount < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if staking::get_config(&env).is_some_and(|current| current.token != config.token)
+            && staking::total_stake(&env) > 0
+        {
+            panic_with_error!(&env, Error::StakeOutstanding);
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::StakingConfig, &config);
+    }
+
+    pub fn get_staking_config(env: Env) -> StakingConfig {
+        staking::get_config(&env)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::StakingNotConfigured))
+    }
+
+    /// Bonds `amount` of the staking token paid by `from` for `resolver`,
+    /// which is a resolver for as long as it has the minimum bonded.
+    pub fn bond(env: Env, from: Address, resolver: Address, amount: i128) {
+        from.require_auth();
+        let config = Self::get_staking_config(env.clone());
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        let this = env.current_contract_address();
+        token::Client::new(&env, &config.token).transfer(&from, &this, &amount);
+        let mut stake = staking::get_stake(&env, &resolver);
+        stake.bonded += amount;
+        staking::set_stake(&env, &resolver, &stake);
+    }
+
+    /// Starts unbonding `amount`, which stops counting right away and can be
+    /// withdrawn after the unbonding period. Unbonding more restarts the
+    /// period for all of it.
+    pub fn unbond(env: Env, resolver: Address, amount: i128) {
+        resolver.require_auth();
+        let config = Self::get_staking_config(env.clone());
+        let mut stake = staking::get_stake(&env, &resolver);
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if amount > stake.bonded {
+            panic_with_error!(&env, Error::InsufficientBalance);
+        }
+        stake.bonded -= amount;
+        stake.unbonding += amount;
+        stake.unlock_at = env.ledger().timestamp() + config.unbonding_period;
+        staking::set_stake(&env, &resolver, &stake);
+    }
+
+    /// Pays the unbonded stake back to `resolver` and returns the amount.
+    pub fn withdraw_unbonded(env: Env, resolver: Address) -> i128 {
+        resolver.require_auth();
+        let config = Self::get_staking_config(env.clone());
+        let mut stake = staking::get_stake(&env, &resolver);
+        if env.ledger().timestamp() < stake.unlock_at {
+            panic_with_error!(&env, Error::InvalidTime);
+        }
+        let amount = stake.unbonding;
+        if amount > 0 {
+            stake.unbonding = 0;
+            staking::set_stake(&env, &resolver, &stake);
+            let this = env.current_contract_address();
+            token::Client::new(&env, &config.token).transfer(&this, &resolver, &amount);
+        }
+        amount
+    }
+
+    /// Slashes the executive resolver of `escrow` if a public path had to
+    /// settle it, i.e. the resolver missed its exclusive window. `escrow`
+    /// has to come from a trusted `factory`. Anyone can call it, the slashed
+    /// stake goes to the order's maker. Stake a maker can't take, e.g. for a
+    /// missing trustline, stays locked in the relayer. Returns the amount
+    /// slashed.
+    pub fn slash(env: Env, factory: Address, escrow: Address) -> i128 {
+        if !Self::is_trusted_factory(env.clone(), factory.clone()) {
+            panic_with_error!(&env, Error::UntrustedFactory);
+        }
+        let config = Self::get_staking_config(env.clone());
+        let order_id = EscrowFactoryClient::new(&env, &factory).get_orderid_from_escrow(&escrow);
+        if staking::is_slashed(&env, &escrow) {
+            panic_with_error!(&env, Error::AlreadySlashed);
+        }
+
+        // source and destination escrows share these getters
+        let escrow_client = EscrowSrcClient::new(&env, &escrow);
+        let kind = match escrow_client.get_settlement() {
+            Some(settlement)
+                if settlement.kind == SettlementKind::PublicWithdrawn
+                    || settlement.kind == SettlementKind::PublicCancelled =>
+            {
+                settlement.kind
+            }
+            _ => panic_with_error!(&env, Error::NoMisbehaviour),
+        };
+        let resolver = escrow_client.get_executive_resolver();
+        let maker = escrow_client.get_maker();
+
+        let amount = staking::slash(&env, &resolver, config.slash_amount);
+        let paid = amount > 0
+            && token::Client::new(&env, &config.token)
+                .try_transfer(&env.current_contract_address(), &maker, &amount)
+                .is_ok_and(|result| result.is_ok());
+        staking::record_slash(&env, &resolver, &escrow, amount);
+        env.events().publish(
+            (symbol_short!("stake"), symbol_short!("slash")),
+            StakeSlashed {
+                resolver,
+                escrow,
+                order_id,
+                kind,
+                amount,
+                maker,
+                paid,
+            },
+        );
+        amount
+    }
+
+    pub fn get_stake(env: Env, resolver: Address) -> Stake {
+        staking::get_stake(&env, &resolver)
+    }
+
+    pub fn get_total_stake(env: Env) -> i128 {
+        staking::total_stake(&env)
+    }
+
+    pub fn get_slashes(env: Env, resolver: Address) -> SlashTotals {
+        staking::get_slashes(&env, &resolver)
+    }
+
+    /// Lets escrows of `factory` prove misbehaviour in `slash`.
+    pub fn set_trusted_factory(env: Env, factory: Address, trusted: bool) {
+        Self::only_owner(env.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::TrustedFactory(factory), &trusted);
+    }
+
+    pub fn is_trusted_factory(env: Env, factory: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TrustedFactory(factory))
             .unwrap_or(false)
     }
 
     pub fn place_order(
         env: Env,
-        order_input: dutch_auction::OrderInput,
+        order_input: OrderInput,
+        nonce: u64,
+        deadline: u64,
         public_key: BytesN<32>,
         signature: BytesN<64>,
-        hash: BytesN<32>,
     ) {
         Self::only_owner(env.clone());
-        let _wrappedtoken = wrapped_tokens::Client::new(&env.clone(), &order_input.tokenIn.clone());
+        let _wrappedtoken =
+            WrappedTokensClient::new(&env.clone(), &Self::get_wrapped_tokens(env.clone()));
         _wrappedtoken.permit(
             &order_input.tokenIn.clone(),
             &order_input.maker.clone(),
             &env.current_contract_address(),
             &order_input.amountIn,
+            &nonce,
+            &deadline,
             &public_key,
             &signature,
-            &hash,
         );
 
-        // start dutch auction
-        let _dutch_auction =
-            dutch_auction::Client::new(&env.clone(), &Self::get_dutch_auction(env.clone()));
-        _dutch_auction.start_auction(&order_input);
+        Self::start_auction(env, order_input);
     }
 
-    fn bytesn32_to_address(env: Env, bytes: BytesN<32>) -> Address {
-        Address::from_xdr(&env, &bytes.into()).unwrap()
+    /// `place_order` without a standing allowance. The maker signs a
+    /// `permit_transfer_from` of `amountIn` with this contract as spender and
+    /// the order's source escrow (`EscrowFactory::get_escrow_address`) as
+    /// recipient, which is checked here and spent when the order is filled.
+    pub fn place_order_with_transfer(
+        env: Env,
+        order_input: OrderInput,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    ) {
+        Self::only_owner(env.clone());
+        // rather than start an auction nobody could fill
+        let dutch_auction = DutchAuctionClient::new(&env, &Self::get_dutch_auction(env.clone()));
+        let factory = EscrowFactoryClient::new(&env, &dutch_auction.get_escrow_factory());
+        let transfer = TokenTransfer {
+            token: order_input.tokenIn.clone(),
+            to: factory.get_escrow_address(&order_input.orderId),
+            amount: order_input.amountIn,
+        };
+        let wrapped_tokens = WrappedTokensClient::new(&env, &Self::get_wrapped_tokens(env.clone()));
+        wrapped_tokens.check_permit_transfer(
+            &order_input.maker,
+            &env.current_contract_address(),
+            &vec![&env, transfer],
+            &nonce,
+            &deadline,
+            &public_key,
+            &signature,
+        );
+        env.storage().persistent().set(
+            &DataKey::SignedTransfer(order_input.orderId.clone()),
+            &SignedTransfer {
+                nonce,
+                deadline,
+                public_key,
+                signature,
+            },
+        );
+
+        Self::start_auction(env, order_input);
+    }
+
+    /// `place_order` for makers signing their permit with an EVM wallet, see
+    /// `WrappedTokens::permit_secp256k1`.
+    pub fn place_order_secp256k1(
+        env: Env,
+        order_input: OrderInput,
+        nonce: u64,
+        deadline: u64,
+        signature: BytesN<64>,
+        recovery_id: u32,
+    ) {
+        Self::only_owner(env.clone());
+        let wrapped_tokens = WrappedTokensClient::new(&env, &Self::get_wrapped_tokens(env.clone()));
+        wrapped_tokens.permit_secp256k1(
+            &order_input.tokenIn,
+            &order_input.maker,
+            &env.current_contract_address(),
+            &order_input.amountIn,
+            &nonce,
+            &deadline,
+            &signature,
+            &recovery_id,
+        );
+
+        Self::start_auction(env, order_input);
+    }
+
+    /// `place_order` for makers signing their permit with a passkey, see
+    /// `WrappedTokens::permit_secp256r1`.
+    pub fn place_order_secp256r1(
+        env: Env,
+        order_input: OrderInput,
+        nonce: u64,
+        deadline: u64,
+        signature: WebAuthnSignature,
+    ) {
+        Self::only_owner(env.clone());
+        let wrapped_tokens = WrappedTokensClient::new(&env, &Self::get_wrapped_tokens(env.clone()));
+        wrapped_tokens.permit_secp256r1(
+            &order_input.tokenIn,
+            &order_input.maker,
+            &env.current_contract_address(),
+            &order_input.amountIn,
+            &nonce,
+            &deadline,
+            &signature,
+        );
+
+        Self::start_auction(env, order_input);
     }
 
     pub fn move_tokens_to_escrow(
         env: Env,
+        order_id: BytesN<32>,
         maker: Address,
         token: Address,
         src_escrow: Address,
@@ -116,13 +363,39 @@ impl Relayer {
         let dutch_auction = Self::get_dutch_auction(env.clone());
         dutch_auction.require_auth();
 
+        let relayer = env.current_contract_address();
+        let wrapped_tokens = WrappedTokensClient::new(&env, &Self::get_wrapped_tokens(env.clone()));
         let token_client = token::Client::new(&env, &token);
-        token_client.transfer_from(
-            &env.current_contract_address(),
-            &maker,
-            &src_escrow,
-            &(amount_in as i128),
-        );
+        let locked = token_client.balance(&src_escrow);
+
+        let key = DataKey::SignedTransfer(order_id);
+        if let Some(signed) = env.storage().persistent().get::<_, SignedTransfer>(&key) {
+            // the maker's signed transfer pays the escrow directly
+            env.storage().persistent().remove(&key);
+            wrapped_tokens.permit_transfer_from(
+                &token,
+                &maker,
+                &relayer,
+                &src_escrow,
+                &amount_in,
+                &signed.nonce,
+                &signed.deadline,
+                &signed.public_key,
+                &signed.signature,
+            );
+        } else {
+            // pull the maker's wrapped balance using the permit allowance, unwrap
+            // it and lock the underlying token in the escrow
+            wrapped_tokens.transfer_from(&token, &amount_in, &maker, &relayer, &relayer);
+            wrapped_tokens.withdraw(&token, &amount_in, &relayer);
+            token_client.transfer(&relayer, &src_escrow, &(amount_in as i128));
+        }
+
+        // the escrow pays out exactly amount_in, anything short of it would be
+        // stuck there for good
+        if token_client.balance(&src_escrow) - locked != amount_in as i128 {
+            panic_with_error!(&env, Error::EscrowUnderfunded);
+        }
     }
 
     pub fn signal_share_secret(
@@ -158,6 +431,16 @@ impl Relayer {
             .set(&DataKey::DutchAuction, &dutch_auction);
     }
 
+    fn start_auction(env: Env, order_input: OrderInput) {
+        let _dutch_auction =
+            DutchAuctionClient::new(&env.clone(), &Self::get_dutch_auction(env.clone()));
+        _dutch_auction.start_auction(
+            &order_input,
+            &Self::get_token_decimals(env.clone(), order_input.tokenIn.clone()),
+            &Self::get_dest_token_decimals(env.clone(), order_input.tokenOut.clone()),
+        );
+    }
+
     fn only_owner(env: Env) {
         let owner = Self::get_owner(env);
         owner.require_auth();
@@ -170,6 +453,56 @@ impl Relayer {
             .unwrap()
     }
 
+    pub fn set_wrapped_tokens(env: Env, wrapped_tokens: Address) {
+        Self::only_owner(env.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::WrappedTokens, &wrapped_tokens);
+    }
+
+    pub fn get_wrapped_tokens(env: Env) -> Address {
+        env.storage()
+            .persistent()
+            .get(&DataKey::WrappedTokens)
+            .unwrap()
+    }
+
+    pub fn set_token_decimals(env: Env, token: Address, decimals: u32) {
+        Self::only_owner(env.clone());
+        Self::validate_decimals(env.clone(), decimals);
+        env.storage()
+            .persistent()
+            .set(&DataKey::TokenDecimals(token), &decimals);
+    }
+
+    pub fn get_token_decimals(env: Env, token: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TokenDecimals(token))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::TokenNotRegistered))
+    }
+
+    pub fn set_dest_token_decimals(env: Env, token: BytesN<32>, decimals: u32) {
+        Self::only_owner(env.clone());
+        Self::validate_decimals(env.clone(), decimals);
+        env.storage()
+            .persistent()
+            .set(&DataKey::DestTokenDecimals(token), &decimals);
+    }
+
+    pub fn get_dest_token_decimals(env: Env, token: BytesN<32>) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DestTokenDecimals(token))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::TokenNotRegistered))
+    }
+
+    fn validate_decimals(env: Env, decimals: u32) {
+        if decimals > MAX_DECIMALS {
+            panic_with_error!(&env, Error::InvalidDecimals);
+        }
+    }
+
     pub fn transfer_ownership(env: Env, new_owner: Address) {
         Self::only_owner(env.clone());
         env.storage().persistent().set(&DataKey::Owner, &new_owner);
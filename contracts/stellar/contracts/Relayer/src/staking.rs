@@ -0,0 +1,105 @@
+//! Stake resolvers bond to register without the owner, and the slashes it
+//! took. Bonded stake stays slashable while it unbonds, so a resolver can't
+//! run from a slash it has coming. Slashes are counted here, each one is
+//! published as a `StakeSlashed` event.
+
+use soroban_sdk::{contracttype, Address, Env};
+
+use crate::DataKey;
+
+/// Set by the owner with `set_staking_config`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StakingConfig {
+    pub token: Address,        // token stake is bonded in
+    pub min_stake: i128,       // bonded stake that makes a resolver
+    pub unbonding_period: u64, // seconds before unbonded stake can be withdrawn
+    pub slash_amount: i128,    // taken per proven misbehaviour, at most the whole stake
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct Stake {
+    pub bonded: i128,
+    pub unbonding: i128, // no longer counts towards min_stake, still slashable
+    pub unlock_at: u64,  // when `unbonding` can be withdrawn
+}
+
+/// Slashes a resolver took.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct SlashTotals {
+    pub count: u32,
+    pub amount: i128,
+}
+
+pub fn get_config(env: &Env) -> Option<StakingConfig> {
+    env.storage().persistent().get(&DataKey::StakingConfig)
+}
+
+pub fn get_stake(env: &Env, resolver: &Address) -> Stake {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Stake(resolver.clone()))
+        .unwrap_or_default()
+}
+
+/// Stores `stake`, keeping the total of all resolvers in step.
+pub fn set_stake(env: &Env, resolver: &Address, stake: &Stake) {
+    let before = get_stake(env, resolver);
+    let total =
+        total_stake(env) + stake.bonded + stake.unbonding - before.bonded - before.unbonding;
+    env.storage().persistent().set(&DataKey::TotalStake, &total);
+    env.storage()
+        .persistent()
+        .set(&DataKey::Stake(resolver.clone()), stake);
+}
+
+pub fn total_stake(env: &Env) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TotalStake)
+        .unwrap_or(0)
+}
+
+pub fn is_bonded(env: &Env, resolver: &Address) -> bool {
+    get_config(env).is_some_and(|config| get_stake(env, resolver).bonded >= config.min_stake)
+}
+
+/// Takes up to `amount` out of the stake, bonded first, and returns what it
+/// took.
+pub fn slash(env: &Env, resolver: &Address, amount: i128) -> i128 {
+    let mut stake = get_stake(env, resolver);
+    let from_bonded = amount.min(stake.bonded);
+    let from_unbonding = (amount - from_bonded).min(stake.unbonding);
+    stake.bonded -= from_bonded;
+    stake.unbonding -= from_unbonding;
+    set_stake(env, resolver, &stake);
+    from_bonded + from_unbonding
+}
+
+pub fn get_slashes(env: &Env, resolver: &Address) -> SlashTotals {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Slashes(resolver.clone()))
+        .unwrap_or_default()
+}
+
+pub fn record_slash(env: &Env, resolver: &Address, escrow: &Address, amount: i128) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::Slashed(escrow.clone()), &true);
+    let mut slashes = get_slashes(env, resolver);
+    slashes.count += 1;
+    slashes.amount += amount;
+    env.storage()
+        .persistent()
+        .set(&DataKey::Slashes(resolver.clone()), &slashes);
+}
+
+pub fn is_slashed(env: &Env, escrow: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Slashed(escrow.clone()))
+        .unwrap_or(false)
+}
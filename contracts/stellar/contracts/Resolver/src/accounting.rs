@@ -0,0 +1,210 @@
+//! Per-order bookkeeping of what the resolver put into and got out of each
+//! swap, for the escrows it is the executive resolver of. Legs are settled
+//! from the escrows' own settlement records. An order is closed once none of
+//! its escrows on this chain is still locked, its totals then count towards
+//! its token pair and the period it closed in. An escrow deployed for it later
+//! reopens it, until it closes again.
+
+use fusionx_types::SettlementKind;
+use soroban_sdk::{contracttype, Address, BytesN, Env, Map, Vec};
+
+use crate::DataKey;
+
+/// Length of a reporting period, one day.
+pub const PERIOD: u64 = 24 * 60 * 60;
+
+/// Order ids per page of a period's index.
+pub const PAGE_SIZE: u32 = 100;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum LegStatus {
+    None, // no escrow on this chain for this side
+    Locked,
+    Withdrawn,
+    Cancelled,
+}
+
+impl From<SettlementKind> for LegStatus {
+    fn from(kind: SettlementKind) -> Self {
+        match kind {
+            SettlementKind::Withdrawn | SettlementKind::PublicWithdrawn => LegStatus::Withdrawn,
+            SettlementKind::Cancelled | SettlementKind::PublicCancelled => LegStatus::Cancelled,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OrderRecord {
+    pub token_in: Option<Address>,  // src token, if its escrow is here
+    pub token_out: Option<Address>, // dest token, if its escrow is here
+    pub amount_in: i128,            // received from the src escrow
+    pub amount_out: i128,           // locked in the dest escrow, less refunds
+    pub deposits_posted: i128,
+    pub deposits_returned: i128, // with rewards for public withdrawals and cancels
+    pub src: LegStatus,
+    pub dest: LegStatus,
+    pub closed_at: Option<u64>,
+}
+
+/// Token pair closed orders are summed up by. A side is `None` when its
+/// escrow lived on another chain.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Pair {
+    pub token_in: Option<Address>,
+    pub token_out: Option<Address>,
+}
+
+/// Sums over closed orders.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct PnlTotals {
+    pub orders: u32,
+    pub amount_in: i128,
+    pub amount_out: i128,
+    pub deposits_posted: i128,
+    pub deposits_returned: i128,
+}
+
+impl OrderRecord {
+    fn new() -> Self {
+        OrderRecord {
+            token_in: None,
+            token_out: None,
+            amount_in: 0,
+            amount_out: 0,
+            deposits_posted: 0,
+            deposits_returned: 0,
+            src: LegStatus::None,
+            dest: LegStatus::None,
+            closed_at: None,
+        }
+    }
+
+    fn is_final(&self) -> bool {
+        (self.src, self.dest) != (LegStatus::None, LegStatus::None)
+            && self.src != LegStatus::Locked
+            && self.dest != LegStatus::Locked
+    }
+
+    fn pair(&self) -> Pair {
+        Pair {
+            token_in: self.token_in.clone(),
+            token_out: self.token_out.clone(),
+        }
+    }
+}
+
+impl PnlTotals {
+    /// Adds `record` to the totals, or takes it out again with `remove`.
+    fn apply(&mut self, record: &OrderRecord, remove: bool) {
+        let sign = if remove { -1 } else { 1 };
+        self.orders = if remove {
+            self.orders - 1
+        } else {
+            self.orders + 1
+        };
+        self.amount_in += sign * record.amount_in;
+        self.amount_out += sign * record.amount_out;
+        self.deposits_posted += sign * record.deposits_posted;
+        self.deposits_returned += sign * record.deposits_returned;
+    }
+}
+
+pub fn get_record(env: &Env, order_id: &BytesN<32>) -> Option<OrderRecord> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::OrderRecord(order_id.clone()))
+}
+
+/// Applies `change` to the order's record, closing it once it is final and
+/// reopening it when a leg is locked again. Records changed after closing are
+/// re-counted in the period they closed in.
+pub fn update(env: &Env, order_id: &BytesN<32>, change: impl FnOnce(&mut OrderRecord)) {
+    let before = get_record(env, order_id);
+    let mut record = before.clone().unwrap_or_else(OrderRecord::new);
+    change(&mut record);
+
+    if !record.is_final() {
+        record.closed_at = None;
+    } else if record.closed_at.is_none() {
+        let now = env.ledger().timestamp();
+        record.closed_at = Some(now);
+        index_closed(env, now / PERIOD, order_id);
+    }
+    if let Some(before) = before.filter(|before| before.closed_at.is_some()) {
+        add_to_totals(env, &before, true);
+    }
+    if record.closed_at.is_some() {
+        add_to_totals(env, &record, false);
+    }
+    env.storage()
+        .persistent()
+        .set(&DataKey::OrderRecord(order_id.clone()), &record);
+}
+
+pub fn pair_totals(env: &Env, pair: Pair) -> PnlTotals {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PairTotals(pair))
+        .unwrap_or_default()
+}
+
+pub fn period_totals(env: &Env, period: u64) -> Map<Pair, PnlTotals> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PeriodTotals(period))
+        .unwrap_or(Map::new(env))
+}
+
+pub fn period_orders(env: &Env, period: u64, page: u32) -> Vec<BytesN<32>> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PeriodOrders(period, page))
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn period_order_count(env: &Env, period: u64) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PeriodOrderCount(period))
+        .unwrap_or(0)
+}
+
+// appends to the period's last page, so no entry grows past PAGE_SIZE ids
+fn index_closed(env: &Env, period: u64, order_id: &BytesN<32>) {
+    let count = period_order_count(env, period);
+    let page = count / PAGE_SIZE;
+    let mut orders = period_orders(env, period, page);
+    orders.push_back(order_id.clone());
+    env.storage()
+        .persistent()
+        .set(&DataKey::PeriodOrders(period, page), &orders);
+    env.storage()
+        .persistent()
+        .set(&DataKey::PeriodOrderCount(period), &(count + 1));
+}
+
+fn add_to_totals(env: &Env, record: &OrderRecord, remove: bool) {
+    let pair = record.pair();
+    let mut totals = pair_totals(env, pair.clone());
+    totals.apply(record, remove);
+    env.storage()
+        .persistent()
+        .set(&DataKey::PairTotals(pair.clone()), &totals);
+
+    let period = record.closed_at.unwrap_or_default() / PERIOD;
+    let mut by_pair = period_totals(env, period);
+    let mut totals = by_pair.get(pair.clone()).unwrap_or_default();
+    totals.apply(record, remove);
+    if totals.orders == 0 {
+        by_pair.remove(pair);
+    } else {
+        by_pair.set(pair, totals);
+    }
+    env.storage()
+        .persistent()
+        .set(&DataKey::PeriodTotals(period), &by_pair);
+}
@@ -1,37 +1,80 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, BytesN, Env};
+pub mod accounting;
 
-mod dutch_auction {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\dutchauction.wasm");
-}
-
-mod escrow_factory {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowfactory.wasm");
-}
+use accounting::{LegStatus, OrderRecord, Pair, PnlTotals};
+use fusionx_types::interfaces::{
+    AmmRouterClient, DutchAuctionClient, EscrowDestClient, EscrowFactoryClient, EscrowSrcClient,
+    LiquidityVaultClient, RelayerClient,
+};
+use fusionx_types::{Error, Settlement};
+use soroban_sdk::auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation};
+use soroban_sdk::{
+    contract, contractimpl, contracttype, panic_with_error, token, vec, Address, BytesN, Env,
+    IntoVal, Map, Symbol, Val, Vec,
+};
 
-mod relayer {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\relayer.wasm");
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum DataKey {
+    Owner,                        // owner address
+    EscrowFactory,                // escrow factory address
+    Relayer,                      // relayer contract address
+    DutchAuction,                 // contract address
+    Operators,                    // keys allowed to settle and cancel escrows
+    AllowedCall(Address, Symbol), // (contract, function) -> callable from a batch
+    NativeReserve,                // native tokens kept back for security deposits
+    OrderRecord(BytesN<32>),      // order_id -> what the resolver put in and got out
+    PairTotals(Pair),             // closed orders summed up per token pair
+    PeriodTotals(u64),            // period -> closed orders summed up per token pair
+    PeriodOrders(u64, u32),       // (period, page) -> orders closed in it
+    PeriodOrderCount(u64),        // period -> orders closed in it, all pages
+    Router,                       // AMM router `Action::Swap` goes through
+    Vault,                        // LiquidityVault `Action::Borrow` borrows from
 }
 
-mod escrow_src {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowsrc.wasm");
-    pub type EscrowSrcClient<'a> = Client<'a>;
+/// Arguments of `deploy_escrow_dest`, bundled for `Action::DeployDest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DestDeployment {
+    pub caller: Address,
+    pub order_id: BytesN<32>,
+    pub hash_lock: BytesN<32>,
+    pub token_out: Address,
+    pub amount_out: u128,
+    pub maker: Address,
 }
-use escrow_src::EscrowSrcClient;
 
-mod escrow_dest {
-    soroban_sdk::contractimport!(file = "G:\\EthUnite\\stellar-fusionX\\contracts\\stellar\\target\\wasm32v1-none\\release\\escrowdest.wasm");
-    pub type EscrowDestClient<'a> = Client<'a>;
+/// Arguments of `swap`, bundled for `Action::Swap`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Swap {
+    pub path: Vec<Address>, // token sold first, token bought last
+    pub amount_in: i128,
+    pub min_amount_out: i128,
+    pub deadline: u64,
 }
-use escrow_dest::EscrowDestClient;
 
+/// One step of `execute_batch`. The typed steps take the arguments of the
+/// matching entrypoint, escrow operations act as the resolver contract.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
-pub enum DataKey {
-    Owner,         // owner address
-    EscrowFactory, // escrow factory address
-    Relayer,       // relayer contract address
-    DutchAuction,  // contract address
+pub enum Action {
+    Fill(Address, BytesN<32>, u128), // (caller, order_id, max_amount_out)
+    DeployDest(DestDeployment),
+    // (order_id, escrow_src, escrow_dest, caller)
+    Notify(BytesN<32>, BytesN<32>, BytesN<32>, Address),
+    WithdrawSrc(Address, BytesN<32>),        // (escrow, secret)
+    PublicWithdrawSrc(Address, BytesN<32>),  // (escrow, secret)
+    CancelSrc(Address),                      // escrow
+    PublicCancelSrc(Address),                // escrow
+    WithdrawDest(Address, BytesN<32>),       // (escrow, secret)
+    PublicWithdrawDest(Address, BytesN<32>), // (escrow, secret)
+    CancelDest(Address),                     // escrow
+    Transfer(Address, Address, i128),        // (token, to, amount), like `withdraw`
+    Call(Address, Symbol, Vec<Val>),         // (contract, function, args), allowlisted only
+    Swap(Swap),
+    Borrow(BytesN<32>, i128), // (order_id, amount)
+    Repay(BytesN<32>),        // order_id
 }
 
 #[contract]
@@ -56,11 +99,16 @@ impl Resolver {
             .set(&DataKey::DutchAuction, &dutch_auction);
     }
 
-    pub fn deploy_escrow_src(env: Env, caller: Address, order_id: BytesN<32>) {
+    /// Fills the order unless that commits to more than `max_amount_out` on
+    /// the destination side.
+    pub fn deploy_escrow_src(
+        env: Env,
+        caller: Address,
+        order_id: BytesN<32>,
+        max_amount_out: u128,
+    ) {
         Self::only_owner(env.clone());
-        let dutch_auction_contract =
-            dutch_auction::Client::new(&env.clone(), &Self::get_dutch_auction(env.clone()));
-        dutch_auction_contract.fillOrder(&caller.clone(), &order_id.clone());
+        Self::run(&env, Action::Fill(caller, order_id, max_amount_out));
     }
 
     pub fn deploy_escrow_dest(
@@ -73,37 +121,209 @@ impl Resolver {
         maker: Address,
     ) {
         Self::only_owner(env.clone());
-        let _escrow_factory =
-            escrow_factory::Client::new(&env.clone(), &Self::get_escrow_factory(env.clone()));
-        _escrow_factory.deploy_dest(
-            &order_id.clone(),
-            &hash_lock.clone(),
-            &token_out.clone(),
-            &amount_out.clone(),
-            &maker.clone(),
-            &caller.clone(), // executive_resolver
+        Self::run(
+            &env,
+            Action::DeployDest(DestDeployment {
+                caller,
+                order_id,
+                hash_lock,
+                token_out,
+                amount_out,
+                maker,
+            }),
         );
     }
 
-    pub fn withdraw(env: Env, escrow: Address, secret: BytesN<32>) {
-        let caller = env.current_contract_address();
-        let escrow = EscrowDestClient::new(&env, &escrow); // common function for both src and dest
-        escrow.withdraw(&secret, &caller);
+    /// Moves `amount` of `token` from `from` into the resolver's treasury.
+    pub fn deposit(env: Env, token: Address, from: Address, amount: i128) {
+        Self::only_owner(env.clone());
+        from.require_auth();
+        let this = env.current_contract_address();
+        token::Client::new(&env, &token).transfer(&from, &this, &amount);
+    }
+
+    /// Pays `amount` of `token` out of the treasury. Native tokens can't go
+    /// below the reserve kept for security deposits.
+    pub fn withdraw(env: Env, token: Address, to: Address, amount: i128) {
+        Self::only_owner(env.clone());
+        Self::run(&env, Action::Transfer(token, to, amount));
+    }
+
+    /// Pays out everything that can be withdrawn of `token` and returns the
+    /// amount.
+    pub fn sweep(env: Env, token: Address, to: Address) -> i128 {
+        Self::only_owner(env.clone());
+        let amount = Self::withdrawable(env.clone(), token.clone());
+        if amount > 0 {
+            Self::run(&env, Action::Transfer(token, to, amount));
+        }
+        amount
+    }
+
+    /// Native tokens `withdraw` and `sweep` leave in the contract so it can
+    /// keep paying security deposits.
+    pub fn set_native_reserve(env: Env, reserve: i128) {
+        Self::only_owner(env.clone());
+        if reserve < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::NativeReserve, &reserve);
+    }
+
+    pub fn get_native_reserve(env: Env) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::NativeReserve)
+            .unwrap_or(0)
+    }
+
+    /// Balance of `token` held by the contract.
+    pub fn inventory(env: Env, token: Address) -> i128 {
+        token::Client::new(&env, &token).balance(&env.current_contract_address())
+    }
+
+    /// Part of the inventory of `token` that can be paid out.
+    pub fn withdrawable(env: Env, token: Address) -> i128 {
+        let inventory = Self::inventory(env.clone(), token.clone());
+        if token == Self::get_native_token(&env) {
+            return (inventory - Self::get_native_reserve(env)).max(0);
+        }
+        inventory
+    }
+
+    /// Native tokens available for security deposits, and how many more
+    /// escrows they pay for, `u32::MAX` if the factory takes no deposit.
+    pub fn security_deposits(env: Env) -> (i128, u32) {
+        let factory = EscrowFactoryClient::new(&env, &Self::get_escrow_factory(env.clone()));
+        let available = Self::inventory(env.clone(), factory.get_native_token());
+        let escrows = match i128::try_from(factory.get_security_deposit()) {
+            Ok(0) => u32::MAX,
+            Ok(deposit) => u32::try_from(available / deposit).unwrap_or(u32::MAX),
+            Err(_) => 0,
+        };
+        (available, escrows)
+    }
+
+    pub fn get_order_record(env: Env, order_id: BytesN<32>) -> OrderRecord {
+        accounting::get_record(&env, &order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound))
     }
-    pub fn public_withdraw(env: Env, escrow: Address, secret: BytesN<32>) {
-        let caller = env.current_contract_address();
-        let escrow = EscrowDestClient::new(&env, &escrow); // common function for both src and dest
-        escrow.public_withdraw(&secret, &caller);
+
+    /// Totals of all closed orders between `token_in` and `token_out`.
+    pub fn pair_pnl(env: Env, token_in: Option<Address>, token_out: Option<Address>) -> PnlTotals {
+        accounting::pair_totals(
+            &env,
+            Pair {
+                token_in,
+                token_out,
+            },
+        )
+    }
+
+    /// Totals per token pair of the orders closed in `period`, a day since
+    /// the unix epoch.
+    pub fn period_pnl(env: Env, period: u64) -> Map<Pair, PnlTotals> {
+        accounting::period_totals(&env, period)
+    }
+
+    /// Ids of the orders closed in `period`, to export their records, in
+    /// pages of `accounting::PAGE_SIZE` from `period_order_count`. An order
+    /// reopened since is listed again where it closes next, its record's
+    /// `closed_at` tells which period it counts in.
+    pub fn period_orders(env: Env, period: u64, page: u32) -> Vec<BytesN<32>> {
+        accounting::period_orders(&env, period, page)
+    }
+
+    pub fn period_order_count(env: Env, period: u64) -> u32 {
+        accounting::period_order_count(&env, period)
+    }
+
+    /// Books how the order's escrows were settled when someone else did it,
+    /// such as a public withdrawal. Anyone can call it.
+    pub fn sync_order(env: Env, order_id: BytesN<32>) -> OrderRecord {
+        if accounting::get_record(&env, &order_id).is_none() {
+            panic_with_error!(&env, Error::OrderNotFound);
+        }
+        Self::sync_src(&env, &order_id);
+        Self::sync_dest(&env, &order_id);
+        Self::get_order_record(env, order_id)
+    }
+
+    /// Lets `operator` run the escrow operations below, the owner always can.
+    pub fn add_operator(env: Env, operator: Address) {
+        Self::only_owner(env.clone());
+        let mut operators = Self::get_operators(env.clone());
+        if !operators.contains(&operator) {
+            operators.push_back(operator);
+            env.storage()
+                .persistent()
+                .set(&DataKey::Operators, &operators);
+        }
+    }
+
+    pub fn remove_operator(env: Env, operator: Address) {
+        Self::only_owner(env.clone());
+        let mut operators = Self::get_operators(env.clone());
+        if let Some(index) = operators.first_index_of(&operator) {
+            operators.remove(index);
+            env.storage()
+                .persistent()
+                .set(&DataKey::Operators, &operators);
+        }
     }
-    pub fn cancel(env: Env, escrow: Address) {
-        let caller = env.current_contract_address();
-        let escrow = EscrowDestClient::new(&env, &escrow); // common function for both src and dest
-        escrow.cancel(&caller);
+
+    pub fn get_operators(env: Env) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Operators)
+            .unwrap_or(Vec::new(&env))
     }
-    pub fn public_cancel(env: Env, escrow: Address) {
-        let caller = env.current_contract_address();
-        let escrow = EscrowSrcClient::new(&env, &escrow); // common function for both src and dest
-        escrow.public_cancel(&caller);
+
+    pub fn is_operator(env: Env, operator: Address) -> bool {
+        operator == Self::get_owner(env.clone()) || Self::get_operators(env).contains(&operator)
+    }
+
+    // Source escrows lock the maker's tokens, the resolver withdraws them
+    // once the secret is out and can cancel to refund the maker.
+
+    pub fn withdraw_src(env: Env, operator: Address, escrow: Address, secret: BytesN<32>) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::WithdrawSrc(escrow, secret));
+    }
+
+    pub fn public_withdraw_src(env: Env, operator: Address, escrow: Address, secret: BytesN<32>) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::PublicWithdrawSrc(escrow, secret));
+    }
+
+    pub fn cancel_src(env: Env, operator: Address, escrow: Address) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::CancelSrc(escrow));
+    }
+
+    pub fn public_cancel_src(env: Env, operator: Address, escrow: Address) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::PublicCancelSrc(escrow));
+    }
+
+    // Destination escrows lock the resolver's tokens for the maker, there is
+    // no public cancel on this side.
+
+    pub fn withdraw_dest(env: Env, operator: Address, escrow: Address, secret: BytesN<32>) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::WithdrawDest(escrow, secret));
+    }
+
+    pub fn public_withdraw_dest(env: Env, operator: Address, escrow: Address, secret: BytesN<32>) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::PublicWithdrawDest(escrow, secret));
+    }
+
+    pub fn cancel_dest(env: Env, operator: Address, escrow: Address) {
+        Self::only_operator(&env, &operator);
+        Self::run(&env, Action::CancelDest(escrow));
     }
 
     pub fn notify_relayer(
@@ -114,8 +334,122 @@ impl Resolver {
         caller: Address,
     ) {
         Self::only_owner(env.clone());
-        let _relayer = relayer::Client::new(&env.clone(), &Self::get_relayer(env.clone()));
-        _relayer.signal_share_secret(&escrow_src, &escrow_dest, &order_id, &caller);
+        Self::run(
+            &env,
+            Action::Notify(order_id, escrow_src, escrow_dest, caller),
+        );
+    }
+
+    /// Runs `actions` in order within one transaction, if any of them fails
+    /// none of them happened.
+    pub fn execute_batch(env: Env, actions: Vec<Action>) {
+        Self::only_owner(env.clone());
+        for action in actions.iter() {
+            Self::run(&env, action);
+        }
+    }
+
+    /// Sells `amount_in` of the first token of the path for the last one
+    /// through the router and returns what arrived, at least
+    /// `min_amount_out`. Put before `DeployDest` in a batch, it buys the
+    /// inventory the destination escrow is funded with.
+    pub fn swap(
+        env: Env,
+        path: Vec<Address>,
+        amount_in: i128,
+        min_amount_out: i128,
+        deadline: u64,
+    ) -> i128 {
+        Self::only_owner(env.clone());
+        Self::swap_through_router(
+            &env,
+            Swap {
+                path,
+                amount_in,
+                min_amount_out,
+                deadline,
+            },
+        )
+    }
+
+    /// Sets the AMM router swaps go through, a Soroswap router or anything
+    /// with the same interface.
+    pub fn set_router(env: Env, router: Address) {
+        Self::only_owner(env.clone());
+        env.storage().persistent().set(&DataKey::Router, &router);
+    }
+
+    pub fn get_router(env: Env) -> Address {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Router)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::RouterNotSet))
+    }
+
+    /// Borrows `amount` of the vault's asset for `order_id`. Put before
+    /// `DeployDest` in a batch, it funds the destination escrow with LP
+    /// capital instead of the resolver's own.
+    pub fn borrow(env: Env, order_id: BytesN<32>, amount: i128) {
+        Self::only_owner(env.clone());
+        Self::run(&env, Action::Borrow(order_id, amount));
+    }
+
+    /// Repays the loan for `order_id` with the vault's fee, and returns what
+    /// the vault took.
+    pub fn repay(env: Env, order_id: BytesN<32>) -> i128 {
+        Self::only_owner(env.clone());
+        Self::repay_vault(&env, order_id)
+    }
+
+    /// Bonds `amount` of the relayer's staking token out of the treasury,
+    /// registering the contract as a resolver without the relayer's owner.
+    pub fn bond(env: Env, token: Address, amount: i128) {
+        Self::only_owner(env.clone());
+        let this = env.current_contract_address();
+        let relayer = RelayerClient::new(&env, &Self::get_relayer(env.clone()));
+        Self::check_withdrawable(&env, &token, amount);
+        Self::authorize_transfer(&env, token, relayer.address.clone(), amount);
+        relayer.bond(&this, &this, &amount);
+    }
+
+    pub fn unbond(env: Env, amount: i128) {
+        Self::only_owner(env.clone());
+        RelayerClient::new(&env, &Self::get_relayer(env.clone()))
+            .unbond(&env.current_contract_address(), &amount);
+    }
+
+    /// Moves stake that finished unbonding back into the treasury.
+    pub fn withdraw_unbonded(env: Env) -> i128 {
+        Self::only_owner(env.clone());
+        RelayerClient::new(&env, &Self::get_relayer(env.clone()))
+            .withdraw_unbonded(&env.current_contract_address())
+    }
+
+    pub fn set_vault(env: Env, vault: Address) {
+        Self::only_owner(env.clone());
+        env.storage().persistent().set(&DataKey::Vault, &vault);
+    }
+
+    pub fn get_vault(env: Env) -> Address {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Vault)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::VaultNotSet))
+    }
+
+    /// Allows or forbids `Action::Call` to invoke `function` on `contract`.
+    pub fn set_call_allowed(env: Env, contract: Address, function: Symbol, allowed: bool) {
+        Self::only_owner(env.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::AllowedCall(contract, function), &allowed);
+    }
+
+    pub fn is_call_allowed(env: Env, contract: Address, function: Symbol) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AllowedCall(contract, function))
+            .unwrap_or(false)
     }
 
     pub fn get_owner(env: Env) -> Address {
@@ -137,6 +471,333 @@ impl Resolver {
         owner.require_auth();
     }
 
+    fn only_operator(env: &Env, operator: &Address) {
+        operator.require_auth();
+        if !Self::is_operator(env.clone(), operator.clone()) {
+            panic_with_error!(env, Error::NotOperator);
+        }
+    }
+
+    fn run(env: &Env, action: Action) {
+        let this = env.current_contract_address();
+        match action {
+            Action::Fill(caller, order_id, max_amount_out) => {
+                let auction = DutchAuctionClient::new(env, &Self::get_dutch_auction(env.clone()));
+                if caller == this {
+                    Self::authorize_deploy_src(env, &auction, &order_id);
+                }
+                auction.fillOrder(&caller, &order_id, &max_amount_out);
+                if caller == this {
+                    let token_in = auction.get_order(&order_id).tokenIn;
+                    let deposit = Self::security_deposit(env);
+                    accounting::update(env, &order_id, |record| {
+                        record.token_in = Some(token_in);
+                        record.deposits_posted += deposit;
+                        record.src = LegStatus::Locked;
+                    });
+                }
+            }
+            Action::DeployDest(dest) => {
+                let factory = EscrowFactoryClient::new(env, &Self::get_escrow_factory(env.clone()));
+                if dest.caller == this {
+                    Self::authorize_deploy_dest(env, &factory, &dest);
+                }
+                factory.deploy_dest(
+                    &dest.order_id,
+                    &dest.hash_lock,
+                    &dest.token_out,
+                    &dest.amount_out,
+                    &dest.maker,
+                    &dest.caller, // executive_resolver
+                );
+                if dest.caller == this {
+                    let deposit = Self::security_deposit(env);
+                    accounting::update(env, &dest.order_id, |record| {
+                        record.token_out = Some(dest.token_out);
+                        record.amount_out += dest.amount_out as i128;
+                        record.deposits_posted += deposit;
+                        record.dest = LegStatus::Locked;
+                    });
+                }
+            }
+            Action::Notify(order_id, escrow_src, escrow_dest, caller) => {
+                RelayerClient::new(env, &Self::get_relayer(env.clone())).signal_share_secret(
+                    &escrow_src,
+                    &escrow_dest,
+                    &order_id,
+                    &caller,
+                );
+            }
+            Action::WithdrawSrc(escrow, secret) => {
+                Self::settle_src(env, &escrow, || {
+                    EscrowSrcClient::new(env, &escrow).withdraw(&secret, &this)
+                });
+            }
+            Action::PublicWithdrawSrc(escrow, secret) => {
+                Self::settle_src(env, &escrow, || {
+                    EscrowSrcClient::new(env, &escrow).public_withdraw(&secret, &this)
+                });
+            }
+            Action::CancelSrc(escrow) => {
+                Self::settle_src(env, &escrow, || {
+                    EscrowSrcClient::new(env, &escrow).cancel(&this)
+                });
+            }
+            Action::PublicCancelSrc(escrow) => {
+                Self::settle_src(env, &escrow, || {
+                    EscrowSrcClient::new(env, &escrow).public_cancel(&this)
+                });
+            }
+            Action::WithdrawDest(escrow, secret) => {
+                Self::settle_dest(env, &escrow, || {
+                    EscrowDestClient::new(env, &escrow).withdraw(&secret, &this)
+                });
+            }
+            Action::PublicWithdrawDest(escrow, secret) => {
+                Self::settle_dest(env, &escrow, || {
+                    EscrowDestClient::new(env, &escrow).public_withdraw(&secret, &this)
+                });
+            }
+            Action::CancelDest(escrow) => {
+                Self::settle_dest(env, &escrow, || {
+                    EscrowDestClient::new(env, &escrow).cancel(&this)
+                });
+            }
+            Action::Transfer(token, to, amount) => {
+                Self::check_withdrawable(env, &token, amount);
+                token::Client::new(env, &token).transfer(&this, &to, &amount);
+            }
+            Action::Swap(swap) => {
+                Self::swap_through_router(env, swap);
+            }
+            Action::Borrow(order_id, amount) => {
+                LiquidityVaultClient::new(env, &Self::get_vault(env.clone()))
+                    .borrow(&this, &order_id, &amount);
+            }
+            Action::Repay(order_id) => {
+                Self::repay_vault(env, order_id);
+            }
+            Action::Call(contract, function, args) => {
+                if !Self::is_call_allowed(env.clone(), contract.clone(), function.clone()) {
+                    panic_with_error!(env, Error::CallNotAllowed);
+                }
+                env.invoke_contract::<Val>(&contract, &function, args);
+            }
+        }
+    }
+
+    fn swap_through_router(env: &Env, swap: Swap) -> i128 {
+        if swap.path.len() < 2 {
+            panic_with_error!(env, Error::InvalidPath);
+        }
+        let router = AmmRouterClient::new(env, &Self::get_router(env.clone()));
+        if swap.amount_in > Self::withdrawable(env.clone(), swap.path.get_unchecked(0)) {
+            panic_with_error!(env, Error::InsufficientBalance);
+        }
+        let this = env.current_contract_address();
+        let token_in = swap.path.get_unchecked(0);
+        let token_out = swap.path.last_unchecked();
+
+        // judged by what arrived, not by what the router reports
+        let before = Self::inventory(env.clone(), token_out.clone());
+
+        // the router moves our tokens into the first pair, authorized right
+        // before the call it is for
+        let pair = router.router_pair_for(&token_in, &swap.path.get_unchecked(1));
+        Self::authorize_transfer(env, token_in, pair, swap.amount_in);
+        router.swap_exact_tokens_for_tokens(
+            &swap.amount_in,
+            &swap.min_amount_out,
+            &swap.path,
+            &this,
+            &swap.deadline,
+        );
+        let received = Self::inventory(env.clone(), token_out) - before;
+        if received < swap.min_amount_out {
+            panic_with_error!(env, Error::SlippageExceeded);
+        }
+        received
+    }
+
+    fn repay_vault(env: &Env, order_id: BytesN<32>) -> i128 {
+        let this = env.current_contract_address();
+        let vault = LiquidityVaultClient::new(env, &Self::get_vault(env.clone()));
+        let owed = vault.owed(&this, &order_id);
+        Self::authorize_transfer(env, vault.get_asset(), vault.address.clone(), owed);
+        vault.repay(&this, &order_id)
+    }
+
+    // payouts only come out of the withdrawable part, the native reserve stays
+    fn check_withdrawable(env: &Env, token: &Address, amount: i128) {
+        if amount > Self::withdrawable(env.clone(), token.clone()) {
+            if *token == Self::get_native_token(env) {
+                panic_with_error!(env, Error::BelowReserve);
+            }
+            panic_with_error!(env, Error::InsufficientBalance);
+        }
+    }
+
+    // Contracts we call can't spend our tokens on their own, a `transfer`
+    // they make from us needs our auth one call deeper than their own.
+    fn authorize_transfer(env: &Env, token: Address, to: Address, amount: i128) {
+        env.authorize_as_current_contract(vec![env, Self::transfer_auth(env, token, to, amount)]);
+    }
+
+    // Filling as the executive resolver, the factory the auction calls needs
+    // our auth and the security deposit it takes from us.
+    fn authorize_deploy_src(env: &Env, auction: &DutchAuctionClient, order_id: &BytesN<32>) {
+        let this = env.current_contract_address();
+        let order = auction.get_order(order_id);
+        let factory = EscrowFactoryClient::new(env, &auction.get_escrow_factory());
+        let deposit = Self::transfer_auth(
+            env,
+            factory.get_native_token(),
+            factory.address.clone(),
+            factory.get_security_deposit() as i128,
+        );
+        env.authorize_as_current_contract(vec![
+            env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: factory.address.clone(),
+                    fn_name: Symbol::new(env, "deploy_src"),
+                    args: (
+                        order_id.clone(),
+                        order.hashLock,
+                        order.tokenIn,
+                        order.amountIn,
+                        order.maker,
+                        this,
+                        auction.address.clone(),
+                    )
+                        .into_val(env),
+                },
+                sub_invocations: vec![env, deposit],
+            }),
+        ]);
+    }
+
+    // We call `deploy_dest` ourselves, but the factory moves the security
+    // deposit and the locked tokens out of the contract.
+    fn authorize_deploy_dest(env: &Env, factory: &EscrowFactoryClient, dest: &DestDeployment) {
+        env.authorize_as_current_contract(vec![
+            env,
+            Self::transfer_auth(
+                env,
+                factory.get_native_token(),
+                factory.address.clone(),
+                factory.get_security_deposit() as i128,
+            ),
+            Self::transfer_auth(
+                env,
+                dest.token_out.clone(),
+                factory.get_escrow_address(&dest.order_id),
+                dest.amount_out as i128,
+            ),
+        ]);
+    }
+
+    fn transfer_auth(
+        env: &Env,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> InvokerContractAuthEntry {
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: token,
+                fn_name: Symbol::new(env, "transfer"),
+                args: (env.current_contract_address(), to, amount).into_val(env),
+            },
+            sub_invocations: vec![env],
+        })
+    }
+
+    /// Runs `op` on a src escrow of the auction's factory, then books how it
+    /// was settled.
+    fn settle_src(env: &Env, escrow: &Address, op: impl FnOnce()) {
+        let auction = DutchAuctionClient::new(env, &Self::get_dutch_auction(env.clone()));
+        let order_id = EscrowFactoryClient::new(env, &auction.get_escrow_factory())
+            .get_orderid_from_escrow(escrow);
+        op();
+        Self::sync_src(env, &order_id);
+    }
+
+    /// Runs `op` on a dest escrow of our factory, then books how it was
+    /// settled.
+    fn settle_dest(env: &Env, escrow: &Address, op: impl FnOnce()) {
+        let order_id = EscrowFactoryClient::new(env, &Self::get_escrow_factory(env.clone()))
+            .get_orderid_from_escrow(escrow);
+        op();
+        Self::sync_dest(env, &order_id);
+    }
+
+    // A locked src leg whose escrow, as the factory reports it, was settled.
+    // Withdrawals pay the order's input to us as its executive resolver, the
+    // deposit goes to whoever settled it.
+    fn sync_src(env: &Env, order_id: &BytesN<32>) {
+        let Some(record) = accounting::get_record(env, order_id) else {
+            return;
+        };
+        if record.src != LegStatus::Locked {
+            return;
+        }
+        let auction = DutchAuctionClient::new(env, &Self::get_dutch_auction(env.clone()));
+        let escrow = EscrowFactoryClient::new(env, &auction.get_escrow_factory())
+            .get_escrow_from_orderid(order_id);
+        let Some(settlement) = EscrowSrcClient::new(env, &escrow).get_settlement() else {
+            return;
+        };
+        let amount_in = auction.get_order(order_id).amountIn as i128;
+        let deposit = Self::returned_deposit(env, &settlement);
+        accounting::update(env, order_id, |record| {
+            record.src = settlement.kind.into();
+            if record.src == LegStatus::Withdrawn {
+                record.amount_in += amount_in;
+            }
+            record.deposits_returned += deposit;
+        });
+    }
+
+    // Same for the dest leg, a cancel refunds what we locked.
+    fn sync_dest(env: &Env, order_id: &BytesN<32>) {
+        let Some(record) = accounting::get_record(env, order_id) else {
+            return;
+        };
+        if record.dest != LegStatus::Locked {
+            return;
+        }
+        let escrow = EscrowFactoryClient::new(env, &Self::get_escrow_factory(env.clone()))
+            .get_escrow_from_orderid(order_id);
+        let Some(settlement) = EscrowDestClient::new(env, &escrow).get_settlement() else {
+            return;
+        };
+        let deposit = Self::returned_deposit(env, &settlement);
+        accounting::update(env, order_id, |record| {
+            record.dest = settlement.kind.into();
+            if record.dest == LegStatus::Cancelled {
+                record.amount_out = 0;
+            }
+            record.deposits_returned += deposit;
+        });
+    }
+
+    fn returned_deposit(env: &Env, settlement: &Settlement) -> i128 {
+        if settlement.caller == env.current_contract_address() {
+            return Self::security_deposit(env);
+        }
+        0
+    }
+
+    fn security_deposit(env: &Env) -> i128 {
+        EscrowFactoryClient::new(env, &Self::get_escrow_factory(env.clone())).get_security_deposit()
+            as i128
+    }
+
+    fn get_native_token(env: &Env) -> Address {
+        EscrowFactoryClient::new(env, &Self::get_escrow_factory(env.clone())).get_native_token()
+    }
+
     fn get_dutch_auction(env: Env) -> Address {
         env.storage()
             .persistent()
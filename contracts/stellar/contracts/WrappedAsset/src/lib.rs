@@ -0,0 +1,166 @@
+#![no_std]
+use fusionx_types::interfaces::WrappedTokensClient;
+use fusionx_types::Error;
+use soroban_sdk::{
+    contract, contractimpl, contracttype, panic_with_error, symbol_short, token,
+    token::TokenInterface, Address, Env, String,
+};
+
+// longest name or symbol of the underlying token we can prefix
+const MAX_METADATA_LEN: usize = 128;
+
+/// SEP-41 view of one token's balances in WrappedTokens, deployed by
+/// `WrappedTokens::deploy_wrapped_asset`. Balances and allowances live in
+/// WrappedTokens, this contract checks auth and emits the token events.
+#[contract]
+pub struct WrappedAsset;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum DataKey {
+    WrappedTokens,
+    Token, // underlying token
+}
+
+#[contractimpl]
+impl WrappedAsset {
+    pub fn __constructor(env: Env, wrapped_tokens: Address, token: Address) {
+        env.storage()
+            .instance()
+            .set(&DataKey::WrappedTokens, &wrapped_tokens);
+        env.storage().instance().set(&DataKey::Token, &token);
+    }
+
+    pub fn get_wrapped_tokens(env: Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::WrappedTokens)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    pub fn get_token(env: Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Token)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for WrappedAsset {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        let allowance = Self::wrapped_tokens(&env).get_allowance(
+            &Self::get_token(env.clone()),
+            &from,
+            &spender,
+        );
+        Self::to_i128(&env, allowance)
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        Self::wrapped_tokens(&env).asset_approve(
+            &Self::get_token(env.clone()),
+            &from,
+            &spender,
+            &Self::to_u128(&env, amount),
+            &expiration_ledger,
+        );
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        let balance = Self::wrapped_tokens(&env).get_balance(&Self::get_token(env.clone()), &id);
+        Self::to_i128(&env, balance)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::wrapped_tokens(&env).asset_transfer(
+            &Self::get_token(env.clone()),
+            &from,
+            &to,
+            &Self::to_u128(&env, amount),
+        );
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::wrapped_tokens(&env).asset_transfer_from(
+            &Self::get_token(env.clone()),
+            &spender,
+            &from,
+            &to,
+            &Self::to_u128(&env, amount),
+        );
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        Self::wrapped_tokens(&env).asset_burn(
+            &Self::get_token(env.clone()),
+            &from,
+            &Self::to_u128(&env, amount),
+        );
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        Self::wrapped_tokens(&env).asset_burn_from(
+            &Self::get_token(env.clone()),
+            &spender,
+            &from,
+            &Self::to_u128(&env, amount),
+        );
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        Self::underlying(&env).decimals()
+    }
+
+    fn name(env: Env) -> String {
+        Self::prefixed(&env, "Wrapped ", Self::underlying(&env).name())
+    }
+
+    fn symbol(env: Env) -> String {
+        Self::prefixed(&env, "w", Self::underlying(&env).symbol())
+    }
+}
+
+impl WrappedAsset {
+    fn wrapped_tokens(env: &Env) -> WrappedTokensClient<'_> {
+        WrappedTokensClient::new(env, &Self::get_wrapped_tokens(env.clone()))
+    }
+
+    fn underlying(env: &Env) -> token::Client<'_> {
+        token::Client::new(env, &Self::get_token(env.clone()))
+    }
+
+    fn to_u128(env: &Env, amount: i128) -> u128 {
+        u128::try_from(amount).unwrap_or_else(|_| panic_with_error!(env, Error::InvalidAmount))
+    }
+
+    fn to_i128(env: &Env, amount: u128) -> i128 {
+        i128::try_from(amount).unwrap_or_else(|_| panic_with_error!(env, Error::InvalidAmount))
+    }
+
+    fn prefixed(env: &Env, prefix: &str, value: String) -> String {
+        let len = prefix.len() + value.len() as usize;
+        if len > MAX_METADATA_LEN {
+            return value;
+        }
+        let mut buf = [0u8; MAX_METADATA_LEN];
+        buf[..prefix.len()].copy_from_slice(prefix.as_bytes());
+        value.copy_into_slice(&mut buf[prefix.len()..len]);
+        String::from_bytes(env, &buf[..len])
+    }
+}
@@ -1,5 +1,37 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, BytesN, Env};
+#![allow(non_snake_case)]
+// permit takes its full payload as arguments, the generated client repeats them
+#![allow(clippy::too_many_arguments)]
+use fusionx_types::{Error, PermitSigner, TokenTransfer, WebAuthnSignature};
+use soroban_sdk::{
+    contract, contractimpl, contracttype, crypto::Hash, panic_with_error, token, vec, xdr::ToXdr,
+    Address, Bytes, BytesN, Env, IntoVal, Vec,
+};
+
+// prefix of the permit domain separator, bump the version if the encoding changes
+const PERMIT_DOMAIN: &[u8] = b"FusionX WrappedTokens permit v1";
+// expiration of allowances set through permit and approve, which never expire
+const NO_EXPIRATION: u32 = u32::MAX;
+// separates permit_transfer_digest from permit_digest
+const TRANSFER_TAG: &[u8] = b"transfer";
+
+// EIP-712 typed data of permits signed by EVM wallets
+const EIP712_DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,bytes32 salt)";
+const EIP712_PERMIT_TYPE: &[u8] =
+    b"Permit(string token,string owner,string spender,uint256 amount,uint256 nonce,uint256 deadline)";
+const EIP712_NAME: &[u8] = b"FusionX WrappedTokens";
+const EIP712_VERSION: &[u8] = b"1";
+// length of a G... or C... strkey
+const STRKEY_LEN: usize = 56;
+
+// longest webauthn client data json we parse
+const MAX_CLIENT_DATA_LEN: usize = 1024;
+const WEBAUTHN_GET: &[u8] = b"webauthn.get";
+// authenticator data is the rp id hash, flags, then the sign count
+const AUTHENTICATOR_FLAGS: u32 = 32;
+const USER_PRESENT: u8 = 0x01;
+const USER_VERIFIED: u8 = 0x04;
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
 
 #[contract]
 pub struct WrappedTokens;
@@ -9,83 +41,404 @@ pub struct WrappedTokens;
 pub enum DataKey {
     Balance(Address, Address),            // (token, user) -> balance
     Allowance(Address, Address, Address), // (token, owner, spender) -> allowance
-    SignatureUsed(Address, BytesN<32>),   // (owner, hash) -> bool
+    NonceBitmap(Address, u64),            // (owner, nonce >> 7) -> used nonce bits
+    WrappedAssetWasmHash,                 // wasm hash of the uploaded SEP-41 wrapped asset
+    WrappedAsset(Address),                // token -> its SEP-41 wrapped asset contract
+    PermitSigner(Address),                // owner -> secp256k1 or secp256r1 permit signer
+    TotalSupply(Address),                 // token -> sum of all wrapped balances
+}
+
+/// An allowance and the last ledger it can be spent in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: u128,
+    pub expiration_ledger: u32,
+}
+
+/// Underlying held against the wrapped supply of a token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Reserves {
+    pub held: u128,    // underlying balance of this contract
+    pub supply: u128,  // wrapped balances owed to users
+    pub surplus: i128, // held - supply, negative when the token is undercollateralized
 }
 
 #[contractimpl]
 impl WrappedTokens {
+    pub fn initialize(env: Env, wrapped_asset_wasm_hash: BytesN<32>) {
+        env.storage()
+            .instance()
+            .set(&DataKey::WrappedAssetWasmHash, &wrapped_asset_wasm_hash);
+    }
+
+    /// Sets `spender`'s allowance on `owner`'s balance to `amount`.
+    ///
+    /// For account owners (G...) the signature must be over `permit_digest`
+    /// of the same arguments by the account's own ed25519 key. Contract
+    /// owners authorize the digest through their `__check_auth` instead, and
+    /// `public_key` and `signature` are ignored.
+    ///
+    /// Each `nonce` can be used once per owner, in any order. Nonces are
+    /// tracked in 128 bit words, so counting up from 0 keeps replay protection
+    /// to one storage entry per 128 permits.
     pub fn permit(
         env: Env,
-        token: Address, // Mock token
-        owner: Address, // Alice
-        spender: Address, // Alice
-        amount: u128, // 
-        public_key: BytesN<32>, // Public address
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64, // last ledger timestamp the permit can be used at
+        public_key: BytesN<32>,
         signature: BytesN<64>,
-        hash: BytesN<32>,
     ) {
-        if Self::isSignatureUsed(env.clone(), owner.clone(), hash.clone()) {
-            panic!("Invalid signature hash");
+        if env.ledger().timestamp() > deadline {
+            panic_with_error!(&env, Error::PermitExpired);
         }
+        let hash = Self::permit_digest(
+            env.clone(),
+            token.clone(),
+            owner.clone(),
+            spender.clone(),
+            amount,
+            nonce,
+            deadline,
+        );
+        Self::use_nonce(&env, &owner, nonce);
+        Self::check_signature(&env, &owner, hash, &public_key, &signature);
+
+        Self::write_allowance(&env, token, owner, spender, amount, NO_EXPIRATION);
+    }
+
+    /// Pays `amount` of `token` out of `owner`'s wrapped balance to `to`, as
+    /// signed by the owner over `permit_transfer_digest`. Unlike `permit` no
+    /// allowance is left behind, the signature is spent on this one transfer.
+    /// `spender` has to authorize the call and the signature is checked as
+    /// in `permit`.
+    pub fn permit_transfer_from(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        to: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    ) {
+        let transfers = vec![&env, TokenTransfer { token, to, amount }];
+        Self::permit_batch_transfer_from(
+            env, owner, spender, transfers, nonce, deadline, public_key, signature,
+        );
+    }
 
+    /// `permit_transfer_from` of several tokens and recipients, such as the
+    /// escrows of several orders, under one signature.
+    pub fn permit_batch_transfer_from(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        transfers: Vec<TokenTransfer>,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    ) {
+        spender.require_auth();
+        Self::check_permit_transfer(
+            env.clone(),
+            owner.clone(),
+            spender,
+            transfers.clone(),
+            nonce,
+            deadline,
+            public_key,
+            signature,
+        );
+        Self::use_nonce(&env, &owner, nonce);
+
+        for transfer in transfers.iter() {
+            if transfer.amount == 0 {
+                panic_with_error!(&env, Error::InvalidAmount);
+            }
+            Self::burn_balance(&env, &transfer.token, &owner, transfer.amount);
+            token::Client::new(&env, &transfer.token).transfer(
+                &env.current_contract_address(),
+                &transfer.to,
+                &(transfer.amount as i128),
+            );
+        }
+    }
+
+    /// Fails unless `permit_batch_transfer_from` would accept the signature
+    /// right now, without using its nonce.
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_permit_transfer(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        transfers: Vec<TokenTransfer>,
+        nonce: u64,
+        deadline: u64,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    ) {
+        if env.ledger().timestamp() > deadline {
+            panic_with_error!(&env, Error::PermitExpired);
+        }
+        if Self::is_nonce_used(env.clone(), owner.clone(), nonce) {
+            panic_with_error!(&env, Error::NonceUsed);
+        }
+        let hash = Self::permit_transfer_digest(
+            env.clone(),
+            owner.clone(),
+            spender,
+            transfers,
+            nonce,
+            deadline,
+        );
+        Self::check_signature(&env, &owner, hash, &public_key, &signature);
+    }
+
+    /// The 32 bytes the owner signs for a transfer: sha256(domain_separator
+    /// || "transfer" || xdr(owner) || xdr(spender) || xdr(token) || xdr(to)
+    /// || amount for each transfer || nonce || deadline), with the integers
+    /// big endian.
+    pub fn permit_transfer_digest(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        transfers: Vec<TokenTransfer>,
+        nonce: u64,
+        deadline: u64,
+    ) -> BytesN<32> {
+        let mut data: Bytes = Self::domain_separator(env.clone()).into();
+        data.extend_from_slice(TRANSFER_TAG);
+        data.append(&owner.to_xdr(&env));
+        data.append(&spender.to_xdr(&env));
+        for transfer in transfers.iter() {
+            data.append(&transfer.token.to_xdr(&env));
+            data.append(&transfer.to.to_xdr(&env));
+            data.extend_from_array(&transfer.amount.to_be_bytes());
+        }
+        data.extend_from_array(&nonce.to_be_bytes());
+        data.extend_from_array(&deadline.to_be_bytes());
+        env.crypto().sha256(&data).into()
+    }
+
+    /// sha256(PERMIT_DOMAIN || network id || xdr(contract address)), ties
+    /// permits to this contract on this network.
+    pub fn domain_separator(env: Env) -> BytesN<32> {
+        let mut data = Bytes::from_slice(&env, PERMIT_DOMAIN);
+        data.append(&env.ledger().network_id().into());
+        data.append(&env.current_contract_address().to_xdr(&env));
+        env.crypto().sha256(&data).into()
+    }
+
+    /// The 32 bytes the owner signs: sha256(domain_separator || xdr(token)
+    /// || xdr(owner) || xdr(spender) || amount || nonce || deadline), with
+    /// the integers big endian.
+    pub fn permit_digest(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+    ) -> BytesN<32> {
+        let mut data: Bytes = Self::domain_separator(env.clone()).into();
+        data.append(&token.to_xdr(&env));
+        data.append(&owner.to_xdr(&env));
+        data.append(&spender.to_xdr(&env));
+        data.extend_from_array(&amount.to_be_bytes());
+        data.extend_from_array(&nonce.to_be_bytes());
+        data.extend_from_array(&deadline.to_be_bytes());
+        env.crypto().sha256(&data).into()
+    }
+
+    /// `permit` signed by the owner's secp256k1 permit signer, an EVM wallet
+    /// signing `evm_permit_digest` as EIP-712 typed data. `signature` is
+    /// r || s and `recovery_id` is v - 27.
+    pub fn permit_secp256k1(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        signature: BytesN<64>,
+        recovery_id: u32,
+    ) {
+        if env.ledger().timestamp() > deadline {
+            panic_with_error!(&env, Error::PermitExpired);
+        }
+        let hash = Self::eip712_hash(&env, &token, &owner, &spender, amount, nonce, deadline);
+        Self::use_nonce(&env, &owner, nonce);
+
+        let PermitSigner::Secp256k1(signer) = Self::get_permit_signer(env.clone(), owner.clone())
+        else {
+            panic_with_error!(&env, Error::InvalidPublicKey);
+        };
+        let public_key = env
+            .crypto()
+            .secp256k1_recover(&hash, &signature, recovery_id);
+        if Self::evm_address(&env, &public_key) != signer {
+            panic_with_error!(&env, Error::InvalidPublicKey);
+        }
+
+        Self::write_allowance(&env, token, owner, spender, amount, NO_EXPIRATION);
+    }
+
+    /// `permit` signed by the owner's secp256r1 permit signer, a passkey
+    /// asserting with `permit_digest` as the WebAuthn challenge. The assertion
+    /// must have the user both present and verified.
+    pub fn permit_secp256r1(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+        signature: WebAuthnSignature,
+    ) {
+        if env.ledger().timestamp() > deadline {
+            panic_with_error!(&env, Error::PermitExpired);
+        }
+        let digest = Self::permit_digest(
+            env.clone(),
+            token.clone(),
+            owner.clone(),
+            spender.clone(),
+            amount,
+            nonce,
+            deadline,
+        );
+        Self::use_nonce(&env, &owner, nonce);
+
+        let PermitSigner::Secp256r1(public_key) =
+            Self::get_permit_signer(env.clone(), owner.clone())
+        else {
+            panic_with_error!(&env, Error::InvalidPublicKey);
+        };
+        Self::check_client_data(&env, &signature.client_data_json, &digest);
+        Self::check_authenticator_data(&env, &signature.authenticator_data);
+        // the authenticator signs authenticator data || sha256(client data)
+        let mut signed = signature.authenticator_data;
+        signed.extend_from_array(&env.crypto().sha256(&signature.client_data_json).to_array());
+        env.crypto().secp256r1_verify(
+            &public_key,
+            &env.crypto().sha256(&signed),
+            &signature.signature,
+        );
+
+        Self::write_allowance(&env, token, owner, spender, amount, NO_EXPIRATION);
+    }
+
+    /// EIP-712 hash EVM wallets sign for `permit_secp256k1`. The domain is
+    /// `EIP712Domain(string name,string version,bytes32 salt)` with name
+    /// "FusionX WrappedTokens", version "1" and `domain_separator` as salt,
+    /// the message `Permit(string token,string owner,string spender,uint256
+    /// amount,uint256 nonce,uint256 deadline)` with addresses as strkeys.
+    pub fn evm_permit_digest(
+        env: Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+    ) -> BytesN<32> {
+        Self::eip712_hash(&env, &token, &owner, &spender, amount, nonce, deadline).into()
+    }
+
+    /// Lets `signer` sign permits for `owner`'s balances, replacing any
+    /// previous signer. Owners without a Stellar key of their own, such as
+    /// EVM makers, authorize this once from the account holding their balance.
+    pub fn set_permit_signer(env: Env, owner: Address, signer: PermitSigner) {
+        owner.require_auth();
         env.storage()
             .persistent()
-            .set(&DataKey::SignatureUsed(owner.clone(), hash.clone()), &true);
+            .set(&DataKey::PermitSigner(owner), &signer);
+    }
 
-        env.crypto()
-            .ed25519_verify(&public_key, &hash.clone().into(), &signature);
+    pub fn remove_permit_signer(env: Env, owner: Address) {
+        owner.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PermitSigner(owner));
+    }
 
+    pub fn get_permit_signer(env: Env, owner: Address) -> PermitSigner {
         env.storage()
             .persistent()
-            .set(&DataKey::Allowance(token, owner, spender), &amount);
+            .get(&DataKey::PermitSigner(owner))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::PermitSignerNotSet))
+    }
+
+    /// Revokes a signed but unsubmitted permit.
+    pub fn invalidate_nonce(env: Env, owner: Address, nonce: u64) {
+        owner.require_auth();
+        let (word_pos, bit) = Self::nonce_position(nonce);
+        Self::set_nonce_bits(&env, &owner, word_pos, bit);
     }
 
-    pub fn isSignatureUsed(env: Env, owner: Address, hash_bytes: BytesN<32>) -> bool {
-        let key = DataKey::SignatureUsed(owner, hash_bytes);
-        env.storage().persistent().has(&key)
+    /// Marks every nonce `(word_pos << 7) + i` with bit `i` set in `mask` as
+    /// used.
+    pub fn invalidate_unordered_nonces(env: Env, owner: Address, word_pos: u64, mask: u128) {
+        owner.require_auth();
+        Self::set_nonce_bits(&env, &owner, word_pos, mask);
+    }
+
+    /// Used nonces `(word_pos << 7)..(word_pos << 7) + 128` of `owner`, bit `i`
+    /// for nonce `(word_pos << 7) + i`.
+    pub fn nonce_bitmap(env: Env, owner: Address, word_pos: u64) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::NonceBitmap(owner, word_pos))
+            .unwrap_or(0)
+    }
+
+    pub fn is_nonce_used(env: Env, owner: Address, nonce: u64) -> bool {
+        let (word_pos, bit) = Self::nonce_position(nonce);
+        Self::nonce_bitmap(env, owner, word_pos) & bit != 0
     }
 
     pub fn deposit(env: Env, token: Address, amount: u128, caller: Address) {
         caller.require_auth();
 
         if amount == 0 {
-            panic!("Invalid Amount");
+            panic_with_error!(&env, Error::InvalidAmount);
         }
 
-        // Transfer tokens from caller to this contract
+        // Transfer tokens from caller to this contract under the caller's auth,
+        // so Stellar Asset Contracts (XLM, classic assets) need no approve
+        // first. What arrived is credited, so fee-on-transfer tokens can't
+        // wrap more than they deliver.
         let token_client = token::Client::new(&env, &token);
-        token_client.transfer_from(
-            &env.current_contract_address(),
-            &caller,
-            &env.current_contract_address(),
-            &(amount as i128),
-        );
-
-        // Update balance
-        let current_balance = Self::get_balance(env.clone(), token.clone(), caller.clone());
-        let new_balance = current_balance + amount;
+        let this = env.current_contract_address();
+        let held = token_client.balance(&this);
+        token_client.transfer(&caller, &this, &(amount as i128));
+        let received = token_client.balance(&this) - held;
+        if received <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(token, caller), &new_balance);
+        Self::mint_balance(&env, &token, &caller, received as u128);
     }
 
     /// Withdraw tokens from the wrapper
     pub fn withdraw(env: Env, token: Address, amount: u128, caller: Address) {
         caller.require_auth();
         if amount == 0 {
-            panic!("Invalid amount");
+            panic_with_error!(&env, Error::InvalidAmount);
         }
-        let current_balance = Self::get_balance(env.clone(), token.clone(), caller.clone());
-        if amount > current_balance {
-            panic!("Invalid balance");
-        }
-        // Update balance
-        let new_balance = current_balance - amount;
-        env.storage().persistent().set(
-            &DataKey::Balance(token.clone(), caller.clone()),
-            &new_balance,
-        );
+        Self::burn_balance(&env, &token, &caller, amount);
 
         // Transfer tokens from this contract to caller
         let token_client = token::Client::new(&env, &token);
@@ -98,12 +451,10 @@ impl WrappedTokens {
         let user_balance = Self::get_balance(env.clone(), token.clone(), caller.clone());
 
         if amount > user_balance {
-            panic!("Invalid amount");
+            panic_with_error!(&env, Error::InvalidAmount);
         }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Allowance(token, caller, to), &amount);
+        Self::write_allowance(&env, token, caller, to, amount, NO_EXPIRATION);
     }
 
     /// Transfer tokens within the wrapper (internal transfer)
@@ -111,93 +462,458 @@ impl WrappedTokens {
         caller.require_auth();
 
         if amount == 0 {
-            panic!("Invalid amount");
+            panic_with_error!(&env, Error::InvalidAmount);
         }
 
-        let sender_balance = Self::get_balance(env.clone(), token.clone(), caller.clone());
+        Self::move_balance(&env, &token, &caller, &to, amount);
+    }
 
-        if amount > sender_balance {
-            panic!("Invalid balance");
+    /// Transfer tokens from one address to another using allowance
+    pub fn transfer_from(
+        env: Env,
+        token: Address,
+        amount: u128,
+        from: Address,
+        to: Address,
+        caller: Address,
+    ) {
+        caller.require_auth();
+
+        if amount == 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
         }
 
-        // Update sender balance
-        let new_sender_balance = sender_balance - amount;
-        env.storage().persistent().set(
-            &DataKey::Balance(token.clone(), caller),
-            &new_sender_balance,
+        Self::spend_allowance(&env, &token, &from, &caller, amount);
+        Self::move_balance(&env, &token, &from, &to, amount);
+    }
+
+    /// Spendable allowance, zero once it has expired.
+    pub fn get_allowance(env: Env, token: Address, owner: Address, spender: Address) -> u128 {
+        let allowance = Self::read_allowance(&env, token, owner, spender);
+        if allowance.expiration_ledger < env.ledger().sequence() {
+            return 0;
+        }
+        allowance.amount
+    }
+
+    pub fn get_balance(env: Env, token: Address, user: Address) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balance(token, user))
+            .unwrap_or(0)
+    }
+
+    /// Sum of the wrapped balances of `token`.
+    pub fn total_supply(env: Env, token: Address) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TotalSupply(token))
+            .unwrap_or(0)
+    }
+
+    /// Compares the underlying this contract holds with the wrapped supply of
+    /// `token`. Underlying sent here without a deposit or left behind by
+    /// `asset_burn` shows up as surplus, a deficit means users are owed more
+    /// than can be withdrawn.
+    pub fn reserves(env: Env, token: Address) -> Reserves {
+        let held = token::Client::new(&env, &token).balance(&env.current_contract_address());
+        let supply = Self::total_supply(env, token);
+        Reserves {
+            held: held as u128,
+            supply,
+            surplus: held - supply as i128,
+        }
+    }
+
+    /// Deploys the SEP-41 token that moves the wrapped balances of `token`, or
+    /// returns it if it already exists. Anyone can deploy it, the code is
+    /// always the uploaded wrapped asset wasm.
+    pub fn deploy_wrapped_asset(env: Env, token: Address) -> Address {
+        let key = DataKey::WrappedAsset(token.clone());
+        if let Some(asset) = env.storage().persistent().get(&key) {
+            return asset;
+        }
+        let salt = env.crypto().sha256(&token.clone().to_xdr(&env));
+        let asset = env.deployer().with_current_contract(salt).deploy_v2(
+            Self::get_wrapped_asset_wasm_hash(env.clone()),
+            (env.current_contract_address(), token),
         );
+        env.storage().persistent().set(&key, &asset);
+        asset
+    }
 
-        // Update recipient balance
-        let recipient_balance = Self::get_balance(env.clone(), token.clone(), to.clone());
-        let new_recipient_balance = recipient_balance + amount;
+    pub fn get_wrapped_asset(env: Env, token: Address) -> Address {
         env.storage()
             .persistent()
-            .set(&DataKey::Balance(token, to), &new_recipient_balance);
+            .get(&DataKey::WrappedAsset(token))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::WrappedAssetNotDeployed))
     }
 
-    /// Transfer tokens from one address to another using allowance
-    pub fn transfer_from(
+    pub fn get_wrapped_asset_wasm_hash(env: Env) -> BytesN<32> {
+        env.storage()
+            .instance()
+            .get(&DataKey::WrappedAssetWasmHash)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    // The asset_* functions back the SEP-41 interface of the wrapped asset of
+    // `token`. Only that contract can call them and it checks the owner's auth.
+
+    pub fn asset_approve(
         env: Env,
         token: Address,
+        from: Address,
+        spender: Address,
         amount: u128,
+        expiration_ledger: u32,
+    ) {
+        Self::only_wrapped_asset(&env, &token);
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            panic_with_error!(&env, Error::InvalidExpirationLedger);
+        }
+        Self::write_allowance(&env, token, from, spender, amount, expiration_ledger);
+    }
+
+    pub fn asset_transfer(env: Env, token: Address, from: Address, to: Address, amount: u128) {
+        Self::only_wrapped_asset(&env, &token);
+        Self::move_balance(&env, &token, &from, &to, amount);
+    }
+
+    pub fn asset_transfer_from(
+        env: Env,
+        token: Address,
+        spender: Address,
         from: Address,
         to: Address,
-        caller: Address,
+        amount: u128,
     ) {
-        caller.require_auth();
+        Self::only_wrapped_asset(&env, &token);
+        Self::spend_allowance(&env, &token, &from, &spender, amount);
+        Self::move_balance(&env, &token, &from, &to, amount);
+    }
 
-        if amount == 0 {
-            panic!("Invalid amount");
+    /// Burnt balances are gone for good, the underlying stays locked here as
+    /// surplus of `reserves`.
+    pub fn asset_burn(env: Env, token: Address, from: Address, amount: u128) {
+        Self::only_wrapped_asset(&env, &token);
+        Self::burn_balance(&env, &token, &from, amount);
+    }
+
+    pub fn asset_burn_from(
+        env: Env,
+        token: Address,
+        spender: Address,
+        from: Address,
+        amount: u128,
+    ) {
+        Self::only_wrapped_asset(&env, &token);
+        Self::spend_allowance(&env, &token, &from, &spender, amount);
+        Self::burn_balance(&env, &token, &from, amount);
+    }
+
+    // ed25519 key of an account address, None for contract addresses. The
+    // address xdr is ScVal::Address tag, ScAddress type, key type, then the key.
+    fn account_key(env: &Env, address: &Address) -> Option<BytesN<32>> {
+        let xdr = address.to_xdr(env);
+        if xdr.len() != 44 || xdr.slice(4..12) != Bytes::from_array(env, &[0; 8]) {
+            return None;
         }
+        xdr.slice(12..).try_into().ok()
+    }
 
-        // Check allowance
-        let current_allowance =
-            Self::get_allowance(env.clone(), token.clone(), from.clone(), caller.clone());
+    // ed25519 signature of an account owner's own key over `hash`, or the
+    // contract owner's auth of it
+    fn check_signature(
+        env: &Env,
+        owner: &Address,
+        hash: BytesN<32>,
+        public_key: &BytesN<32>,
+        signature: &BytesN<64>,
+    ) {
+        match Self::account_key(env, owner) {
+            Some(owner_key) => {
+                if *public_key != owner_key {
+                    panic_with_error!(env, Error::InvalidPublicKey);
+                }
+                env.crypto()
+                    .ed25519_verify(public_key, &hash.into(), signature);
+            }
+            None => owner.require_auth_for_args(vec![env, hash.into_val(env)]),
+        }
+    }
 
-        if amount > current_allowance {
-            panic!("Invalid allowance");
+    fn eip712_hash(
+        env: &Env,
+        token: &Address,
+        owner: &Address,
+        spender: &Address,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+    ) -> Hash<32> {
+        let mut domain = Bytes::from_array(env, &Self::keccak(env, EIP712_DOMAIN_TYPE));
+        domain.extend_from_array(&Self::keccak(env, EIP712_NAME));
+        domain.extend_from_array(&Self::keccak(env, EIP712_VERSION));
+        domain.append(&Self::domain_separator(env.clone()).into());
+
+        // strings are encoded as their keccak, integers as uint256 words
+        let mut permit = Bytes::from_array(env, &Self::keccak(env, EIP712_PERMIT_TYPE));
+        for address in [token, owner, spender] {
+            let mut strkey = [0u8; STRKEY_LEN];
+            address.to_string().copy_into_slice(&mut strkey);
+            permit.extend_from_array(&Self::keccak(env, &strkey));
+        }
+        for value in [amount, nonce.into(), deadline.into()] {
+            let mut word = [0u8; 32];
+            word[16..].copy_from_slice(&value.to_be_bytes());
+            permit.extend_from_array(&word);
         }
 
-        // Check balance
-        let from_balance = Self::get_balance(env.clone(), token.clone(), from.clone());
+        let mut data = Bytes::from_array(env, &[0x19, 0x01]);
+        data.extend_from_array(&env.crypto().keccak256(&domain).to_array());
+        data.extend_from_array(&env.crypto().keccak256(&permit).to_array());
+        env.crypto().keccak256(&data)
+    }
 
-        if amount > from_balance {
-            panic!("Invalid balance");
+    fn keccak(env: &Env, data: &[u8]) -> [u8; 32] {
+        env.crypto()
+            .keccak256(&Bytes::from_slice(env, data))
+            .to_array()
+    }
+
+    // last 20 bytes of the keccak of the uncompressed key without its 0x04 tag
+    fn evm_address(env: &Env, public_key: &BytesN<65>) -> BytesN<20> {
+        let key: Bytes = public_key.clone().into();
+        let hash: Bytes = env.crypto().keccak256(&key.slice(1..)).into();
+        hash.slice(12..).try_into().unwrap()
+    }
+
+    fn check_client_data(env: &Env, client_data_json: &Bytes, digest: &BytesN<32>) {
+        let len = client_data_json.len() as usize;
+        if len > MAX_CLIENT_DATA_LEN {
+            panic_with_error!(env, Error::InvalidClientData);
+        }
+        let mut json = [0u8; MAX_CLIENT_DATA_LEN];
+        client_data_json.copy_into_slice(&mut json[..len]);
+        let json = &json[..len];
+
+        // the challenge is the unpadded base64url digest
+        let challenge = Self::base64url(&digest.to_array());
+        if Self::json_field(json, b"type") != Some(WEBAUTHN_GET)
+            || Self::json_field(json, b"challenge") != Some(&challenge[..])
+        {
+            panic_with_error!(env, Error::InvalidClientData);
         }
+    }
 
-        // Update allowance
-        let new_allowance = current_allowance - amount;
-        env.storage().persistent().set(
-            &DataKey::Allowance(token.clone(), from.clone(), caller),
-            &new_allowance,
-        );
+    fn check_authenticator_data(env: &Env, authenticator_data: &Bytes) {
+        let flags = authenticator_data
+            .get(AUTHENTICATOR_FLAGS)
+            .unwrap_or_default();
+        if flags & (USER_PRESENT | USER_VERIFIED) != USER_PRESENT | USER_VERIFIED {
+            panic_with_error!(env, Error::InvalidAuthenticatorData);
+        }
+    }
 
-        // Update from balance
-        let new_from_balance = from_balance - amount;
+    // value of the string field `key` of the top level json object, None if
+    // it's missing, repeated, escaped or not a string
+    fn json_field<'a>(json: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+        let (mut depth, mut i, mut field) = (0u32, 0, None);
+        while i < json.len() {
+            match json[i] {
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => depth = depth.checked_sub(1)?,
+                b'"' => {
+                    let end = Self::json_string_end(json, i)?;
+                    let name = &json[i + 1..end];
+                    i = end + 1;
+                    let colon = Self::skip_whitespace(json, i);
+                    if depth == 1 && json.get(colon) == Some(&b':') && name == key {
+                        let start = Self::skip_whitespace(json, colon + 1);
+                        if field.is_some() || json.get(start) != Some(&b'"') {
+                            return None;
+                        }
+                        let end = Self::json_string_end(json, start)?;
+                        let value = &json[start + 1..end];
+                        if value.contains(&b'\\') {
+                            return None;
+                        }
+                        field = Some(value);
+                        i = end + 1;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        field
+    }
+
+    // index of the quote closing the json string opened at `start`
+    fn json_string_end(json: &[u8], start: usize) -> Option<usize> {
+        let mut i = start + 1;
+        while i < json.len() {
+            match json[i] {
+                b'\\' => i += 2,
+                b'"' => return Some(i),
+                _ => i += 1,
+            }
+        }
+        None
+    }
+
+    fn skip_whitespace(json: &[u8], mut i: usize) -> usize {
+        while json.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    }
+
+    // unpadded base64url of 32 bytes
+    fn base64url(bytes: &[u8; 32]) -> [u8; 43] {
+        let mut out = [0u8; 43];
+        let (mut bits, mut len, mut i) = (0u32, 0, 0);
+        for byte in bytes {
+            bits = bits << 8 | *byte as u32;
+            len += 8;
+            while len >= 6 {
+                len -= 6;
+                out[i] = BASE64URL[(bits >> len & 63) as usize];
+                i += 1;
+            }
+        }
+        out[i] = BASE64URL[(bits << (6 - len) & 63) as usize];
+        out
+    }
+
+    fn use_nonce(env: &Env, owner: &Address, nonce: u64) {
+        let (word_pos, bit) = Self::nonce_position(nonce);
+        if Self::nonce_bitmap(env.clone(), owner.clone(), word_pos) & bit != 0 {
+            panic_with_error!(env, Error::NonceUsed);
+        }
+        Self::set_nonce_bits(env, owner, word_pos, bit);
+    }
+
+    fn set_nonce_bits(env: &Env, owner: &Address, word_pos: u64, mask: u128) {
+        let bitmap = Self::nonce_bitmap(env.clone(), owner.clone(), word_pos);
         env.storage().persistent().set(
-            &DataKey::Balance(token.clone(), from.clone()),
-            &new_from_balance,
+            &DataKey::NonceBitmap(owner.clone(), word_pos),
+            &(bitmap | mask),
         );
+    }
 
-        // Update to balance
-        let to_balance = Self::get_balance(env.clone(), token.clone(), to.clone());
-        let new_to_balance = to_balance + amount;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Balance(token, to), &new_to_balance);
+    // (word, bit) of a nonce in the owner's bitmap
+    fn nonce_position(nonce: u64) -> (u64, u128) {
+        (nonce >> 7, 1 << (nonce & 127))
     }
 
-    pub fn get_allowance(env: Env, token: Address, owner: Address, spender: Address) -> u128 {
+    fn only_wrapped_asset(env: &Env, token: &Address) {
+        Self::get_wrapped_asset(env.clone(), token.clone()).require_auth();
+    }
+
+    fn read_allowance(
+        env: &Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> AllowanceValue {
         env.storage()
             .persistent()
             .get(&DataKey::Allowance(token, owner, spender))
-            .unwrap_or(0)
+            .unwrap_or(AllowanceValue {
+                amount: 0,
+                expiration_ledger: 0,
+            })
     }
 
-    pub fn get_balance(env: Env, token: Address, user: Address) -> u128 {
+    fn write_allowance(
+        env: &Env,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: u128,
+        expiration_ledger: u32,
+    ) {
+        env.storage().persistent().set(
+            &DataKey::Allowance(token, owner, spender),
+            &AllowanceValue {
+                amount,
+                expiration_ledger,
+            },
+        );
+    }
+
+    fn spend_allowance(
+        env: &Env,
+        token: &Address,
+        owner: &Address,
+        spender: &Address,
+        amount: u128,
+    ) {
+        let allowance =
+            Self::get_allowance(env.clone(), token.clone(), owner.clone(), spender.clone());
+        if amount > allowance {
+            panic_with_error!(env, Error::InsufficientAllowance);
+        }
+        let expiration_ledger =
+            Self::read_allowance(env, token.clone(), owner.clone(), spender.clone())
+                .expiration_ledger;
+        Self::write_allowance(
+            env,
+            token.clone(),
+            owner.clone(),
+            spender.clone(),
+            allowance - amount,
+            expiration_ledger,
+        );
+    }
+
+    fn move_balance(env: &Env, token: &Address, from: &Address, to: &Address, amount: u128) {
+        Self::debit_balance(env, token, from, amount);
+        Self::credit_balance(env, token, to, amount);
+    }
+
+    fn mint_balance(env: &Env, token: &Address, to: &Address, amount: u128) {
+        Self::credit_balance(env, token, to, amount);
+        Self::set_total_supply(
+            env,
+            token,
+            Self::total_supply(env.clone(), token.clone()) + amount,
+        );
+    }
+
+    fn burn_balance(env: &Env, token: &Address, from: &Address, amount: u128) {
+        Self::debit_balance(env, token, from, amount);
+        Self::set_total_supply(
+            env,
+            token,
+            Self::total_supply(env.clone(), token.clone()) - amount,
+        );
+    }
+
+    fn set_total_supply(env: &Env, token: &Address, supply: u128) {
         env.storage()
             .persistent()
-            .get(&DataKey::Balance(token, user))
-            .unwrap_or(0)
+            .set(&DataKey::TotalSupply(token.clone()), &supply);
+    }
+
+    fn credit_balance(env: &Env, token: &Address, to: &Address, amount: u128) {
+        let to_balance = Self::get_balance(env.clone(), token.clone(), to.clone());
+        env.storage().persistent().set(
+            &DataKey::Balance(token.clone(), to.clone()),
+            &(to_balance + amount),
+        );
+    }
+
+    fn debit_balance(env: &Env, token: &Address, from: &Address, amount: u128) {
+        let from_balance = Self::get_balance(env.clone(), token.clone(), from.clone());
+        if amount > from_balance {
+            panic_with_error!(env, Error::InsufficientBalance);
+        }
+        env.storage().persistent().set(
+            &DataKey::Balance(token.clone(), from.clone()),
+            &(from_balance - amount),
+        );
     }
 }
@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{keccak256, Address, Clock, Stage, Timelocks};
+
+/// Token address the escrows use for the native currency (`address(0)`).
+pub const NATIVE: Address = Address([0; 20]);
+
+/// `IBaseEscrow.Immutables` without the fee `parameters`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Immutables {
+    pub order_hash: [u8; 32],
+    pub hashlock: [u8; 32], // keccak256 of the secret
+    pub maker: Address,
+    pub taker: Address,
+    pub token: Address,
+    pub amount: u128,
+    pub safety_deposit: u128,
+    pub timelocks: Timelocks,
+}
+
+impl Immutables {
+    /// keccak256 of the abi encoded struct, used as the create2 salt.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut encoded = Vec::with_capacity(8 * 32);
+        encoded.extend_from_slice(&self.order_hash);
+        encoded.extend_from_slice(&self.hashlock);
+        for address in [self.maker, self.taker, self.token] {
+            encoded.extend_from_slice(&[0; 12]);
+            encoded.extend_from_slice(&address.0);
+        }
+        for amount in [self.amount, self.safety_deposit] {
+            encoded.extend_from_slice(&[0; 16]);
+            encoded.extend_from_slice(&amount.to_be_bytes());
+        }
+        encoded.extend_from_slice(&self.timelocks.pack());
+        keccak256(&encoded)
+    }
+}
+
+/// Custom errors of the Solidity contracts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvmError {
+    InvalidCaller,
+    InvalidSecret,
+    InvalidTime,
+    InvalidImmutables,
+    InvalidCreationTime, // dst escrow would outlive the src cancellation
+    InsufficientBalance,
+}
+
+impl fmt::Display for EvmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for EvmError {}
+
+/// Logs emitted by the factory and the escrows.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    SrcEscrowCreated {
+        escrow: Address,
+        immutables: Immutables,
+    },
+    DstEscrowCreated {
+        escrow: Address,
+        hashlock: [u8; 32],
+        taker: Address,
+    },
+    EscrowWithdrawal {
+        escrow: Address,
+        secret: [u8; 32],
+    },
+    EscrowCancelled {
+        escrow: Address,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Src,
+    Dst,
+}
+
+#[derive(Clone, Default)]
+struct State {
+    balances: HashMap<(Address, Address), u128>, // (token, holder) -> balance
+    events: Vec<Event>,
+}
+
+/// One EVM chain with the escrow factory, ERC20 balances and native balances.
+/// Every call is atomic: a failed call leaves balances and logs untouched.
+pub struct EvmChain {
+    clock: Box<dyn Clock>,
+    factory: Address,
+    access_token: Address,
+    state: State,
+}
+
+impl EvmChain {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        EvmChain {
+            clock: Box::new(clock),
+            factory: Address::labeled("EscrowFactory"),
+            access_token: Address::labeled("AccessToken"),
+            state: State::default(),
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    pub fn factory(&self) -> Address {
+        self.factory
+    }
+
+    /// Token whose holders may call the public withdraw and cancel paths.
+    pub fn access_token(&self) -> Address {
+        self.access_token
+    }
+
+    pub fn mint(&mut self, token: Address, to: Address, amount: u128) {
+        *self.state.balances.entry((token, to)).or_default() += amount;
+    }
+
+    pub fn deal(&mut self, to: Address, amount: u128) {
+        self.mint(NATIVE, to, amount);
+    }
+
+    pub fn balance_of(&self, token: Address, holder: Address) -> u128 {
+        self.state
+            .balances
+            .get(&(token, holder))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn native_balance(&self, holder: Address) -> u128 {
+        self.balance_of(NATIVE, holder)
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.state.events
+    }
+
+    /// The secret an escrow was withdrawn with, as a resolver reads it from
+    /// the logs.
+    pub fn revealed_secret(&self, escrow: Address) -> Option<[u8; 32]> {
+        self.state.events.iter().find_map(|event| match event {
+            Event::EscrowWithdrawal { escrow: e, secret } if *e == escrow => Some(*secret),
+            _ => None,
+        })
+    }
+
+    pub fn src_address(&self, immutables: &Immutables) -> Address {
+        self.escrow_address(Kind::Src, immutables)
+    }
+
+    pub fn dst_address(&self, immutables: &Immutables) -> Address {
+        self.escrow_address(Kind::Dst, immutables)
+    }
+
+    /// The limit order fill that deploys a source escrow: the maker's tokens
+    /// and the taker's safety deposit move into it. Returns the escrow and
+    /// the immutables with `deployed_at` set.
+    pub fn create_src_escrow(
+        &mut self,
+        immutables: Immutables,
+    ) -> Result<(Address, Immutables), EvmError> {
+        let immutables = Immutables {
+            timelocks: immutables.timelocks.with_deployed_at(self.now()),
+            ..immutables
+        };
+        let escrow = self.src_address(&immutables);
+        self.transact(|state| {
+            state.transfer(NATIVE, immutables.taker, escrow, immutables.safety_deposit)?;
+            state.transfer(
+                immutables.token,
+                immutables.maker,
+                escrow,
+                immutables.amount,
+            )?;
+            state.events.push(Event::SrcEscrowCreated {
+                escrow,
+                immutables: immutables.clone(),
+            });
+            Ok(())
+        })?;
+        Ok((escrow, immutables))
+    }
+
+    /// `EscrowFactory.createDstEscrow`: `caller` locks the tokens and the
+    /// safety deposit. The escrow must be cancellable before the source
+    /// escrow on the other chain is.
+    pub fn create_dst_escrow(
+        &mut self,
+        caller: Address,
+        immutables: Immutables,
+        src_cancellation_timestamp: u64,
+    ) -> Result<(Address, Immutables), EvmError> {
+        let immutables = Immutables {
+            timelocks: immutables.timelocks.with_deployed_at(self.now()),
+            ..immutables
+        };
+        if immutables.timelocks.get(Stage::DstCancellation) > src_cancellation_timestamp {
+            return Err(EvmError::InvalidCreationTime);
+        }
+        let escrow = self.dst_address(&immutables);
+        self.transact(|state| {
+            state.transfer(NATIVE, caller, escrow, immutables.safety_deposit)?;
+            state.transfer(immutables.token, caller, escrow, immutables.amount)?;
+            state.events.push(Event::DstEscrowCreated {
+                escrow,
+                hashlock: immutables.hashlock,
+                taker: immutables.taker,
+            });
+            Ok(())
+        })?;
+        Ok((escrow, immutables))
+    }
+
+    pub fn escrow_src(&mut self, escrow: Address) -> EscrowSrc<'_> {
+        EscrowSrc {
+            chain: self,
+            escrow,
+        }
+    }
+
+    pub fn escrow_dst(&mut self, escrow: Address) -> EscrowDst<'_> {
+        EscrowDst {
+            chain: self,
+            escrow,
+        }
+    }
+
+    // internal functions
+    fn escrow_address(&self, kind: Kind, immutables: &Immutables) -> Address {
+        // create2 of the kind's clone with the immutables hash as salt
+        let implementation = keccak256(format!("{kind:?}").as_bytes());
+        let mut preimage = Vec::with_capacity(85);
+        preimage.push(0xff);
+        preimage.extend_from_slice(&self.factory.0);
+        preimage.extend_from_slice(&immutables.hash());
+        preimage.extend_from_slice(&implementation);
+        Address::from_hash(keccak256(&preimage))
+    }
+
+    fn transact(
+        &mut self,
+        call: impl FnOnce(&mut State) -> Result<(), EvmError>,
+    ) -> Result<(), EvmError> {
+        let snapshot = self.state.clone();
+        let result = call(&mut self.state);
+        if result.is_err() {
+            self.state = snapshot;
+        }
+        result
+    }
+
+    fn only_taker(caller: Address, immutables: &Immutables) -> Result<(), EvmError> {
+        if caller != immutables.taker {
+            return Err(EvmError::InvalidCaller);
+        }
+        Ok(())
+    }
+
+    fn only_access_token_holder(&self, caller: Address) -> Result<(), EvmError> {
+        if self.balance_of(self.access_token, caller) == 0 {
+            return Err(EvmError::InvalidCaller);
+        }
+        Ok(())
+    }
+
+    fn only_after(&self, start: u64) -> Result<(), EvmError> {
+        if self.now() < start {
+            return Err(EvmError::InvalidTime);
+        }
+        Ok(())
+    }
+
+    fn only_before(&self, stop: u64) -> Result<(), EvmError> {
+        if self.now() >= stop {
+            return Err(EvmError::InvalidTime);
+        }
+        Ok(())
+    }
+
+    fn only_valid_immutables(
+        &self,
+        kind: Kind,
+        escrow: Address,
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        if self.escrow_address(kind, immutables) != escrow {
+            return Err(EvmError::InvalidImmutables);
+        }
+        Ok(())
+    }
+
+    fn only_valid_secret(secret: [u8; 32], immutables: &Immutables) -> Result<(), EvmError> {
+        if keccak256(&secret) != immutables.hashlock {
+            return Err(EvmError::InvalidSecret);
+        }
+        Ok(())
+    }
+
+    /// Pays out the locked tokens to `to` and the safety deposit to `caller`.
+    fn release(
+        &mut self,
+        escrow: Address,
+        caller: Address,
+        to: Address,
+        immutables: &Immutables,
+        event: Event,
+    ) -> Result<(), EvmError> {
+        self.transact(|state| {
+            state.transfer(immutables.token, escrow, to, immutables.amount)?;
+            state.transfer(NATIVE, escrow, caller, immutables.safety_deposit)?;
+            state.events.push(event);
+            Ok(())
+        })
+    }
+}
+
+impl State {
+    fn transfer(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: u128,
+    ) -> Result<(), EvmError> {
+        let from_balance = self.balances.entry((token, from)).or_default();
+        *from_balance = from_balance
+            .checked_sub(amount)
+            .ok_or(EvmError::InsufficientBalance)?;
+        *self.balances.entry((token, to)).or_default() += amount;
+        Ok(())
+    }
+}
+
+/// `EscrowSrc` deployed at `escrow`. Every call takes `msg.sender` and the
+/// immutables the escrow was created with.
+pub struct EscrowSrc<'a> {
+    chain: &'a mut EvmChain,
+    escrow: Address,
+}
+
+impl EscrowSrc<'_> {
+    pub fn withdraw(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.withdraw_to(caller, secret, caller, immutables)
+    }
+
+    pub fn withdraw_to(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        target: Address,
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        EvmChain::only_taker(caller, immutables)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::SrcWithdrawal))?;
+        self.chain
+            .only_before(immutables.timelocks.get(Stage::SrcCancellation))?;
+        self.withdraw_internal(caller, secret, target, immutables)
+    }
+
+    pub fn public_withdraw(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.chain.only_access_token_holder(caller)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::SrcPublicWithdrawal))?;
+        self.chain
+            .only_before(immutables.timelocks.get(Stage::SrcCancellation))?;
+        self.withdraw_internal(caller, secret, immutables.taker, immutables)
+    }
+
+    pub fn cancel(&mut self, caller: Address, immutables: &Immutables) -> Result<(), EvmError> {
+        EvmChain::only_taker(caller, immutables)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::SrcCancellation))?;
+        self.cancel_internal(caller, immutables)
+    }
+
+    pub fn public_cancel(
+        &mut self,
+        caller: Address,
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.chain.only_access_token_holder(caller)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::SrcPublicCancellation))?;
+        self.cancel_internal(caller, immutables)
+    }
+
+    fn withdraw_internal(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        target: Address,
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.chain
+            .only_valid_immutables(Kind::Src, self.escrow, immutables)?;
+        EvmChain::only_valid_secret(secret, immutables)?;
+        let event = Event::EscrowWithdrawal {
+            escrow: self.escrow,
+            secret,
+        };
+        self.chain
+            .release(self.escrow, caller, target, immutables, event)
+    }
+
+    fn cancel_internal(
+        &mut self,
+        caller: Address,
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.chain
+            .only_valid_immutables(Kind::Src, self.escrow, immutables)?;
+        let event = Event::EscrowCancelled {
+            escrow: self.escrow,
+        };
+        self.chain
+            .release(self.escrow, caller, immutables.maker, immutables, event)
+    }
+}
+
+/// `EscrowDst` deployed at `escrow`. Every call takes `msg.sender` and the
+/// immutables the escrow was created with.
+pub struct EscrowDst<'a> {
+    chain: &'a mut EvmChain,
+    escrow: Address,
+}
+
+impl EscrowDst<'_> {
+    pub fn withdraw(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        EvmChain::only_taker(caller, immutables)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::DstWithdrawal))?;
+        self.chain
+            .only_before(immutables.timelocks.get(Stage::DstCancellation))?;
+        self.withdraw_internal(caller, secret, immutables)
+    }
+
+    pub fn public_withdraw(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.chain.only_access_token_holder(caller)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::DstPublicWithdrawal))?;
+        self.chain
+            .only_before(immutables.timelocks.get(Stage::DstCancellation))?;
+        self.withdraw_internal(caller, secret, immutables)
+    }
+
+    pub fn cancel(&mut self, caller: Address, immutables: &Immutables) -> Result<(), EvmError> {
+        EvmChain::only_taker(caller, immutables)?;
+        self.chain
+            .only_valid_immutables(Kind::Dst, self.escrow, immutables)?;
+        self.chain
+            .only_after(immutables.timelocks.get(Stage::DstCancellation))?;
+        let event = Event::EscrowCancelled {
+            escrow: self.escrow,
+        };
+        self.chain
+            .release(self.escrow, caller, immutables.taker, immutables, event)
+    }
+
+    // tokens go to the maker, the safety deposit to the caller
+    fn withdraw_internal(
+        &mut self,
+        caller: Address,
+        secret: [u8; 32],
+        immutables: &Immutables,
+    ) -> Result<(), EvmError> {
+        self.chain
+            .only_valid_immutables(Kind::Dst, self.escrow, immutables)?;
+        EvmChain::only_valid_secret(secret, immutables)?;
+        let event = Event::EscrowWithdrawal {
+            escrow: self.escrow,
+            secret,
+        };
+        self.chain
+            .release(self.escrow, caller, immutables.maker, immutables, event)
+    }
+}
@@ -0,0 +1,78 @@
+//! In-memory model of the EVM side of FusionX (`contracts/eth/src/Escrow.sol`)
+//! for local cross-chain tests.
+//!
+//! Only the behaviour the Stellar contracts depend on is modelled: the
+//! factory locking funds in `EscrowSrc` / `EscrowDst`, keccak hash locks, the
+//! `Timelocks` stages and the withdraw / cancel paths with their access
+//! rules. Time comes from a [`Clock`], so one clock can drive both chains.
+
+mod chain;
+mod timelocks;
+
+pub use chain::{EscrowDst, EscrowSrc, Event, EvmChain, EvmError, Immutables, NATIVE};
+pub use timelocks::{Stage, Timelocks};
+
+use sha3::{Digest, Keccak256};
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+/// A 20 byte EVM account or contract address.
+#[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Address(pub [u8; 20]);
+
+impl Address {
+    /// Deterministic address for a test actor, e.g. `Address::labeled("maker")`.
+    pub fn labeled(label: &str) -> Self {
+        Self::from_hash(keccak256(label.as_bytes()))
+    }
+
+    /// The last 20 bytes of a hash, like `address(uint160(uint256(hash)))`.
+    pub fn from_hash(hash: [u8; 32]) -> Self {
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        Address(address)
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+/// Source of `block.timestamp`.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> Clock for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock(Rc<Cell<u64>>);
+
+impl ManualClock {
+    pub fn new(now: u64) -> Self {
+        ManualClock(Rc::new(Cell::new(now)))
+    }
+
+    pub fn set(&self, now: u64) {
+        self.0.set(now);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.0.get()
+    }
+}
@@ -0,0 +1,74 @@
+/// Stages of the EVM escrows, in the order `TimelocksLib` packs them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    SrcWithdrawal,
+    SrcPublicWithdrawal,
+    SrcCancellation,
+    SrcPublicCancellation,
+    DstWithdrawal,
+    DstPublicWithdrawal,
+    DstCancellation,
+}
+
+/// Seconds from deployment to the start of each stage, plus the deployment
+/// timestamp the factory fills in. Mirrors `TimelocksLib` in `Escrow.sol`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Timelocks {
+    pub deployed_at: u32,
+    pub src_withdrawal: u32,
+    pub src_public_withdrawal: u32,
+    pub src_cancellation: u32,
+    pub src_public_cancellation: u32,
+    pub dst_withdrawal: u32,
+    pub dst_public_withdrawal: u32,
+    pub dst_cancellation: u32,
+}
+
+impl Timelocks {
+    pub fn with_deployed_at(self, deployed_at: u64) -> Self {
+        // the maximum u32 value will be reached in 2106, same as on chain
+        Timelocks {
+            deployed_at: deployed_at as u32,
+            ..self
+        }
+    }
+
+    /// Absolute timestamp at which `stage` starts.
+    pub fn get(&self, stage: Stage) -> u64 {
+        self.deployed_at as u64 + self.offset(stage) as u64
+    }
+
+    /// The `uint256` the Solidity contracts store: stage `i` in bits
+    /// `32 * i`, the deployment timestamp in the top 32 bits. Big endian.
+    pub fn pack(&self) -> [u8; 32] {
+        let mut packed = [0u8; 32];
+        packed[..4].copy_from_slice(&self.deployed_at.to_be_bytes());
+        for (i, stage) in STAGES.iter().enumerate() {
+            let end = 32 - 4 * i;
+            packed[end - 4..end].copy_from_slice(&self.offset(*stage).to_be_bytes());
+        }
+        packed
+    }
+
+    fn offset(&self, stage: Stage) -> u32 {
+        match stage {
+            Stage::SrcWithdrawal => self.src_withdrawal,
+            Stage::SrcPublicWithdrawal => self.src_public_withdrawal,
+            Stage::SrcCancellation => self.src_cancellation,
+            Stage::SrcPublicCancellation => self.src_public_cancellation,
+            Stage::DstWithdrawal => self.dst_withdrawal,
+            Stage::DstPublicWithdrawal => self.dst_public_withdrawal,
+            Stage::DstCancellation => self.dst_cancellation,
+        }
+    }
+}
+
+const STAGES: [Stage; 7] = [
+    Stage::SrcWithdrawal,
+    Stage::SrcPublicWithdrawal,
+    Stage::SrcCancellation,
+    Stage::SrcPublicCancellation,
+    Stage::DstWithdrawal,
+    Stage::DstPublicWithdrawal,
+    Stage::DstCancellation,
+];
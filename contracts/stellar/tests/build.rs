@@ -0,0 +1,68 @@
+//! Builds the contracts that other contracts deploy (the escrows and the
+//! wrapped asset) to wasm so tests deploy them the same way as on chain.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+const DEPLOYED: [&str; 3] = ["escrowsrc", "escrowdest", "wrappedasset"];
+const WASM_TARGET: &str = "wasm32v1-none";
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let workspace = manifest_dir.parent().unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let target_dir = out_dir.join("wasm");
+
+    for dir in [
+        "contracts/EscrowSrc",
+        "contracts/EscrowDest",
+        "contracts/WrappedAsset",
+        "contracts/FusionXTypes",
+    ] {
+        println!("cargo:rerun-if-changed={}", workspace.join(dir).display());
+    }
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut build = Command::new(cargo);
+    build
+        .current_dir(workspace)
+        .args([
+            "build",
+            "--release",
+            "--target",
+            WASM_TARGET,
+            "--target-dir",
+        ])
+        .arg(&target_dir);
+    for package in DEPLOYED {
+        build.args(["--package", package]);
+    }
+    // don't leak the outer build's wrappers and flags (e.g. clippy-driver) into the wasm build
+    for var in [
+        "RUSTC_WRAPPER",
+        "RUSTC_WORKSPACE_WRAPPER",
+        "RUSTFLAGS",
+        "CARGO_ENCODED_RUSTFLAGS",
+        "CARGO_TARGET_DIR",
+        "CARGO_BUILD_TARGET",
+    ] {
+        build.env_remove(var);
+    }
+
+    let status = build
+        .status()
+        .expect("failed to run cargo for the wasm build");
+    assert!(
+        status.success(),
+        "building the deployed contracts for {WASM_TARGET} failed (rustup target add {WASM_TARGET})"
+    );
+
+    for package in DEPLOYED {
+        let wasm = target_dir
+            .join(WASM_TARGET)
+            .join("release")
+            .join(format!("{package}.wasm"));
+        std::fs::copy(&wasm, out_dir.join(format!("{package}.wasm"))).unwrap();
+    }
+}
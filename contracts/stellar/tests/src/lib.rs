@@ -0,0 +1,451 @@
+//! Test harness that deploys the whole FusionX Stellar stack into one `Env`.
+//!
+//! Relayer, DutchAuction, Resolver, EscrowFactory, WrappedTokens and MockToken
+//! are registered natively. The escrows and the wrapped asset are built to
+//! wasm by `build.rs` and deployed by the factory and WrappedTokens, exactly
+//! as on chain.
+
+use ed25519_dalek::{Signer, SigningKey};
+use fusionx_types::interfaces::{EscrowDestClient, EscrowSrcClient};
+use fusionx_types::{OrderInput, TokenTransfer};
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::xdr::{self, FromXdr, ToXdr};
+use soroban_sdk::{token, Address, Bytes, BytesN, Env, IntoVal, String, Symbol, TryFromVal, Val};
+use std::rc::Rc;
+
+pub mod mock_router;
+pub mod token_fixtures;
+
+use dutchauction::{DutchAuction, DutchAuctionClient};
+use escrowfactory::{EscrowFactory, EscrowFactoryClient};
+use liquidityvault::{LiquidityVault, LiquidityVaultClient};
+use mock_router::{MockRouter, MockRouterClient};
+use mocktoken::{MockToken, MockTokenClient};
+use relayer::{Relayer, RelayerClient};
+use resolver::{Resolver, ResolverClient};
+use token_fixtures::{AdversarialToken, AdversarialTokenClient};
+use wrappedtoken::{WrappedTokens, WrappedTokensClient};
+
+pub const ESCROW_SRC_WASM: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/escrowsrc.wasm"));
+pub const ESCROW_DEST_WASM: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/escrowdest.wasm"));
+pub const WRAPPED_ASSET_WASM: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/wrappedasset.wasm"));
+
+// mirrors of the contract constants
+pub const SECURITY_DEPOSIT: i128 = 500_000_000;
+pub const AUCTION_START_BUFFER: u64 = 2 * 60;
+pub const MAX_AUCTION_TIME: u64 = 10 * 60;
+
+pub const SRC_FINALITY_LOCK: u64 = 2 * 60;
+pub const SRC_RESOLVER_UNLOCK_PERIOD: u64 = SRC_FINALITY_LOCK + 4 * 60;
+pub const SRC_ANYONE_UNLOCK_PERIOD: u64 = SRC_RESOLVER_UNLOCK_PERIOD + 4 * 60;
+pub const SRC_RESOLVER_CANCEL: u64 = SRC_ANYONE_UNLOCK_PERIOD + 2 * 60;
+
+pub const DEST_FINALITY_LOCK: u64 = 2 * 60;
+pub const DEST_RESOLVER_UNLOCK_PERIOD: u64 = DEST_FINALITY_LOCK + 2 * 60;
+pub const DEST_ANYONE_UNLOCK_PERIOD: u64 = DEST_RESOLVER_UNLOCK_PERIOD + 2 * 60;
+
+pub const START_TIME: u64 = 1_700_000_000;
+pub const PERMIT_VALIDITY: u64 = 60 * 60;
+pub const LOAN_DURATION: u64 = 24 * 60 * 60;
+
+pub const TOKEN_IN_DECIMALS: u32 = 18;
+pub const DEST_TOKEN_DECIMALS: u32 = 6;
+
+/// A maker with a funded MockToken balance and the ed25519 key it signs
+/// permits with.
+pub struct Maker {
+    pub address: Address,
+    pub key: SigningKey,
+}
+
+/// The account (G...) address controlled by `key`.
+pub fn account_address(env: &Env, key: &SigningKey) -> Address {
+    // ScVal::Address(ScAddress::Account(PublicKey::Ed25519(key))) as xdr
+    let mut xdr = Bytes::from_array(env, &[0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0]);
+    xdr.extend_from_array(&key.verifying_key().to_bytes());
+    Address::from_xdr(env, &xdr).unwrap()
+}
+
+// inverse of `account_address`
+fn account_id(account: &Address) -> xdr::AccountId {
+    let mut key = [0u8; 32];
+    account
+        .to_xdr(account.env())
+        .slice(12..)
+        .copy_into_slice(&mut key);
+    xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256(key)))
+}
+
+/// An order together with the secret behind its hash lock.
+pub struct TestOrder {
+    pub input: OrderInput,
+    pub secret: BytesN<32>,
+}
+
+pub struct FusionX<'a> {
+    pub env: Env,
+    pub owner: Address,
+    pub xlm: Address,       // stand-in for the native token, pays security deposits
+    pub token_out: Address, // token the resolver delivers on the destination side
+    pub dest_token: BytesN<32>, // tokenOut of orders, registered with the relayer
+    pub token_in: MockTokenClient<'a>,
+    pub wrapped: WrappedTokensClient<'a>,
+    pub relayer: RelayerClient<'a>,
+    pub auction: DutchAuctionClient<'a>,
+    pub factory: EscrowFactoryClient<'a>,
+    pub dest_factory: EscrowFactoryClient<'a>,
+    pub resolver: ResolverClient<'a>,
+}
+
+impl<'a> FusionX<'a> {
+    /// Deploys and wires up every contract. The source and destination
+    /// escrows of an order live on different chains, so the destination side
+    /// gets its own factory, which the Resolver deploys through.
+    pub fn setup() -> Self {
+        let env = Env::default();
+        // root auth only, like a signed transaction: what contracts need from
+        // each other deeper down they have to authorize themselves
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = START_TIME);
+
+        let owner = Address::generate(&env);
+        let xlm = env
+            .register_stellar_asset_contract_v2(owner.clone())
+            .address();
+        let token_out = env
+            .register_stellar_asset_contract_v2(owner.clone())
+            .address();
+
+        let token_in = MockTokenClient::new(
+            &env,
+            &env.register(
+                MockToken,
+                (
+                    owner.clone(),
+                    TOKEN_IN_DECIMALS,
+                    String::from_str(&env, "MyToken"),
+                    String::from_str(&env, "MTK"),
+                ),
+            ),
+        );
+        let wrapped = WrappedTokensClient::new(&env, &env.register(WrappedTokens, ()));
+        let relayer = RelayerClient::new(&env, &env.register(Relayer, ()));
+        let auction = DutchAuctionClient::new(&env, &env.register(DutchAuction, ()));
+        let factory = EscrowFactoryClient::new(&env, &env.register(EscrowFactory, ()));
+        let dest_factory = EscrowFactoryClient::new(&env, &env.register(EscrowFactory, ()));
+        let resolver = ResolverClient::new(&env, &env.register(Resolver, ()));
+
+        let escrow_src_wasm = env.deployer().upload_contract_wasm(ESCROW_SRC_WASM);
+        let escrow_dest_wasm = env.deployer().upload_contract_wasm(ESCROW_DEST_WASM);
+        let wrapped_asset_wasm = env.deployer().upload_contract_wasm(WRAPPED_ASSET_WASM);
+
+        wrapped.initialize(&wrapped_asset_wasm);
+        relayer.initialize(&owner, &auction.address, &wrapped.address);
+        auction.initialize(&owner);
+        auction.set_relayer(&relayer.address);
+        auction.set_escrow_factory(&factory.address);
+        for f in [&factory, &dest_factory] {
+            f.initialize(
+                &auction.address,
+                &relayer.address,
+                &xlm,
+                &escrow_src_wasm,
+                &escrow_dest_wasm,
+            );
+            relayer.set_trusted_factory(&f.address, &true);
+        }
+        resolver.initialize(
+            &owner,
+            &dest_factory.address,
+            &relayer.address,
+            &auction.address,
+        );
+        relayer.add_resolver(&resolver.address);
+        let dest_token = BytesN::from_array(&env, &[0x0d; 32]);
+        relayer.set_token_decimals(&token_in.address, &TOKEN_IN_DECIMALS);
+        relayer.set_dest_token_decimals(&dest_token, &DEST_TOKEN_DECIMALS);
+
+        // security deposits for both escrows and inventory for the destination side
+        let fx = FusionX {
+            env,
+            owner,
+            xlm,
+            token_out,
+            dest_token,
+            token_in,
+            wrapped,
+            relayer,
+            auction,
+            factory,
+            dest_factory,
+            resolver,
+        };
+        fx.mint_xlm(&fx.resolver.address, 10 * SECURITY_DEPOSIT);
+        fx.mint_token_out(&fx.resolver.address, 1_000_000_000_000);
+        fx
+    }
+
+    pub fn mint_xlm(&self, to: &Address, amount: i128) {
+        token::StellarAssetClient::new(&self.env, &self.xlm).mint(to, &amount);
+    }
+
+    pub fn mint_token_out(&self, to: &Address, amount: i128) {
+        token::StellarAssetClient::new(&self.env, &self.token_out).mint(to, &amount);
+    }
+
+    /// Opens an authorized trustline from `account` to the classic asset behind
+    /// the SAC `token`, which accounts need before they can hold it.
+    pub fn trust(&self, account: &Address, token: &Address) {
+        let name = token::Client::new(&self.env, token).name().to_string();
+        let (code, issuer) = name.split_once(':').unwrap();
+        let mut asset_code = [0u8; 4];
+        asset_code[..code.len()].copy_from_slice(code.as_bytes());
+        let asset = xdr::TrustLineAsset::CreditAlphanum4(xdr::AlphaNum4 {
+            asset_code: xdr::AssetCode4(asset_code),
+            issuer: account_id(&Address::from_str(&self.env, issuer)),
+        });
+        let account_id = account_id(account);
+
+        let key = Rc::new(xdr::LedgerKey::Trustline(xdr::LedgerKeyTrustLine {
+            account_id: account_id.clone(),
+            asset: asset.clone(),
+        }));
+        let entry = Rc::new(xdr::LedgerEntry {
+            last_modified_ledger_seq: 0,
+            data: xdr::LedgerEntryData::Trustline(xdr::TrustLineEntry {
+                account_id,
+                asset,
+                balance: 0,
+                limit: i64::MAX,
+                flags: xdr::TrustLineFlags::AuthorizedFlag as u32,
+                ext: xdr::TrustLineEntryExt::V0,
+            }),
+            ext: xdr::LedgerEntryExt::V0,
+        });
+        let host = self.env.host();
+        host.with_mut_storage(|storage| storage.put(&key, &entry, None, &host.budget_cloned()))
+            .unwrap();
+    }
+
+    pub fn xlm_balance(&self, id: &Address) -> i128 {
+        token::Client::new(&self.env, &self.xlm).balance(id)
+    }
+
+    pub fn token_out_balance(&self, id: &Address) -> i128 {
+        token::Client::new(&self.env, &self.token_out).balance(id)
+    }
+
+    pub fn token_in_balance(&self, id: &Address) -> i128 {
+        self.token_in.balance(id)
+    }
+
+    /// Mints MockToken to a fresh maker and wraps `wrapped` of it.
+    pub fn maker(&self, minted: u128, wrapped: u128) -> Maker {
+        let key = SigningKey::generate(&mut rand::thread_rng());
+        let address = account_address(&self.env, &key);
+        self.trust(&address, &self.token_out);
+        self.token_in.mint(&address, &(minted as i128));
+        self.wrapped
+            .deposit(&self.token_in.address, &wrapped, &address);
+        Maker { address, key }
+    }
+
+    /// Registers a mock AMM router and makes it the Resolver's router.
+    pub fn mock_router(&self) -> MockRouterClient<'a> {
+        let router = MockRouterClient::new(&self.env, &self.env.register(MockRouter, ()));
+        self.resolver.set_router(&router.address);
+        router
+    }
+
+    /// Registers a LiquidityVault of `token_out` owned by the owner, with the
+    /// Resolver as its one borrower and the Resolver borrowing from it. Loans
+    /// are due `LOAN_DURATION` after they are taken.
+    pub fn liquidity_vault(
+        &self,
+        fee_bps: u32,
+        max_utilization_bps: u32,
+    ) -> LiquidityVaultClient<'a> {
+        let vault = LiquidityVaultClient::new(&self.env, &self.env.register(LiquidityVault, ()));
+        vault.initialize(
+            &self.owner,
+            &self.token_out,
+            &fee_bps,
+            &max_utilization_bps,
+            &LOAN_DURATION,
+        );
+        vault.set_borrower(&self.resolver.address, &true);
+        self.resolver.set_vault(&vault.address);
+        vault
+    }
+
+    /// Registers a misbehaving token administered by the owner, with the
+    /// decimals of MockToken and registered with the Relayer.
+    pub fn adversarial_token(&self) -> AdversarialTokenClient<'a> {
+        let token = AdversarialTokenClient::new(
+            &self.env,
+            &self.env.register(
+                AdversarialToken,
+                (
+                    self.owner.clone(),
+                    TOKEN_IN_DECIMALS,
+                    String::from_str(&self.env, "Adversarial"),
+                    String::from_str(&self.env, "ADV"),
+                ),
+            ),
+        );
+        self.relayer
+            .set_token_decimals(&token.address, &TOKEN_IN_DECIMALS);
+        token
+    }
+
+    /// Builds an order selling `amount_in` of MockToken with a fresh secret.
+    pub fn order(
+        &self,
+        maker: &Maker,
+        amount_in: u128,
+        min_amount_out: u128,
+        max_amount_out: u128,
+    ) -> TestOrder {
+        let secret = self.random_bytes();
+        let hash_lock = self.keccak(&secret);
+        TestOrder {
+            input: OrderInput {
+                orderId: self.random_bytes(),
+                maker: maker.address.clone(),
+                tokenIn: self.token_in.address.clone(),
+                tokenOut: self.dest_token.clone(),
+                amountIn: amount_in,
+                minAmountOut: min_amount_out,
+                maxAmountOut: max_amount_out,
+                hashLock: hash_lock,
+            },
+            secret,
+        }
+    }
+
+    /// Signs a permit for the order and places it through the Relayer.
+    pub fn place_order(&self, maker: &Maker, order: &TestOrder) {
+        let nonce: u64 = rand::random();
+        let deadline = self.now() + PERMIT_VALIDITY;
+        let digest = self.wrapped.permit_digest(
+            &order.input.tokenIn,
+            &order.input.maker,
+            &self.relayer.address,
+            &order.input.amountIn,
+            &nonce,
+            &deadline,
+        );
+        let signature = maker.key.sign(&digest.to_array());
+        self.relayer.place_order(
+            &order.input,
+            &nonce,
+            &deadline,
+            &BytesN::from_array(&self.env, &maker.key.verifying_key().to_bytes()),
+            &BytesN::from_array(&self.env, &signature.to_bytes()),
+        );
+    }
+
+    /// Signs a transfer of the order's amount into its source escrow and
+    /// places it through the Relayer, returns the permit nonce.
+    pub fn place_order_with_transfer(&self, maker: &Maker, order: &TestOrder) -> u64 {
+        let nonce: u64 = rand::random();
+        let deadline = self.now() + PERMIT_VALIDITY;
+        let transfer = TokenTransfer {
+            token: order.input.tokenIn.clone(),
+            to: self.factory.get_escrow_address(&order.input.orderId),
+            amount: order.input.amountIn,
+        };
+        let digest = self.wrapped.permit_transfer_digest(
+            &order.input.maker,
+            &self.relayer.address,
+            &soroban_sdk::vec![&self.env, transfer],
+            &nonce,
+            &deadline,
+        );
+        let signature = maker.key.sign(&digest.to_array());
+        self.relayer.place_order_with_transfer(
+            &order.input,
+            &nonce,
+            &deadline,
+            &BytesN::from_array(&self.env, &maker.key.verifying_key().to_bytes()),
+            &BytesN::from_array(&self.env, &signature.to_bytes()),
+        );
+        nonce
+    }
+
+    /// Fills the order through Resolver -> DutchAuction -> EscrowFactory at
+    /// whatever the current price is and returns the source escrow.
+    pub fn fill(&self, order: &TestOrder) -> EscrowSrcClient<'a> {
+        self.resolver.deploy_escrow_src(
+            &self.resolver.address,
+            &order.input.orderId,
+            &order.input.maxAmountOut,
+        );
+        let escrow = self.factory.get_escrow_from_orderid(&order.input.orderId);
+        EscrowSrcClient::new(&self.env, &escrow)
+    }
+
+    /// Deploys and funds the destination escrow with the filled amount.
+    pub fn deploy_dest(&self, order: &TestOrder) -> EscrowDestClient<'a> {
+        let amount_out = self.auction.get_order(&order.input.orderId).amountOut;
+        self.resolver.deploy_escrow_dest(
+            &self.resolver.address,
+            &order.input.orderId,
+            &order.input.hashLock,
+            &self.token_out,
+            &amount_out,
+            &order.input.maker,
+        );
+        let escrow = self
+            .dest_factory
+            .get_escrow_from_orderid(&order.input.orderId);
+        EscrowDestClient::new(&self.env, &escrow)
+    }
+
+    /// Whitelists a fresh resolver address, used for the public paths.
+    pub fn other_resolver(&self) -> Address {
+        let resolver = Address::generate(&self.env);
+        self.relayer.add_resolver(&resolver);
+        resolver
+    }
+
+    pub fn now(&self) -> u64 {
+        self.env.ledger().timestamp()
+    }
+
+    pub fn set_time(&self, timestamp: u64) {
+        self.env.ledger().with_mut(|l| l.timestamp = timestamp);
+    }
+
+    pub fn random_bytes(&self) -> BytesN<32> {
+        let bytes: [u8; 32] = rand::random();
+        BytesN::from_array(&self.env, &bytes)
+    }
+
+    pub fn keccak(&self, bytes: &BytesN<32>) -> BytesN<32> {
+        let bytes: Bytes = bytes.clone().into();
+        self.env.crypto().keccak256(&bytes).into()
+    }
+
+    /// Data of the events `contract` published under `topics` during the
+    /// last invocation.
+    pub fn events<T: TryFromVal<Env, Val>>(
+        &self,
+        contract: &Address,
+        topics: (&str, &str),
+    ) -> std::vec::Vec<T> {
+        let topics: soroban_sdk::Vec<Val> = (
+            Symbol::new(&self.env, topics.0),
+            Symbol::new(&self.env, topics.1),
+        )
+            .into_val(&self.env);
+        self.env
+            .events()
+            .all()
+            .iter()
+            .filter(|(id, t, _)| id == contract && *t == topics)
+            .map(|(_, _, data)| T::try_from_val(&self.env, &data).unwrap())
+            .collect()
+    }
+}
@@ -0,0 +1,81 @@
+//! A Soroswap-shaped AMM router for tests. It is its own only pair: it swaps
+//! at fixed rates set by the test out of whatever it holds, pulling the input
+//! from `to` the way Soroswap pulls it into the first pair.
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, panic_with_error, token, vec, Address,
+    Env, Vec,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RouterError {
+    Expired = 200,
+    InsufficientOutputAmount = 201,
+    NoRate = 202, // no rate set for a hop of the path
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RouterKey {
+    Rate(Address, Address), // (token_in, token_out) -> (numerator, denominator)
+}
+
+#[contract]
+pub struct MockRouter;
+
+#[contractimpl]
+impl MockRouter {
+    /// Every `denominator` of `token_in` buys `numerator` of `token_out`.
+    pub fn set_rate(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        numerator: i128,
+        denominator: i128,
+    ) {
+        env.storage().persistent().set(
+            &RouterKey::Rate(token_in, token_out),
+            &(numerator, denominator),
+        );
+    }
+
+    pub fn router_pair_for(env: Env, _token_a: Address, _token_b: Address) -> Address {
+        env.current_contract_address()
+    }
+
+    pub fn swap_exact_tokens_for_tokens(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        deadline: u64,
+    ) -> Vec<i128> {
+        to.require_auth();
+        if env.ledger().timestamp() > deadline {
+            panic_with_error!(&env, RouterError::Expired);
+        }
+
+        let mut amounts = vec![&env, amount_in];
+        for i in 1..path.len() {
+            let rate: Option<(i128, i128)> = env.storage().persistent().get(&RouterKey::Rate(
+                path.get_unchecked(i - 1),
+                path.get_unchecked(i),
+            ));
+            let (numerator, denominator) =
+                rate.unwrap_or_else(|| panic_with_error!(&env, RouterError::NoRate));
+            amounts.push_back(amounts.last_unchecked() * numerator / denominator);
+        }
+        let amount_out = amounts.last_unchecked();
+        if amount_out < amount_out_min {
+            panic_with_error!(&env, RouterError::InsufficientOutputAmount);
+        }
+
+        let this = env.current_contract_address();
+        token::Client::new(&env, &path.get_unchecked(0)).transfer(&to, &this, &amount_in);
+        token::Client::new(&env, &path.last_unchecked()).transfer(&this, &to, &amount_out);
+        amounts
+    }
+}
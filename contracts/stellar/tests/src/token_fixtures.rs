@@ -0,0 +1,175 @@
+//! Misbehaving tokens built on MockToken's storage, for checking how the
+//! pipeline contracts cope with them.
+//!
+//! One contract covers every misbehaviour, each switched on by the admin:
+//! a fee taken from every transfer, transfers that always fail, a blocklist,
+//! a `balance` that lies by a fixed offset and a pause. Mints and burns are
+//! never affected, so tests can set up balances while a misbehaviour is on.
+
+use mocktoken::MockToken;
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, panic_with_error, symbol_short,
+    token::TokenInterface, Address, Env, String,
+};
+
+/// Basis points in 100%.
+pub const BPS: i128 = 10_000;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum FixtureError {
+    TransferFailed = 100,
+    Blocked = 101,
+    Paused = 102,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum FixtureKey {
+    FeeBps,
+    FailTransfers,
+    Blocked(Address),
+    BalanceOffset,
+    Paused,
+}
+
+#[contract]
+pub struct AdversarialToken;
+
+#[contractimpl]
+impl AdversarialToken {
+    pub fn __constructor(env: Env, admin: Address, decimals: u32, name: String, symbol: String) {
+        MockToken::__constructor(env, admin, decimals, name, symbol);
+    }
+
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        MockToken::mint(env, to, amount);
+    }
+
+    /// Share of every transfer that is burnt instead of delivered.
+    pub fn set_fee_bps(env: Env, fee_bps: u32) {
+        Self::only_admin(&env);
+        env.storage().instance().set(&FixtureKey::FeeBps, &fee_bps);
+    }
+
+    pub fn set_fail_transfers(env: Env, fail: bool) {
+        Self::only_admin(&env);
+        env.storage()
+            .instance()
+            .set(&FixtureKey::FailTransfers, &fail);
+    }
+
+    /// Blocked addresses can neither send nor receive.
+    pub fn set_blocked(env: Env, id: Address, blocked: bool) {
+        Self::only_admin(&env);
+        env.storage()
+            .persistent()
+            .set(&FixtureKey::Blocked(id), &blocked);
+    }
+
+    /// Added to every balance `balance` reports.
+    pub fn set_balance_offset(env: Env, offset: i128) {
+        Self::only_admin(&env);
+        env.storage()
+            .instance()
+            .set(&FixtureKey::BalanceOffset, &offset);
+    }
+
+    pub fn set_paused(env: Env, paused: bool) {
+        Self::only_admin(&env);
+        env.storage().instance().set(&FixtureKey::Paused, &paused);
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for AdversarialToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        MockToken::allowance(env, from, spender)
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        MockToken::approve(env, from, spender, amount, expiration_ledger);
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        let offset: i128 = env
+            .storage()
+            .instance()
+            .get(&FixtureKey::BalanceOffset)
+            .unwrap_or(0);
+        MockToken::balance(env, id) + offset
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::move_balance(&env, &from, &to, amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        MockToken::spend_allowance(&env, &from, &spender, amount);
+        Self::move_balance(&env, &from, &to, amount);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        MockToken::burn(env, from, amount);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        MockToken::burn_from(env, spender, from, amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        MockToken::decimals(env)
+    }
+
+    fn name(env: Env) -> String {
+        MockToken::name(env)
+    }
+
+    fn symbol(env: Env) -> String {
+        MockToken::symbol(env)
+    }
+}
+
+impl AdversarialToken {
+    fn only_admin(env: &Env) {
+        MockToken::admin(env.clone()).require_auth();
+    }
+
+    fn move_balance(env: &Env, from: &Address, to: &Address, amount: i128) {
+        let storage = env.storage();
+        if storage.instance().get(&FixtureKey::Paused).unwrap_or(false) {
+            panic_with_error!(env, FixtureError::Paused);
+        }
+        if storage
+            .instance()
+            .get(&FixtureKey::FailTransfers)
+            .unwrap_or(false)
+        {
+            panic_with_error!(env, FixtureError::TransferFailed);
+        }
+        for id in [from, to] {
+            if storage
+                .persistent()
+                .get(&FixtureKey::Blocked(id.clone()))
+                .unwrap_or(false)
+            {
+                panic_with_error!(env, FixtureError::Blocked);
+            }
+        }
+
+        MockToken::check_nonnegative_amount(env, amount);
+        let fee_bps: u32 = storage.instance().get(&FixtureKey::FeeBps).unwrap_or(0);
+        let fee = amount * fee_bps as i128 / BPS;
+        // the fee leaves the supply like a burn
+        MockToken::burn_balance(env, from, fee);
+        MockToken::debit(env, from, amount - fee);
+        MockToken::credit(env, to, amount - fee);
+        env.events().publish(
+            (symbol_short!("transfer"), from.clone(), to.clone()),
+            amount - fee,
+        );
+    }
+}
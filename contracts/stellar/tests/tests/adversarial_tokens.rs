@@ -0,0 +1,250 @@
+use fusionx_tests::token_fixtures::{AdversarialTokenClient, FixtureError};
+use fusionx_tests::*;
+use fusionx_types::Error;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::Address;
+
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+
+/// A maker holding `AMOUNT` of `token` wrapped, set up before any
+/// misbehaviour is switched on.
+fn maker(fx: &FusionX, token: &AdversarialTokenClient) -> Maker {
+    let maker = fx.maker(1, 1);
+    token.mint(&maker.address, &(AMOUNT as i128));
+    fx.wrapped.deposit(&token.address, &AMOUNT, &maker.address);
+    maker
+}
+
+/// A placed order selling `AMOUNT` of `token`.
+fn order(fx: &FusionX, maker: &Maker, token: &AdversarialTokenClient) -> TestOrder {
+    let mut order = fx.order(maker, AMOUNT, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    order.input.tokenIn = token.address.clone();
+    fx.place_order(maker, &order);
+    fx.set_time(FILL_TIME);
+    order
+}
+
+fn try_fill(fx: &FusionX, order: &TestOrder) -> Result<(), soroban_sdk::Error> {
+    match fx.resolver.try_deploy_escrow_src(
+        &fx.resolver.address,
+        &order.input.orderId,
+        &order.input.maxAmountOut,
+    ) {
+        Ok(_) => Ok(()),
+        Err(Ok(error)) => Err(error),
+        Err(Err(error)) => panic!("{error:?}"),
+    }
+}
+
+/// The fill was rolled back: no resolver, no escrow and the maker keeps its
+/// wrapped balance.
+fn assert_unfilled(fx: &FusionX, order: &TestOrder, token: &AdversarialTokenClient) {
+    assert_eq!(
+        fx.auction.get_order(&order.input.orderId).resolverAssigned,
+        None
+    );
+    assert_eq!(
+        fx.factory.try_get_escrow_from_orderid(&order.input.orderId),
+        Err(Ok(Error::OrderNotFound.into()))
+    );
+    assert_eq!(
+        fx.wrapped.get_balance(&token.address, &order.input.maker),
+        AMOUNT
+    );
+}
+
+#[test]
+fn fee_on_transfer_deposits_credit_what_arrived() {
+    let fx = FusionX::setup();
+    let token = fx.adversarial_token();
+    token.set_fee_bps(&100);
+    let holder = Address::generate(&fx.env);
+    token.mint(&holder, &(AMOUNT as i128));
+
+    fx.wrapped.deposit(&token.address, &AMOUNT, &holder);
+    assert_eq!(
+        fx.wrapped.get_balance(&token.address, &holder),
+        AMOUNT / 100 * 99
+    );
+    assert_eq!(fx.wrapped.reserves(&token.address).surplus, 0);
+
+    // the unwrap pays the fee again, WrappedTokens stays solvent
+    fx.wrapped
+        .withdraw(&token.address, &(AMOUNT / 100 * 99), &holder);
+    assert_eq!(token.balance(&holder), (AMOUNT / 10_000 * 9801) as i128);
+    assert_eq!(fx.wrapped.reserves(&token.address).surplus, 0);
+}
+
+#[test]
+fn fee_on_transfer_src_tokens_are_rejected_at_fill() {
+    let fx = FusionX::setup();
+    let token = fx.adversarial_token();
+    let maker = maker(&fx, &token);
+    let order = order(&fx, &maker, &token);
+
+    // unwrapping through the relayer leaves it short of what it has to lock
+    token.set_fee_bps(&100);
+    assert_eq!(
+        try_fill(&fx, &order),
+        Err(Error::InsufficientBalance.into())
+    );
+    assert_unfilled(&fx, &order, &token);
+
+    // signed transfers pay the escrow directly and are checked the same way
+    let mut direct = fx.order(&maker, AMOUNT, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    direct.input.tokenIn = token.address.clone();
+    fx.place_order_with_transfer(&maker, &direct);
+    fx.set_time(FILL_TIME + AUCTION_START_BUFFER);
+    assert_eq!(try_fill(&fx, &direct), Err(Error::EscrowUnderfunded.into()));
+}
+
+#[test]
+fn fee_on_transfer_dest_tokens_are_rejected() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let order = fx.order(&maker, AMOUNT, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    fx.fill(&order);
+
+    let token = fx.adversarial_token();
+    token.mint(&fx.resolver.address, &(MAX_AMOUNT_OUT as i128));
+    token.set_fee_bps(&100);
+    assert_eq!(
+        fx.resolver.try_deploy_escrow_dest(
+            &fx.resolver.address,
+            &order.input.orderId,
+            &order.input.hashLock,
+            &token.address,
+            &MAX_AMOUNT_OUT,
+            &maker.address,
+        ),
+        Err(Ok(Error::EscrowUnderfunded.into()))
+    );
+    assert_eq!(token.balance(&fx.resolver.address), MAX_AMOUNT_OUT as i128);
+}
+
+#[test]
+fn failing_transfers_revert_cleanly() {
+    let fx = FusionX::setup();
+    let token = fx.adversarial_token();
+    let maker = maker(&fx, &token);
+    let order = order(&fx, &maker, &token);
+
+    token.set_fail_transfers(&true);
+    assert_eq!(
+        fx.wrapped.try_withdraw(&token.address, &1, &maker.address),
+        Err(Ok(FixtureError::TransferFailed.into()))
+    );
+    assert_eq!(
+        try_fill(&fx, &order),
+        Err(FixtureError::TransferFailed.into())
+    );
+    assert_unfilled(&fx, &order, &token);
+
+    // nothing was consumed, the order fills once the token works again
+    token.set_fail_transfers(&false);
+    let src = fx.fill(&order);
+    assert_eq!(token.balance(&src.address), AMOUNT as i128);
+}
+
+#[test]
+fn blocked_makers_cannot_wrap_and_resolvers_recover_dest_funds() {
+    let fx = FusionX::setup();
+    let token = fx.adversarial_token();
+    let blocked = Address::generate(&fx.env);
+    token.mint(&blocked, &(AMOUNT as i128));
+    token.set_blocked(&blocked, &true);
+    assert_eq!(
+        fx.wrapped.try_deposit(&token.address, &AMOUNT, &blocked),
+        Err(Ok(FixtureError::Blocked.into()))
+    );
+    assert_eq!(fx.wrapped.total_supply(&token.address), 0);
+
+    // a maker blocked on the destination token can't be paid out, the
+    // resolver cancels and gets its funds back
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let order = fx.order(&maker, AMOUNT, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    fx.fill(&order);
+    token.mint(&fx.resolver.address, &(MAX_AMOUNT_OUT as i128));
+    fx.resolver.deploy_escrow_dest(
+        &fx.resolver.address,
+        &order.input.orderId,
+        &order.input.hashLock,
+        &token.address,
+        &MAX_AMOUNT_OUT,
+        &maker.address,
+    );
+    let dest = fx
+        .dest_factory
+        .get_escrow_from_orderid(&order.input.orderId);
+    token.set_blocked(&maker.address, &true);
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_dest(&fx.owner, &dest, &order.secret),
+        Err(Ok(FixtureError::Blocked.into()))
+    );
+    fx.set_time(FILL_TIME + DEST_ANYONE_UNLOCK_PERIOD);
+    fx.resolver.cancel_dest(&fx.owner, &dest);
+    assert_eq!(token.balance(&fx.resolver.address), MAX_AMOUNT_OUT as i128);
+}
+
+#[test]
+fn lying_balances_do_not_skew_accounting() {
+    let fx = FusionX::setup();
+    let token = fx.adversarial_token();
+    token.set_balance_offset(&1_000);
+    let maker = maker(&fx, &token);
+
+    // deposits and escrow funding only look at balance differences
+    assert_eq!(
+        fx.wrapped.get_balance(&token.address, &maker.address),
+        AMOUNT
+    );
+    let order = order(&fx, &maker, &token);
+    let src = fx.fill(&order);
+    assert_eq!(token.balance(&src.address), AMOUNT as i128 + 1_000);
+
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(token.balance(&fx.resolver.address), AMOUNT as i128 + 1_000);
+
+    // reserves can only report what the token claims
+    assert_eq!(fx.wrapped.reserves(&token.address).surplus, 1_000);
+}
+
+#[test]
+fn paused_tokens_hold_up_the_swap_until_unpaused() {
+    let fx = FusionX::setup();
+    let token = fx.adversarial_token();
+    let maker = maker(&fx, &token);
+    let order = order(&fx, &maker, &token);
+
+    token.set_paused(&true);
+    assert_eq!(try_fill(&fx, &order), Err(FixtureError::Paused.into()));
+    assert_unfilled(&fx, &order, &token);
+
+    token.set_paused(&false);
+    let src = fx.fill(&order);
+
+    // funds locked in the escrow wait out the pause
+    token.set_paused(&true);
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_src(&fx.owner, &src.address, &order.secret),
+        Err(Ok(FixtureError::Paused.into()))
+    );
+    token.set_paused(&false);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(token.balance(&fx.resolver.address), AMOUNT as i128);
+}
@@ -0,0 +1,209 @@
+use fusionx_tests::mock_router::{MockRouterClient, RouterError};
+use fusionx_tests::*;
+use fusionx_types::Error;
+use resolver::{Action, DestDeployment, Swap};
+use soroban_sdk::testutils::{MockAuth, MockAuthInvoke};
+use soroban_sdk::{vec, Address, IntoVal, Vec};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+// the order's whole input buys 1.1 times its max output
+const RATE: (i128, i128) = (11, 10_000_000_000);
+
+struct Setup<'a> {
+    fx: FusionX<'a>,
+    order: TestOrder,
+    amount_out: u128,
+}
+
+/// A placed order and a resolver holding MockToken but no `token_out`, the
+/// router sells it `token_out` at `RATE`.
+fn setup<'a>() -> Setup<'a> {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+
+    fx.resolver.sweep(&fx.token_out, &fx.owner);
+    fx.token_in.mint(&fx.resolver.address, &(AMOUNT_IN as i128));
+    let router = fx.mock_router();
+    router.set_rate(&fx.token_in.address, &fx.token_out, &RATE.0, &RATE.1);
+    fx.mint_token_out(&router.address, 10 * MAX_AMOUNT_OUT as i128);
+
+    let amount_out = fx.auction.get_amount_out(&order.input.orderId);
+    Setup {
+        fx,
+        order,
+        amount_out,
+    }
+}
+
+fn path(fx: &FusionX, token_out: &Address) -> Vec<Address> {
+    vec![&fx.env, fx.token_in.address.clone(), token_out.clone()]
+}
+
+/// Fill, buy the destination tokens and lock them in one batch.
+fn jit_fill(s: &Setup, token_out: &Address, min_amount_out: u128) -> Vec<Action> {
+    let fx = &s.fx;
+    vec![
+        &fx.env,
+        Action::Fill(
+            fx.resolver.address.clone(),
+            s.order.input.orderId.clone(),
+            s.amount_out,
+        ),
+        Action::Swap(Swap {
+            path: path(fx, token_out),
+            amount_in: AMOUNT_IN as i128,
+            min_amount_out: min_amount_out as i128,
+            deadline: FILL_TIME,
+        }),
+        Action::DeployDest(DestDeployment {
+            caller: fx.resolver.address.clone(),
+            order_id: s.order.input.orderId.clone(),
+            hash_lock: s.order.input.hashLock.clone(),
+            token_out: token_out.clone(),
+            amount_out: s.amount_out,
+            maker: s.order.input.maker.clone(),
+        }),
+    ]
+}
+
+#[test]
+fn swaps_fund_the_dest_escrow_in_the_fill_batch() {
+    let s = setup();
+    let fx = &s.fx;
+    assert_eq!(fx.resolver.inventory(&fx.token_out), 0);
+
+    fx.resolver
+        .execute_batch(&jit_fill(&s, &fx.token_out, s.amount_out));
+    let dest = fx
+        .dest_factory
+        .get_escrow_from_orderid(&s.order.input.orderId);
+    assert_eq!(fx.token_out_balance(&dest), s.amount_out as i128);
+    // what the swap bought beyond the fill stays with the resolver
+    assert_eq!(
+        fx.resolver.inventory(&fx.token_out),
+        (AMOUNT_IN as i128) * RATE.0 / RATE.1 - s.amount_out as i128
+    );
+    assert_eq!(fx.resolver.inventory(&fx.token_in.address), 0);
+}
+
+#[test]
+fn unprofitable_swaps_revert_the_fill() {
+    let s = setup();
+    let fx = &s.fx;
+
+    // the router's own check, asking for more than the rate gives
+    let too_much = (AMOUNT_IN * RATE.0 as u128) / RATE.1 as u128 + 1;
+    assert_eq!(
+        fx.resolver
+            .try_execute_batch(&jit_fill(&s, &fx.token_out, too_much)),
+        Err(Ok(RouterError::InsufficientOutputAmount.into()))
+    );
+
+    // and ours, the router promised enough but a fee ate into it
+    let taxed = fx.adversarial_token();
+    let router = MockRouterClient::new(&fx.env, &fx.resolver.get_router());
+    taxed.mint(&router.address, &(10 * MAX_AMOUNT_OUT as i128));
+    router.set_rate(&fx.token_in.address, &taxed.address, &RATE.0, &RATE.1);
+    taxed.set_fee_bps(&2_000);
+    assert_eq!(
+        fx.resolver
+            .try_execute_batch(&jit_fill(&s, &taxed.address, s.amount_out)),
+        Err(Ok(Error::SlippageExceeded.into()))
+    );
+
+    assert_eq!(
+        fx.auction
+            .get_order(&s.order.input.orderId)
+            .resolverAssigned,
+        None
+    );
+    assert_eq!(
+        fx.resolver.inventory(&fx.token_in.address),
+        AMOUNT_IN as i128
+    );
+}
+
+#[test]
+fn swap_checks_its_inputs() {
+    let s = setup();
+    let fx = &s.fx;
+    let swap = |path: Vec<Address>, amount_in: u128, deadline: u64| {
+        fx.resolver
+            .try_swap(&path, &(amount_in as i128), &1, &deadline)
+    };
+
+    assert_eq!(
+        swap(
+            vec![&fx.env, fx.token_in.address.clone()],
+            AMOUNT_IN,
+            FILL_TIME
+        ),
+        Err(Ok(Error::InvalidPath.into()))
+    );
+    assert_eq!(
+        swap(path(fx, &fx.token_out), AMOUNT_IN + 1, FILL_TIME),
+        Err(Ok(Error::InsufficientBalance.into()))
+    );
+    assert_eq!(
+        swap(path(fx, &fx.token_out), AMOUNT_IN, FILL_TIME - 1),
+        Err(Ok(RouterError::Expired.into()))
+    );
+    assert_eq!(
+        fx.resolver.swap(
+            &path(fx, &fx.token_out),
+            &(AMOUNT_IN as i128),
+            &1,
+            &FILL_TIME
+        ),
+        (AMOUNT_IN as i128) * RATE.0 / RATE.1
+    );
+
+    fx.env.set_auths(&[]);
+    assert!(swap(path(fx, &fx.token_out), 1, FILL_TIME).is_err());
+    assert!(fx.resolver.try_set_router(&fx.owner).is_err());
+}
+
+#[test]
+fn swaps_run_on_the_owners_signature_alone() {
+    let s = setup();
+    let fx = &s.fx;
+    let path = path(fx, &fx.token_out);
+    fx.env.mock_auths(&[MockAuth {
+        address: &fx.owner,
+        invoke: &MockAuthInvoke {
+            contract: &fx.resolver.address,
+            fn_name: "swap",
+            args: (path.clone(), AMOUNT_IN as i128, 1i128, FILL_TIME).into_val(&fx.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert_eq!(
+        fx.resolver
+            .swap(&path, &(AMOUNT_IN as i128), &1, &FILL_TIME),
+        (AMOUNT_IN as i128) * RATE.0 / RATE.1
+    );
+}
+
+#[test]
+fn swaps_need_a_router() {
+    let fx = FusionX::setup();
+    assert_eq!(
+        fx.resolver.try_get_router(),
+        Err(Ok(Error::RouterNotSet.into()))
+    );
+    assert_eq!(
+        fx.resolver.try_swap(
+            &vec![&fx.env, fx.token_in.address.clone(), fx.token_out.clone()],
+            &1,
+            &1,
+            &START_TIME,
+        ),
+        Err(Ok(Error::RouterNotSet.into()))
+    );
+}
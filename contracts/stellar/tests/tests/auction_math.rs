@@ -0,0 +1,152 @@
+use dutchauction::math::{amount_out, mul_div, normalize, Rounding, NORMALIZED_DECIMALS};
+use fusionx_tests::*;
+use fusionx_types::{Error, MAX_DECIMALS};
+use proptest::prelude::*;
+use soroban_sdk::BytesN;
+
+const DURATION: u64 = MAX_AUCTION_TIME;
+
+/// `(min, max)` with `min <= max` over the full `u128` range.
+fn bounds() -> impl Strategy<Value = (u128, u128)> {
+    (any::<u128>(), any::<u128>()).prop_map(|(a, b)| (a.min(b), a.max(b)))
+}
+
+proptest! {
+    #[test]
+    fn mul_div_matches_exact_product(a in any::<u64>(), b in any::<u64>(), d in 1..=u64::MAX) {
+        let product = a as u128 * b as u128;
+        let (a, b, d) = (a as u128, b as u128, d as u128);
+        let down = mul_div(a, b, d, Rounding::Down).unwrap();
+        let up = mul_div(a, b, d, Rounding::Up).unwrap();
+        prop_assert_eq!(down, product / d);
+        prop_assert_eq!(up, product.div_ceil(d));
+    }
+
+    #[test]
+    fn mul_div_never_overflows_below_one(a in any::<u128>(), (b, d) in bounds()) {
+        // b <= d keeps the result <= a, so it always fits
+        prop_assume!(d > 0);
+        let down = mul_div(a, b, d, Rounding::Down).unwrap();
+        let up = mul_div(a, b, d, Rounding::Up).unwrap();
+        prop_assert!(down <= a && up <= a);
+        prop_assert!(up == down || up == down + 1);
+    }
+
+    #[test]
+    fn mul_div_is_exact_across_the_full_range(a in any::<u128>(), b in 1..=u128::MAX) {
+        prop_assert_eq!(mul_div(a, b, b, Rounding::Down), Some(a));
+        prop_assert_eq!(mul_div(a, b, 1, Rounding::Up), a.checked_mul(b));
+        prop_assert_eq!(mul_div(a, b, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn amount_out_stays_within_bounds((min, max) in bounds(), elapsed in any::<u64>()) {
+        let out = amount_out(max, min, elapsed, DURATION).unwrap();
+        prop_assert!(min <= out && out <= max);
+    }
+
+    #[test]
+    fn amount_out_decreases_monotonically(
+        (min, max) in bounds(),
+        e1 in 0..2 * DURATION,
+        e2 in 0..2 * DURATION,
+    ) {
+        let (early, late) = (e1.min(e2), e1.max(e2));
+        prop_assert!(
+            amount_out(max, min, early, DURATION).unwrap()
+                >= amount_out(max, min, late, DURATION).unwrap()
+        );
+    }
+
+    #[test]
+    fn amount_out_hits_both_ends((min, max) in bounds(), after in any::<u64>()) {
+        prop_assert_eq!(amount_out(max, min, 0, DURATION), Some(max));
+        prop_assert_eq!(amount_out(max, min, DURATION, DURATION), Some(min));
+        prop_assert_eq!(amount_out(max, min, DURATION.saturating_add(after), DURATION), Some(min));
+    }
+
+    #[test]
+    fn amount_out_rounds_in_makers_favour(
+        min in any::<u64>(),
+        max in any::<u64>(),
+        elapsed in 0..=DURATION,
+    ) {
+        let (min, max) = (min.min(max) as u128, min.max(max) as u128);
+        let out = amount_out(max, min, elapsed, DURATION).unwrap();
+        // exact price: max - (max - min) * elapsed / DURATION
+        let exact_decrease = (max - min) * elapsed as u128;
+        let decrease = max - out;
+        prop_assert!(decrease * DURATION as u128 <= exact_decrease);
+        prop_assert!((decrease + 1) * DURATION as u128 > exact_decrease);
+    }
+
+    #[test]
+    fn normalize_scales_by_decimals(amount in any::<u64>(), decimals in 0..=MAX_DECIMALS) {
+        let amount = amount as u128;
+        if decimals <= NORMALIZED_DECIMALS {
+            let scale = 10u128.pow(NORMALIZED_DECIMALS - decimals);
+            prop_assert_eq!(normalize(amount, decimals, Rounding::Down), Some(amount * scale));
+        } else {
+            let scale = 10u128.pow(decimals - NORMALIZED_DECIMALS);
+            prop_assert_eq!(normalize(amount, decimals, Rounding::Down), Some(amount / scale));
+            prop_assert_eq!(normalize(amount, decimals, Rounding::Up), Some(amount.div_ceil(scale)));
+        }
+        prop_assert_eq!(normalize(u128::MAX, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected(min in 1..=u128::MAX, elapsed in any::<u64>()) {
+        prop_assert_eq!(amount_out(min - 1, min, elapsed, DURATION), None);
+        prop_assert_eq!(amount_out(min, min - 1, elapsed, 0), None);
+    }
+}
+
+#[test]
+fn auction_prices_orders_near_u128_max() {
+    let fx = FusionX::setup();
+    let amount_in = 10u128.pow(TOKEN_IN_DECIMALS);
+    let maker = fx.maker(amount_in, amount_in);
+    let mut order = fx.order(&maker, amount_in, u128::MAX - 1_000, u128::MAX);
+    // only a token with the most decimals keeps such amounts in range once normalized
+    order.input.tokenOut = BytesN::from_array(&fx.env, &[0x26; 32]);
+    fx.relayer
+        .set_dest_token_decimals(&order.input.tokenOut, &MAX_DECIMALS);
+    fx.place_order(&maker, &order);
+    let start = START_TIME + AUCTION_START_BUFFER;
+
+    let mut last = u128::MAX;
+    for elapsed in [
+        0,
+        1,
+        DURATION / 3,
+        DURATION / 2,
+        DURATION - 1,
+        DURATION,
+        2 * DURATION,
+    ] {
+        fx.set_time(start + elapsed);
+        let out = fx.auction.get_amount_out(&order.input.orderId);
+        assert!(out <= last && out >= u128::MAX - 1_000);
+        last = out;
+    }
+    assert_eq!(last, u128::MAX - 1_000);
+
+    // halfway through, the exact price has no remainder
+    fx.set_time(start + DURATION / 2);
+    assert_eq!(
+        fx.auction.get_amount_out(&order.input.orderId),
+        u128::MAX - 500
+    );
+}
+
+#[test]
+fn start_auction_rejects_inverted_bounds() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(1, 1);
+    let order = fx.order(&maker, 1, 2, 1);
+    assert_eq!(
+        fx.auction
+            .try_start_auction(&order.input, &TOKEN_IN_DECIMALS, &DEST_TOKEN_DECIMALS),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+}
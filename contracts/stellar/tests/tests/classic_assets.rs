@@ -0,0 +1,155 @@
+use ed25519_dalek::SigningKey;
+use fusionx_tests::*;
+use fusionx_types::Error;
+use soroban_sdk::testutils::{Address as _, IssuerFlags};
+use soroban_sdk::{token, Address};
+
+const AMOUNT_IN: u128 = 10_000_000_000; // 1000 USDC (7 decimals)
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+
+/// A revocable classic asset issued by the FusionX owner, and a maker holding
+/// `AMOUNT_IN` of it wrapped.
+fn usdc_maker(fx: &FusionX) -> (Address, Maker) {
+    let sac = fx.env.register_stellar_asset_contract_v2(fx.owner.clone());
+    sac.issuer().set_flag(IssuerFlags::RevocableFlag);
+    let usdc = sac.address();
+    fx.relayer.set_token_decimals(&usdc, &7);
+
+    let key = SigningKey::generate(&mut rand::thread_rng());
+    let maker = Maker {
+        address: account_address(&fx.env, &key),
+        key,
+    };
+    fx.trust(&maker.address, &usdc);
+    fx.trust(&maker.address, &fx.token_out);
+    token::StellarAssetClient::new(&fx.env, &usdc).mint(&maker.address, &(AMOUNT_IN as i128));
+    // no approve, the maker's auth of the deposit covers the transfer
+    fx.wrapped.deposit(&usdc, &AMOUNT_IN, &maker.address);
+    (usdc, maker)
+}
+
+#[test]
+fn classic_assets_wrap_and_unwrap() {
+    let fx = FusionX::setup();
+    let (usdc, maker) = usdc_maker(&fx);
+    let usdc_client = token::Client::new(&fx.env, &usdc);
+    assert_eq!(usdc_client.balance(&maker.address), 0);
+    assert_eq!(usdc_client.balance(&fx.wrapped.address), AMOUNT_IN as i128);
+    assert_eq!(fx.wrapped.get_balance(&usdc, &maker.address), AMOUNT_IN);
+
+    fx.wrapped.withdraw(&usdc, &AMOUNT_IN, &maker.address);
+    assert_eq!(usdc_client.balance(&maker.address), AMOUNT_IN as i128);
+    assert_eq!(fx.wrapped.reserves(&usdc).held, 0);
+
+    // accounts can only unwrap into a trustline
+    let other = account_address(&fx.env, &SigningKey::generate(&mut rand::thread_rng()));
+    fx.wrapped.deposit(&usdc, &1, &maker.address);
+    fx.wrapped.transfer(&usdc, &1, &other, &maker.address);
+    assert!(fx.wrapped.try_withdraw(&usdc, &1, &other).is_err());
+}
+
+#[test]
+fn makers_sell_classic_assets() {
+    let fx = FusionX::setup();
+    let (usdc, maker) = usdc_maker(&fx);
+    let mut order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    order.input.tokenIn = usdc.clone();
+    fx.place_order(&maker, &order);
+    assert_eq!(
+        fx.auction.get_order(&order.input.orderId).tokenInDecimals,
+        7
+    );
+
+    fx.set_time(START_TIME + AUCTION_START_BUFFER);
+    let src = fx.fill(&order);
+    let dest = fx.deploy_dest(&order);
+    let usdc_client = token::Client::new(&fx.env, &usdc);
+    assert_eq!(usdc_client.balance(&src.address), AMOUNT_IN as i128);
+
+    fx.set_time(START_TIME + AUCTION_START_BUFFER + SRC_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_dest(&fx.owner, &dest.address, &order.secret);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(usdc_client.balance(&fx.resolver.address), AMOUNT_IN as i128);
+    let amount_out = fx.auction.get_order(&order.input.orderId).amountOut;
+    assert_eq!(fx.token_out_balance(&maker.address), amount_out as i128);
+}
+
+#[test]
+fn dest_escrow_needs_the_makers_trustline() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(START_TIME + AUCTION_START_BUFFER);
+    fx.fill(&order);
+
+    let deploy_dest = |maker: &Address| {
+        fx.resolver.try_deploy_escrow_dest(
+            &fx.resolver.address,
+            &order.input.orderId,
+            &order.input.hashLock,
+            &fx.token_out,
+            &MAX_AMOUNT_OUT,
+            maker,
+        )
+    };
+    let resolver_funds = fx.token_out_balance(&fx.resolver.address);
+
+    // an account that never trusted the asset
+    let untrusted = account_address(&fx.env, &SigningKey::generate(&mut rand::thread_rng()));
+    assert_eq!(
+        deploy_dest(&untrusted),
+        Err(Ok(Error::TrustlineMissing.into()))
+    );
+
+    // a trustline the issuer deauthorized
+    let token_out = fx.env.register_stellar_asset_contract_v2(fx.owner.clone());
+    token_out.issuer().set_flag(IssuerFlags::RevocableFlag);
+    fx.trust(&maker.address, &token_out.address());
+    token::StellarAssetClient::new(&fx.env, &token_out.address())
+        .set_authorized(&maker.address, &false);
+    assert_eq!(
+        fx.resolver.try_deploy_escrow_dest(
+            &fx.resolver.address,
+            &order.input.orderId,
+            &order.input.hashLock,
+            &token_out.address(),
+            &MAX_AMOUNT_OUT,
+            &maker.address,
+        ),
+        Err(Ok(Error::TrustlineMissing.into()))
+    );
+    assert_eq!(fx.token_out_balance(&fx.resolver.address), resolver_funds);
+
+    // contract makers hold any asset without a trustline
+    assert!(deploy_dest(&Address::generate(&fx.env)).is_ok());
+}
+
+#[test]
+fn dest_escrow_accepts_contract_tokens() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(START_TIME + AUCTION_START_BUFFER);
+    fx.fill(&order);
+
+    // MockToken has neither trustlines nor `authorized`
+    fx.token_in
+        .mint(&fx.resolver.address, &(MAX_AMOUNT_OUT as i128));
+    fx.resolver.deploy_escrow_dest(
+        &fx.resolver.address,
+        &order.input.orderId,
+        &order.input.hashLock,
+        &fx.token_in.address,
+        &MAX_AMOUNT_OUT,
+        &maker.address,
+    );
+    let dest = fx
+        .dest_factory
+        .get_escrow_from_orderid(&order.input.orderId);
+    assert_eq!(fx.token_in_balance(&dest), MAX_AMOUNT_OUT as i128);
+}
@@ -0,0 +1,381 @@
+//! Full Stellar <-> EVM swaps against the simulated EVM escrows. Both chains
+//! read the Soroban ledger timestamp, so `fx.set_time` moves them together.
+
+use fusionx_evm_sim::{Address as EvmAddress, EvmChain, EvmError, Immutables, Stage, Timelocks};
+use fusionx_tests::*;
+use fusionx_types::interfaces::{EscrowDestClient, EscrowSrcClient};
+use fusionx_types::{Error, EscrowWithdrawal, SignalSecretShare};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{Address, BytesN};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER + MAX_AUCTION_TIME / 2;
+
+const EVM_DEPOSIT: u128 = 10_000_000_000_000_000; // 0.01 POL
+const EVM_TIMELOCKS: Timelocks = Timelocks {
+    deployed_at: 0,
+    src_withdrawal: 60,
+    src_public_withdrawal: 10 * 60,
+    src_cancellation: 15 * 60,
+    src_public_cancellation: 20 * 60,
+    dst_withdrawal: 60,
+    dst_public_withdrawal: 3 * 60,
+    dst_cancellation: 5 * 60,
+};
+
+struct TwoChains<'a> {
+    fx: FusionX<'a>,
+    evm: EvmChain,
+    usdc: EvmAddress,
+    evm_maker: EvmAddress,
+    evm_resolver: EvmAddress, // the resolver's taker account on the EVM side
+    evm_other: EvmAddress,    // another resolver holding the access token
+}
+
+fn two_chains<'a>() -> TwoChains<'a> {
+    let fx = FusionX::setup();
+    let env = fx.env.clone();
+    let mut evm = EvmChain::new(move || env.ledger().timestamp());
+    let usdc = EvmAddress::labeled("USDC");
+    let evm_maker = EvmAddress::labeled("maker");
+    let evm_resolver = EvmAddress::labeled("resolver");
+    let evm_other = EvmAddress::labeled("other");
+
+    evm.mint(usdc, evm_maker, MAX_AMOUNT_OUT);
+    evm.mint(usdc, evm_resolver, 10 * MAX_AMOUNT_OUT);
+    for account in [evm_resolver, evm_other] {
+        evm.deal(account, 10 * EVM_DEPOSIT);
+        evm.mint(evm.access_token(), account, 1);
+    }
+    TwoChains {
+        fx,
+        evm,
+        usdc,
+        evm_maker,
+        evm_resolver,
+        evm_other,
+    }
+}
+
+/// An order selling MockToken on Stellar for USDC on the EVM chain.
+struct StellarToEvm<'a> {
+    order: TestOrder,
+    src: EscrowSrcClient<'a>,
+    dst: EvmAddress,
+    immutables: Immutables,
+}
+
+impl<'a> TwoChains<'a> {
+    /// Places and fills the order on Stellar, then the resolver locks the
+    /// auction price on the EVM side.
+    fn stellar_to_evm(&mut self) -> StellarToEvm<'a> {
+        let maker = self.fx.maker(AMOUNT_IN, AMOUNT_IN);
+        let order = self
+            .fx
+            .order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+        self.fx.place_order(&maker, &order);
+        self.fx.set_time(FILL_TIME);
+        let src = self.fx.fill(&order);
+
+        let amount_out = self.fx.auction.get_order(&order.input.orderId).amountOut;
+        let (dst, immutables) = self
+            .evm
+            .create_dst_escrow(
+                self.evm_resolver,
+                Immutables {
+                    order_hash: order.input.orderId.to_array(),
+                    hashlock: order.input.hashLock.to_array(),
+                    maker: self.evm_maker,
+                    taker: self.evm_resolver,
+                    token: self.usdc,
+                    amount: amount_out,
+                    safety_deposit: EVM_DEPOSIT,
+                    timelocks: EVM_TIMELOCKS,
+                },
+                FILL_TIME + SRC_ANYONE_UNLOCK_PERIOD,
+            )
+            .unwrap();
+        StellarToEvm {
+            order,
+            src,
+            dst,
+            immutables,
+        }
+    }
+
+    fn bytes(&self, bytes: [u8; 32]) -> BytesN<32> {
+        BytesN::from_array(&self.fx.env, &bytes)
+    }
+}
+
+fn padded(address: EvmAddress) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[12..].copy_from_slice(&address.0);
+    bytes
+}
+
+#[test]
+fn stellar_to_evm_swap() {
+    let mut chains = two_chains();
+    let swap = chains.stellar_to_evm();
+    let fx = &chains.fx;
+    let amount_out = swap.immutables.amount;
+
+    // both escrows are funded, the relayer tells the maker to share the secret
+    assert_eq!(chains.evm.balance_of(chains.usdc, swap.dst), amount_out);
+    assert_eq!(chains.evm.native_balance(swap.dst), EVM_DEPOSIT);
+    let escrow_src = fx.random_bytes();
+    let escrow_dest = chains.bytes(padded(swap.dst));
+    fx.resolver.notify_relayer(
+        &swap.order.input.orderId,
+        &escrow_src,
+        &escrow_dest,
+        &fx.resolver.address,
+    );
+    assert_eq!(
+        fx.events::<SignalSecretShare>(&fx.relayer.address, ("signal", "secret")),
+        [SignalSecretShare {
+            escrow_src,
+            escrow_dest,
+            order_id: swap.order.input.orderId.clone(),
+            resolver: fx.resolver.address.clone(),
+        }]
+    );
+
+    // the resolver pays the maker on the EVM side, which reveals the secret
+    fx.set_time(FILL_TIME + EVM_TIMELOCKS.dst_withdrawal as u64);
+    let secret = swap.order.secret.to_array();
+    chains
+        .evm
+        .escrow_dst(swap.dst)
+        .withdraw(chains.evm_resolver, secret, &swap.immutables)
+        .unwrap();
+    assert_eq!(
+        chains.evm.balance_of(chains.usdc, chains.evm_maker),
+        MAX_AMOUNT_OUT + amount_out
+    );
+    assert_eq!(
+        chains.evm.native_balance(chains.evm_resolver),
+        10 * EVM_DEPOSIT
+    );
+
+    // and claims the maker's tokens on Stellar with the secret from the logs
+    let revealed = chains.bytes(chains.evm.revealed_secret(swap.dst).unwrap());
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_src(&fx.owner, &swap.src.address, &revealed);
+    assert_eq!(fx.token_in_balance(&fx.resolver.address), AMOUNT_IN as i128);
+    assert_eq!(fx.xlm_balance(&swap.src.address), 0);
+}
+
+#[test]
+fn evm_to_stellar_swap() {
+    let mut chains = two_chains();
+    let fx = &chains.fx;
+    let maker = Address::generate(&fx.env);
+    let secret = fx.random_bytes();
+    let hash_lock = fx.keccak(&secret);
+    let order_id = fx.random_bytes();
+    let amount_in = MAX_AMOUNT_OUT;
+    let amount_out = 500_000_000;
+
+    // the resolver fills the maker's EVM order into a source escrow
+    fx.set_time(FILL_TIME);
+    let (src, immutables) = chains
+        .evm
+        .create_src_escrow(Immutables {
+            order_hash: order_id.to_array(),
+            hashlock: hash_lock.to_array(),
+            maker: chains.evm_maker,
+            taker: chains.evm_resolver,
+            token: chains.usdc,
+            amount: amount_in,
+            safety_deposit: EVM_DEPOSIT,
+            timelocks: EVM_TIMELOCKS,
+        })
+        .unwrap();
+    assert_eq!(chains.evm.balance_of(chains.usdc, chains.evm_maker), 0);
+    assert_eq!(chains.evm.balance_of(chains.usdc, src), amount_in);
+
+    // and locks token_out for the maker on Stellar
+    fx.resolver.deploy_escrow_dest(
+        &fx.resolver.address,
+        &order_id,
+        &hash_lock,
+        &fx.token_out,
+        &amount_out,
+        &maker,
+    );
+    let dest = EscrowDestClient::new(&fx.env, &fx.dest_factory.get_escrow_from_orderid(&order_id));
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    fx.resolver.withdraw_dest(&fx.owner, &dest.address, &secret);
+    let revealed = fx.events::<EscrowWithdrawal>(&dest.address, ("escrow", "withdraw"));
+    assert_eq!(fx.token_out_balance(&maker), amount_out as i128);
+
+    chains
+        .evm
+        .escrow_src(src)
+        .withdraw(
+            chains.evm_resolver,
+            revealed[0].secret.to_array(),
+            &immutables,
+        )
+        .unwrap();
+    assert_eq!(
+        chains.evm.balance_of(chains.usdc, chains.evm_resolver),
+        10 * MAX_AMOUNT_OUT + amount_in
+    );
+    assert_eq!(chains.evm.native_balance(src), 0);
+}
+
+#[test]
+fn resolver_failure_falls_back_to_public_withdrawals() {
+    let mut chains = two_chains();
+    let swap = chains.stellar_to_evm();
+    let fx = &chains.fx;
+    let secret = swap.order.secret.to_array();
+    let other = fx.other_resolver();
+
+    // the maker shared the secret but the resolver went silent
+    fx.set_time(FILL_TIME + EVM_TIMELOCKS.dst_public_withdrawal as u64 - 1);
+    assert_eq!(
+        chains
+            .evm
+            .escrow_dst(swap.dst)
+            .public_withdraw(chains.evm_other, secret, &swap.immutables),
+        Err(EvmError::InvalidTime)
+    );
+    fx.set_time(FILL_TIME + EVM_TIMELOCKS.dst_public_withdrawal as u64);
+    assert_eq!(
+        chains
+            .evm
+            .escrow_dst(swap.dst)
+            .public_withdraw(chains.evm_maker, secret, &swap.immutables),
+        Err(EvmError::InvalidCaller)
+    );
+    chains
+        .evm
+        .escrow_dst(swap.dst)
+        .public_withdraw(chains.evm_other, secret, &swap.immutables)
+        .unwrap();
+    assert_eq!(
+        chains.evm.balance_of(chains.usdc, chains.evm_maker),
+        MAX_AMOUNT_OUT + swap.immutables.amount
+    );
+    assert_eq!(
+        chains.evm.native_balance(chains.evm_other),
+        11 * EVM_DEPOSIT
+    );
+
+    // on Stellar the exclusive window passes and anyone whitelisted finishes
+    let revealed = chains.bytes(chains.evm.revealed_secret(swap.dst).unwrap());
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_src(&fx.owner, &swap.src.address, &revealed),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+    swap.src.public_withdraw(&revealed, &other);
+    assert_eq!(fx.token_in_balance(&fx.resolver.address), AMOUNT_IN as i128);
+    assert_eq!(fx.xlm_balance(&other), SECURITY_DEPOSIT);
+}
+
+#[test]
+fn timeout_refunds_both_chains() {
+    let mut chains = two_chains();
+    let resolver_usdc = chains.evm.balance_of(chains.usdc, chains.evm_resolver);
+    let swap = chains.stellar_to_evm();
+    let fx = &chains.fx;
+    let dst_cancellation = swap.immutables.timelocks.get(Stage::DstCancellation);
+
+    // the secret is never shared, the resolver takes its EVM tokens back first
+    fx.set_time(dst_cancellation - 1);
+    assert_eq!(
+        chains
+            .evm
+            .escrow_dst(swap.dst)
+            .cancel(chains.evm_resolver, &swap.immutables),
+        Err(EvmError::InvalidTime)
+    );
+    fx.set_time(dst_cancellation);
+    assert_eq!(
+        chains.evm.escrow_dst(swap.dst).withdraw(
+            chains.evm_resolver,
+            swap.order.secret.to_array(),
+            &swap.immutables
+        ),
+        Err(EvmError::InvalidTime)
+    );
+    chains
+        .evm
+        .escrow_dst(swap.dst)
+        .cancel(chains.evm_resolver, &swap.immutables)
+        .unwrap();
+    assert_eq!(
+        chains.evm.balance_of(chains.usdc, chains.evm_resolver),
+        resolver_usdc
+    );
+    assert_eq!(chains.evm.revealed_secret(swap.dst), None);
+
+    // then returns the maker's tokens on Stellar
+    fx.set_time(FILL_TIME + SRC_ANYONE_UNLOCK_PERIOD);
+    fx.resolver.cancel_src(&fx.owner, &swap.src.address);
+    assert_eq!(
+        fx.token_in_balance(&swap.order.input.maker),
+        AMOUNT_IN as i128
+    );
+    assert_eq!(fx.token_in_balance(&swap.src.address), 0);
+}
+
+#[test]
+fn evm_escrows_enforce_access_rules() {
+    let mut chains = two_chains();
+    let swap = chains.stellar_to_evm();
+    let secret = swap.order.secret.to_array();
+    chains
+        .fx
+        .set_time(FILL_TIME + EVM_TIMELOCKS.dst_withdrawal as u64);
+
+    let mut dst = chains.evm.escrow_dst(swap.dst);
+    assert_eq!(
+        dst.withdraw(chains.evm_other, secret, &swap.immutables),
+        Err(EvmError::InvalidCaller)
+    );
+    assert_eq!(
+        dst.withdraw(chains.evm_resolver, [0; 32], &swap.immutables),
+        Err(EvmError::InvalidSecret)
+    );
+    let tampered = Immutables {
+        maker: chains.evm_resolver,
+        ..swap.immutables.clone()
+    };
+    assert_eq!(
+        dst.withdraw(chains.evm_resolver, secret, &tampered),
+        Err(EvmError::InvalidImmutables)
+    );
+    dst.withdraw(chains.evm_resolver, secret, &swap.immutables)
+        .unwrap();
+    // the escrow is empty now
+    assert_eq!(
+        dst.withdraw(chains.evm_resolver, secret, &swap.immutables),
+        Err(EvmError::InsufficientBalance)
+    );
+
+    // a dst escrow that outlives the src cancellation is refused
+    let resolver_usdc = chains.evm.balance_of(chains.usdc, chains.evm_resolver);
+    assert_eq!(
+        chains.evm.create_dst_escrow(
+            chains.evm_resolver,
+            swap.immutables.clone(),
+            chains.evm.now() + EVM_TIMELOCKS.dst_cancellation as u64 - 1,
+        ),
+        Err(EvmError::InvalidCreationTime)
+    );
+    assert_eq!(
+        chains.evm.balance_of(chains.usdc, chains.evm_resolver),
+        resolver_usdc
+    );
+}
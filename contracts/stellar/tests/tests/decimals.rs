@@ -0,0 +1,101 @@
+use fusionx_tests::*;
+use fusionx_types::{Error, MAX_DECIMALS};
+use soroban_sdk::BytesN;
+
+const ONE_TOKEN_IN: u128 = 1_000_000_000_000_000_000; // 18 decimals
+const MIN_AMOUNT_OUT: u128 = 900_000_000; // 900 with 6 decimals
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000; // 1000 with 6 decimals
+const ONE: u128 = 1_000_000_000_000_000_000; // 1.0 normalized
+
+#[test]
+fn relayer_registers_decimals() {
+    let fx = FusionX::setup();
+    assert_eq!(
+        fx.relayer.get_token_decimals(&fx.token_in.address),
+        TOKEN_IN_DECIMALS
+    );
+    assert_eq!(
+        fx.relayer.get_dest_token_decimals(&fx.dest_token),
+        DEST_TOKEN_DECIMALS
+    );
+
+    let unknown = BytesN::from_array(&fx.env, &[0xee; 32]);
+    assert_eq!(
+        fx.relayer.try_get_dest_token_decimals(&unknown),
+        Err(Ok(Error::TokenNotRegistered.into()))
+    );
+    assert_eq!(
+        fx.relayer.try_get_token_decimals(&fx.xlm),
+        Err(Ok(Error::TokenNotRegistered.into()))
+    );
+    assert_eq!(
+        fx.relayer
+            .try_set_dest_token_decimals(&unknown, &(MAX_DECIMALS + 1)),
+        Err(Ok(Error::InvalidDecimals.into()))
+    );
+
+    // classic assets and their SACs use 7 decimals
+    fx.relayer.set_token_decimals(&fx.xlm, &7);
+    assert_eq!(fx.relayer.get_token_decimals(&fx.xlm), 7);
+}
+
+#[test]
+fn order_records_decimals_and_prices_are_normalized() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(ONE_TOKEN_IN, ONE_TOKEN_IN);
+    let order = fx.order(&maker, ONE_TOKEN_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    let order_id = &order.input.orderId;
+
+    let stored = fx.auction.get_order(order_id);
+    assert_eq!(stored.tokenInDecimals, TOKEN_IN_DECIMALS);
+    assert_eq!(stored.tokenOutDecimals, DEST_TOKEN_DECIMALS);
+
+    assert_eq!(
+        fx.auction.get_price_range(order_id),
+        (900 * ONE, 1000 * ONE)
+    );
+    assert_eq!(fx.auction.get_price(order_id), 1000 * ONE);
+
+    fx.set_time(START_TIME + AUCTION_START_BUFFER + MAX_AUCTION_TIME / 2);
+    assert_eq!(fx.auction.get_amount_out(order_id), 950_000_000);
+    assert_eq!(fx.auction.get_normalized_amount_out(order_id), 950 * ONE);
+    assert_eq!(fx.auction.get_price(order_id), 950 * ONE);
+}
+
+#[test]
+fn price_of_a_partial_token_scales_up() {
+    let fx = FusionX::setup();
+    // half a token in for 475 out is the same 950 price
+    let maker = fx.maker(ONE_TOKEN_IN, ONE_TOKEN_IN / 2);
+    let order = fx.order(&maker, ONE_TOKEN_IN / 2, 475_000_000, 475_000_000);
+    fx.place_order(&maker, &order);
+    assert_eq!(fx.auction.get_price(&order.input.orderId), 950 * ONE);
+}
+
+#[test]
+fn start_auction_rejects_unrepresentable_amounts() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(1, 1);
+    let start = |amount_in: u128, max_amount_out: u128| {
+        let order = fx.order(&maker, amount_in, 0, max_amount_out);
+        fx.auction
+            .try_start_auction(&order.input, &TOKEN_IN_DECIMALS, &DEST_TOKEN_DECIMALS)
+    };
+
+    // tokens move as i128
+    assert_eq!(
+        start(i128::MAX as u128 + 1, MAX_AMOUNT_OUT),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    assert_eq!(
+        start(0, MAX_AMOUNT_OUT),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    // 6 decimals scaled to 18 overflows
+    assert_eq!(
+        start(ONE_TOKEN_IN, u128::MAX / 10u128.pow(12) + 1),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    assert_eq!(start(ONE_TOKEN_IN, u128::MAX / 10u128.pow(18)), Ok(Ok(())));
+}
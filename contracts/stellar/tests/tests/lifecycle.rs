@@ -0,0 +1,576 @@
+use fusionx_tests::*;
+use fusionx_types::interfaces::{EscrowDestClient, EscrowSrcClient};
+use fusionx_types::{Error, EscrowCancelled, EscrowWithdrawal, Immutables, SignalSecretShare};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{Address, BytesN};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000; // 1 MockToken (18 decimals)
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER + MAX_AUCTION_TIME / 2;
+
+struct Filled<'a> {
+    maker: Maker,
+    order: TestOrder,
+    src: EscrowSrcClient<'a>,
+    dest: EscrowDestClient<'a>,
+}
+
+/// Places an order and fills it halfway through the auction.
+fn filled<'a>(fx: &FusionX<'a>) -> Filled<'a> {
+    let maker = fx.maker(2 * AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let src = fx.fill(&order);
+    let dest = fx.deploy_dest(&order);
+    Filled {
+        maker,
+        order,
+        src,
+        dest,
+    }
+}
+
+fn amount_out(fx: &FusionX, order: &TestOrder) -> u128 {
+    fx.auction.get_order(&order.input.orderId).amountOut
+}
+
+#[test]
+fn place_order_sets_permit_and_starts_auction() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(2 * AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+
+    assert_eq!(fx.token_in_balance(&maker.address), AMOUNT_IN as i128);
+    assert_eq!(fx.token_in_balance(&fx.wrapped.address), AMOUNT_IN as i128);
+    assert_eq!(
+        fx.wrapped.get_balance(&fx.token_in.address, &maker.address),
+        AMOUNT_IN
+    );
+
+    fx.place_order(&maker, &order);
+
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.relayer.address),
+        AMOUNT_IN
+    );
+    let stored = fx.auction.get_order(&order.input.orderId);
+    assert_eq!(
+        stored.startTime,
+        (START_TIME + AUCTION_START_BUFFER) as u128
+    );
+    assert_eq!(stored.amountOut, 0);
+    assert_eq!(stored.resolverAssigned, None);
+    assert_eq!(
+        fx.auction.get_amount_out(&order.input.orderId),
+        MAX_AMOUNT_OUT
+    );
+}
+
+#[test]
+fn fill_before_auction_start_fails() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+
+    fx.set_time(START_TIME + AUCTION_START_BUFFER - 1);
+    assert_eq!(
+        fx.resolver.try_deploy_escrow_src(
+            &fx.resolver.address,
+            &order.input.orderId,
+            &order.input.maxAmountOut,
+        ),
+        Err(Ok(Error::AuctionNotStarted.into()))
+    );
+}
+
+#[test]
+fn fill_reverts_above_max_amount_out() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+
+    // quoted for a later ledger, the price is still higher when it lands
+    fx.set_time(FILL_TIME + 60);
+    let quoted = fx.auction.get_amount_out(&order.input.orderId);
+    fx.set_time(FILL_TIME);
+    assert_eq!(
+        fx.resolver
+            .try_deploy_escrow_src(&fx.resolver.address, &order.input.orderId, &quoted),
+        Err(Ok(Error::SlippageExceeded.into()))
+    );
+    assert_eq!(
+        fx.auction.get_order(&order.input.orderId).resolverAssigned,
+        None
+    );
+
+    fx.set_time(FILL_TIME + 60);
+    fx.resolver
+        .deploy_escrow_src(&fx.resolver.address, &order.input.orderId, &quoted);
+    assert_eq!(amount_out(&fx, &order), quoted);
+}
+
+#[test]
+fn only_whitelisted_resolvers_can_fill() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.relayer.remove_resolver(&fx.resolver.address);
+
+    fx.set_time(FILL_TIME);
+    assert_eq!(
+        fx.resolver.try_deploy_escrow_src(
+            &fx.resolver.address,
+            &order.input.orderId,
+            &order.input.maxAmountOut,
+        ),
+        Err(Ok(Error::NotResolver.into()))
+    );
+}
+
+#[test]
+fn fill_locks_funds_and_deposits_in_both_escrows() {
+    let fx = FusionX::setup();
+    let resolver_xlm = fx.xlm_balance(&fx.resolver.address);
+    let Filled {
+        maker,
+        order,
+        src,
+        dest,
+        ..
+    } = filled(&fx);
+
+    let stored = fx.auction.get_order(&order.input.orderId);
+    assert_eq!(stored.amountOut, (MIN_AMOUNT_OUT + MAX_AMOUNT_OUT) / 2);
+    assert_eq!(stored.resolverAssigned, Some(fx.resolver.address.clone()));
+
+    // maker side: wrapped balance and permit are spent, the underlying sits in the src escrow
+    assert_eq!(
+        fx.wrapped.get_balance(&fx.token_in.address, &maker.address),
+        0
+    );
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.relayer.address),
+        0
+    );
+    assert_eq!(fx.token_in_balance(&fx.wrapped.address), 0);
+    assert_eq!(fx.token_in_balance(&src.address), AMOUNT_IN as i128);
+
+    // resolver side: token_out and one deposit per escrow
+    assert_eq!(
+        fx.token_out_balance(&dest.address),
+        stored.amountOut as i128
+    );
+    assert_eq!(fx.xlm_balance(&src.address), SECURITY_DEPOSIT);
+    assert_eq!(fx.xlm_balance(&dest.address), SECURITY_DEPOSIT);
+    assert_eq!(
+        fx.xlm_balance(&fx.resolver.address),
+        resolver_xlm - 2 * SECURITY_DEPOSIT
+    );
+    assert_eq!(
+        fx.factory.get_orderid_from_escrow(&src.address),
+        order.input.orderId
+    );
+    assert_eq!(
+        fx.dest_factory.get_orderid_from_escrow(&dest.address),
+        order.input.orderId
+    );
+}
+
+#[test]
+fn notify_relayer_signals_secret_share() {
+    let fx = FusionX::setup();
+    let Filled { order, .. } = filled(&fx);
+    let escrow_src = fx.random_bytes();
+    let escrow_dest = fx.random_bytes();
+
+    fx.resolver.notify_relayer(
+        &order.input.orderId,
+        &escrow_src,
+        &escrow_dest,
+        &fx.resolver.address,
+    );
+
+    assert_eq!(
+        fx.events::<SignalSecretShare>(&fx.relayer.address, ("signal", "secret")),
+        [SignalSecretShare {
+            escrow_src,
+            escrow_dest,
+            order_id: order.input.orderId,
+            resolver: fx.resolver.address.clone(),
+        }]
+    );
+}
+
+#[test]
+fn exclusive_withdrawals_complete_the_swap() {
+    let fx = FusionX::setup();
+    let resolver_xlm = fx.xlm_balance(&fx.resolver.address);
+    let Filled {
+        maker,
+        order,
+        src,
+        dest,
+    } = filled(&fx);
+    let amount_out = amount_out(&fx, &order);
+    let resolver_token_out = fx.token_out_balance(&fx.resolver.address);
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_dest(&fx.owner, &dest.address, &order.secret);
+    assert_eq!(
+        fx.events::<EscrowWithdrawal>(&dest.address, ("escrow", "withdraw")),
+        [EscrowWithdrawal {
+            order_id: order.input.orderId.clone(),
+            secret: order.secret.clone(),
+            to: maker.address.clone(),
+            amount: amount_out,
+        }]
+    );
+    assert_eq!(fx.token_out_balance(&maker.address), amount_out as i128);
+    assert_eq!(fx.token_out_balance(&dest.address), 0);
+
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(
+        fx.events::<EscrowWithdrawal>(&src.address, ("escrow", "withdraw")),
+        [EscrowWithdrawal {
+            order_id: order.input.orderId.clone(),
+            secret: order.secret.clone(),
+            to: fx.resolver.address.clone(),
+            amount: AMOUNT_IN,
+        }]
+    );
+    assert_eq!(fx.token_in_balance(&fx.resolver.address), AMOUNT_IN as i128);
+    assert_eq!(fx.token_in_balance(&src.address), 0);
+
+    // both deposits come back, the maker keeps the unwrapped half of the mint
+    assert_eq!(fx.xlm_balance(&fx.resolver.address), resolver_xlm);
+    assert_eq!(fx.xlm_balance(&src.address), 0);
+    assert_eq!(fx.xlm_balance(&dest.address), 0);
+    assert_eq!(
+        fx.token_out_balance(&fx.resolver.address),
+        resolver_token_out
+    );
+    assert_eq!(fx.token_in_balance(&maker.address), AMOUNT_IN as i128);
+}
+
+#[test]
+fn withdraw_is_limited_to_secret_and_exclusive_window() {
+    let fx = FusionX::setup();
+    let Filled {
+        order, src, dest, ..
+    } = filled(&fx);
+    let wrong_secret: BytesN<32> = fx.random_bytes();
+
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK - 1);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_src(&fx.owner, &src.address, &order.secret),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_dest(&fx.owner, &dest.address, &order.secret),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_src(&fx.owner, &src.address, &wrong_secret),
+        Err(Ok(Error::InvalidSecret.into()))
+    );
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_dest(&fx.owner, &dest.address, &wrong_secret),
+        Err(Ok(Error::InvalidSecret.into()))
+    );
+
+    fx.set_time(FILL_TIME + DEST_RESOLVER_UNLOCK_PERIOD);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_dest(&fx.owner, &dest.address, &order.secret),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_src(&fx.owner, &src.address, &order.secret),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    // nothing moved
+    assert_eq!(fx.token_in_balance(&src.address), AMOUNT_IN as i128);
+    assert_eq!(
+        fx.token_out_balance(&dest.address),
+        amount_out(&fx, &order) as i128
+    );
+}
+
+#[test]
+fn public_withdrawals_after_exclusive_window() {
+    let fx = FusionX::setup();
+    let Filled {
+        maker,
+        order,
+        src,
+        dest,
+    } = filled(&fx);
+    let amount_out = amount_out(&fx, &order);
+    let other = fx.other_resolver();
+    let stranger = soroban_sdk::Address::from_str(
+        &fx.env,
+        "GB3I5T27E4VXU7PTTIWPBFJTKPUT4SIXKCMK3V6FKU6QXL7Q4TWCZTWQ",
+    );
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    assert_eq!(
+        dest.try_public_withdraw(&order.secret, &other),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    fx.set_time(FILL_TIME + DEST_RESOLVER_UNLOCK_PERIOD);
+    assert_eq!(
+        dest.try_public_withdraw(&order.secret, &stranger),
+        Err(Ok(Error::NotResolver.into()))
+    );
+    dest.public_withdraw(&order.secret, &other);
+    assert_eq!(
+        fx.events::<EscrowWithdrawal>(&dest.address, ("escrow", "withdraw")),
+        [EscrowWithdrawal {
+            order_id: order.input.orderId.clone(),
+            secret: order.secret.clone(),
+            to: maker.address.clone(),
+            amount: amount_out,
+        }]
+    );
+    assert_eq!(fx.token_out_balance(&maker.address), amount_out as i128);
+    assert_eq!(fx.xlm_balance(&other), SECURITY_DEPOSIT);
+
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    src.public_withdraw(&order.secret, &other);
+    assert_eq!(
+        fx.events::<EscrowWithdrawal>(&src.address, ("escrow", "withdraw")),
+        [EscrowWithdrawal {
+            order_id: order.input.orderId.clone(),
+            secret: order.secret.clone(),
+            to: fx.resolver.address.clone(),
+            amount: AMOUNT_IN,
+        }]
+    );
+    // the locked tokens still go to the executive resolver, only the deposit rewards the caller
+    assert_eq!(fx.token_in_balance(&fx.resolver.address), AMOUNT_IN as i128);
+    assert_eq!(fx.xlm_balance(&other), 2 * SECURITY_DEPOSIT);
+
+    fx.set_time(FILL_TIME + SRC_ANYONE_UNLOCK_PERIOD);
+    assert_eq!(
+        src.try_public_withdraw(&order.secret, &other),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+}
+
+#[test]
+fn resolver_public_operations_follow_each_side() {
+    let fx = FusionX::setup();
+    let Filled {
+        maker,
+        order,
+        src,
+        dest,
+    } = filled(&fx);
+    let amount_out = amount_out(&fx, &order);
+
+    // dest escrows have no public cancel, a src call can't reach them
+    assert!(fx
+        .resolver
+        .try_public_cancel_src(&fx.owner, &dest.address)
+        .is_err());
+
+    fx.set_time(FILL_TIME + DEST_RESOLVER_UNLOCK_PERIOD);
+    fx.resolver
+        .public_withdraw_dest(&fx.owner, &dest.address, &order.secret);
+    assert_eq!(fx.token_out_balance(&maker.address), amount_out as i128);
+
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    fx.resolver
+        .public_withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(fx.token_in_balance(&fx.resolver.address), AMOUNT_IN as i128);
+}
+
+#[test]
+fn cancellation_returns_funds() {
+    let fx = FusionX::setup();
+    let resolver_xlm = fx.xlm_balance(&fx.resolver.address);
+    let resolver_token_out = fx.token_out_balance(&fx.resolver.address);
+    let Filled {
+        maker,
+        order,
+        src,
+        dest,
+    } = filled(&fx);
+    let amount_out = amount_out(&fx, &order);
+    let other = fx.other_resolver();
+
+    fx.set_time(FILL_TIME + DEST_ANYONE_UNLOCK_PERIOD - 1);
+    assert_eq!(
+        fx.resolver.try_cancel_dest(&fx.owner, &dest.address),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    fx.set_time(FILL_TIME + DEST_ANYONE_UNLOCK_PERIOD);
+    fx.resolver.cancel_dest(&fx.owner, &dest.address);
+    assert_eq!(
+        fx.events::<EscrowCancelled>(&dest.address, ("escrow", "cancel")),
+        [EscrowCancelled {
+            order_id: order.input.orderId.clone(),
+            to: fx.resolver.address.clone(),
+            amount: amount_out,
+        }]
+    );
+    assert_eq!(
+        fx.token_out_balance(&fx.resolver.address),
+        resolver_token_out
+    );
+
+    fx.set_time(FILL_TIME + SRC_ANYONE_UNLOCK_PERIOD - 1);
+    assert_eq!(
+        fx.resolver.try_cancel_src(&fx.owner, &src.address),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    fx.set_time(FILL_TIME + SRC_ANYONE_UNLOCK_PERIOD);
+    assert_eq!(
+        src.try_cancel(&other),
+        Err(Ok(Error::UnauthorizedCaller.into()))
+    );
+    fx.resolver.cancel_src(&fx.owner, &src.address);
+    assert_eq!(
+        fx.events::<EscrowCancelled>(&src.address, ("escrow", "cancel")),
+        [EscrowCancelled {
+            order_id: order.input.orderId.clone(),
+            to: maker.address.clone(),
+            amount: AMOUNT_IN,
+        }]
+    );
+    assert_eq!(fx.token_in_balance(&maker.address), 2 * AMOUNT_IN as i128);
+    assert_eq!(fx.token_in_balance(&src.address), 0);
+
+    assert_eq!(fx.xlm_balance(&fx.resolver.address), resolver_xlm);
+}
+
+#[test]
+fn public_cancel_after_resolver_cancel_window() {
+    let fx = FusionX::setup();
+    let resolver_xlm = fx.xlm_balance(&fx.resolver.address);
+    let Filled {
+        maker, order, src, ..
+    } = filled(&fx);
+    let other = fx.other_resolver();
+
+    fx.set_time(FILL_TIME + SRC_RESOLVER_CANCEL - 1);
+    assert_eq!(
+        fx.resolver.try_public_cancel_src(&fx.owner, &src.address),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    fx.set_time(FILL_TIME + SRC_RESOLVER_CANCEL);
+    assert_eq!(
+        fx.resolver.try_cancel_src(&fx.owner, &src.address),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+    src.public_cancel(&other);
+    assert_eq!(
+        fx.events::<EscrowCancelled>(&src.address, ("escrow", "cancel")),
+        [EscrowCancelled {
+            order_id: order.input.orderId.clone(),
+            to: maker.address.clone(),
+            amount: AMOUNT_IN,
+        }]
+    );
+    assert_eq!(fx.token_in_balance(&maker.address), 2 * AMOUNT_IN as i128);
+    assert_eq!(fx.xlm_balance(&other), SECURITY_DEPOSIT);
+
+    // the resolver lost the src deposit to the public canceller, the dest one is still locked
+    assert_eq!(
+        fx.xlm_balance(&fx.resolver.address),
+        resolver_xlm - 2 * SECURITY_DEPOSIT
+    );
+}
+
+#[test]
+fn escrows_only_take_immutables_once_from_their_factory() {
+    let fx = FusionX::setup();
+    let Filled {
+        order, src, dest, ..
+    } = filled(&fx);
+    let thief = Address::generate(&fx.env);
+    let forged = |token: &Address| Immutables {
+        order_id: order.input.orderId.clone(),
+        hash_lock: fx.random_bytes(),
+        token: token.clone(),
+        amount: AMOUNT_IN,
+        maker: thief.clone(),
+        executive_resolver: thief.clone(),
+        relayer: fx.relayer.address.clone(),
+        native_token: fx.xlm.clone(),
+    };
+
+    assert_eq!(
+        src.try_initialize(&forged(&fx.token_in.address)),
+        Err(Ok(Error::AlreadyInitialized.into()))
+    );
+    assert_eq!(
+        dest.try_initialize(&forged(&fx.token_out)),
+        Err(Ok(Error::AlreadyInitialized.into()))
+    );
+    assert_eq!(src.get_executive_resolver(), fx.resolver.address);
+    assert_eq!(dest.get_executive_resolver(), fx.resolver.address);
+
+    // a fresh escrow doesn't take them from anyone but its factory either
+    let fresh = EscrowSrcClient::new(
+        &fx.env,
+        &fx.env
+            .register(ESCROW_SRC_WASM, (fx.factory.address.clone(),)),
+    );
+    fx.env.set_auths(&[]);
+    assert!(fresh.try_initialize(&forged(&fx.token_in.address)).is_err());
+}
+
+#[test]
+fn only_resolvers_deploy_dest_escrows() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(2 * AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+
+    // someone racing the resolver to the order's escrow address
+    let squatter = Address::generate(&fx.env);
+    fx.mint_xlm(&squatter, SECURITY_DEPOSIT);
+    fx.mint_token_out(&squatter, MAX_AMOUNT_OUT as i128);
+    assert_eq!(
+        fx.dest_factory.try_deploy_dest(
+            &order.input.orderId,
+            &order.input.hashLock,
+            &fx.token_out,
+            &MAX_AMOUNT_OUT,
+            &maker.address,
+            &squatter,
+        ),
+        Err(Ok(Error::NotResolver.into()))
+    );
+
+    fx.set_time(FILL_TIME);
+    fx.fill(&order);
+    assert_eq!(
+        fx.deploy_dest(&order).address,
+        fx.dest_factory.get_escrow_address(&order.input.orderId)
+    );
+}
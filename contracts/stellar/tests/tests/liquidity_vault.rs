@@ -0,0 +1,267 @@
+use fusionx_tests::*;
+use fusionx_types::Error;
+use liquidityvault::PAGE_SIZE;
+use resolver::{Action, DestDeployment, Swap};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{vec, Address};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+const LP_DEPOSIT: i128 = 2_000_000_000;
+const FEE_BPS: u32 = 100;
+const MAX_UTILIZATION_BPS: u32 = 8_000;
+
+fn lp(fx: &FusionX, amount: i128) -> Address {
+    let lp = Address::generate(&fx.env);
+    fx.mint_token_out(&lp, amount);
+    lp
+}
+
+#[test]
+fn lp_capital_funds_a_fill_and_earns_the_fee() {
+    let fx = FusionX::setup();
+    let vault = fx.liquidity_vault(FEE_BPS, MAX_UTILIZATION_BPS);
+    let lp = lp(&fx, LP_DEPOSIT);
+    assert_eq!(vault.deposit(&lp, &LP_DEPOSIT), LP_DEPOSIT);
+
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    let order_id = order.input.orderId.clone();
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let amount_out = fx.auction.get_amount_out(&order_id);
+
+    // no inventory of its own, the vault funds the destination escrow
+    fx.resolver.sweep(&fx.token_out, &fx.owner);
+    fx.resolver.execute_batch(&vec![
+        &fx.env,
+        Action::Fill(fx.resolver.address.clone(), order_id.clone(), amount_out),
+        Action::Borrow(order_id.clone(), amount_out as i128),
+        Action::DeployDest(DestDeployment {
+            caller: fx.resolver.address.clone(),
+            order_id: order_id.clone(),
+            hash_lock: order.input.hashLock.clone(),
+            token_out: fx.token_out.clone(),
+            amount_out,
+            maker: order.input.maker.clone(),
+        }),
+    ]);
+    assert_eq!(
+        vault.get_loan(&fx.resolver.address, &order_id),
+        amount_out as i128
+    );
+    assert_eq!(
+        vault.utilization(),
+        (amount_out as i128 * 10_000 / LP_DEPOSIT) as u32
+    );
+    assert_eq!(fx.resolver.inventory(&fx.token_out), 0);
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    let dest = fx.dest_factory.get_escrow_from_orderid(&order_id);
+    fx.resolver.withdraw_dest(&fx.owner, &dest, &order.secret);
+
+    // repay out of the source withdrawal, swapped back into the vault's asset
+    let router = fx.mock_router();
+    router.set_rate(&fx.token_in.address, &fx.token_out, &11, &10_000_000_000);
+    fx.mint_token_out(&router.address, LP_DEPOSIT);
+    let proceeds = AMOUNT_IN as i128 * 11 / 10_000_000_000;
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    let src = fx.factory.get_escrow_from_orderid(&order_id);
+    fx.resolver.execute_batch(&vec![
+        &fx.env,
+        Action::WithdrawSrc(src, order.secret.clone()),
+        Action::Swap(Swap {
+            path: vec![&fx.env, fx.token_in.address.clone(), fx.token_out.clone()],
+            amount_in: AMOUNT_IN as i128,
+            min_amount_out: proceeds,
+            deadline: fx.now(),
+        }),
+        Action::Repay(order_id.clone()),
+    ]);
+
+    // the rest of the proceeds is the operator's
+    let fee = amount_out as i128 * FEE_BPS as i128 / 10_000;
+    assert_eq!(
+        vault.try_get_loan(&fx.resolver.address, &order_id),
+        Err(Ok(Error::LoanNotFound.into()))
+    );
+    assert_eq!(
+        fx.resolver.inventory(&fx.token_out),
+        proceeds - amount_out as i128 - fee
+    );
+    assert_eq!(vault.total_assets(), LP_DEPOSIT + fee);
+    assert_eq!(vault.utilization(), 0);
+    assert_eq!(vault.withdraw(&lp, &LP_DEPOSIT), LP_DEPOSIT + fee);
+    assert_eq!(fx.token_out_balance(&vault.address), 0);
+}
+
+#[test]
+fn borrowing_is_limited_to_approved_resolvers_and_the_cap() {
+    let fx = FusionX::setup();
+    let order_id = fx.random_bytes();
+    assert_eq!(
+        fx.resolver.try_borrow(&order_id, &1),
+        Err(Ok(Error::VaultNotSet.into()))
+    );
+
+    let vault = fx.liquidity_vault(FEE_BPS, MAX_UTILIZATION_BPS);
+    vault.deposit(&lp(&fx, LP_DEPOSIT), &LP_DEPOSIT);
+    let cap = LP_DEPOSIT * MAX_UTILIZATION_BPS as i128 / 10_000;
+    assert_eq!(
+        fx.resolver.try_borrow(&order_id, &(cap + 1)),
+        Err(Ok(Error::UtilizationCapExceeded.into()))
+    );
+    fx.resolver.borrow(&order_id, &(cap - 1));
+    // the cap counts across loans
+    assert_eq!(
+        fx.resolver.try_borrow(&fx.random_bytes(), &2),
+        Err(Ok(Error::UtilizationCapExceeded.into()))
+    );
+    fx.resolver.borrow(&order_id, &1);
+    assert_eq!(vault.get_loan(&fx.resolver.address, &order_id), cap);
+
+    vault.set_borrower(&fx.resolver.address, &false);
+    assert_eq!(
+        fx.resolver.try_borrow(&fx.random_bytes(), &1),
+        Err(Ok(Error::NotBorrower.into()))
+    );
+    assert_eq!(
+        vault.try_set_max_utilization(&10_001),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    assert_eq!(
+        fx.resolver.try_repay(&fx.random_bytes()),
+        Err(Ok(Error::LoanNotFound.into()))
+    );
+}
+
+#[test]
+fn queued_withdrawals_are_paid_first_once_loans_are_repaid() {
+    let fx = FusionX::setup();
+    let vault = fx.liquidity_vault(0, MAX_UTILIZATION_BPS);
+    let first = lp(&fx, 1_000);
+    vault.deposit(&first, &1_000);
+    let order_id = fx.random_bytes();
+    fx.resolver.borrow(&order_id, &800);
+
+    assert_eq!(
+        vault.try_withdraw(&first, &1_000),
+        Err(Ok(Error::InsufficientLiquidity.into()))
+    );
+    vault.queue_withdrawal(&first, &1_000);
+    assert_eq!(vault.shares_of(&first), 0);
+    assert_eq!(vault.process_queue(&10), 0);
+
+    // new deposits don't jump the queue, neither for LPs nor for borrowers
+    let second = lp(&fx, 500);
+    vault.deposit(&second, &500);
+    assert_eq!(vault.available(), 0);
+    assert_eq!(
+        vault.try_withdraw(&second, &500),
+        Err(Ok(Error::InsufficientLiquidity.into()))
+    );
+    assert_eq!(
+        fx.resolver.try_borrow(&fx.random_bytes(), &1),
+        Err(Ok(Error::InsufficientLiquidity.into()))
+    );
+
+    assert_eq!(fx.resolver.repay(&order_id), 800);
+    assert_eq!(vault.process_queue(&10), 1);
+    assert_eq!(vault.queue_head(), vault.queue_tail());
+    assert_eq!(fx.token_out_balance(&first), 1_000);
+    assert_eq!(vault.withdraw(&second, &500), 500);
+}
+
+#[test]
+fn the_queue_is_paged() {
+    let fx = FusionX::setup();
+    let vault = fx.liquidity_vault(FEE_BPS, MAX_UTILIZATION_BPS);
+    let lp = lp(&fx, 1_000);
+    vault.deposit(&lp, &1_000);
+    for _ in 0..=PAGE_SIZE {
+        vault.queue_withdrawal(&lp, &1);
+    }
+    assert_eq!(vault.queue_tail(), PAGE_SIZE + 1);
+    assert_eq!(vault.get_queue(&0).len(), PAGE_SIZE);
+    assert_eq!(vault.get_queue(&1).len(), 1);
+
+    // a page paid in full is dropped
+    assert_eq!(vault.process_queue(&PAGE_SIZE), PAGE_SIZE);
+    assert_eq!(vault.queue_head(), PAGE_SIZE);
+    assert_eq!(vault.get_queue(&0).len(), 0);
+    vault.queue_withdrawal(&lp, &1);
+    assert_eq!(vault.get_queue(&1).len(), 2);
+    assert_eq!(vault.process_queue(&10), 2);
+    assert_eq!(vault.queue_head(), PAGE_SIZE + 2);
+    assert_eq!(fx.token_out_balance(&lp), PAGE_SIZE as i128 + 2);
+}
+
+#[test]
+fn loans_are_repaid_with_the_fee() {
+    let fx = FusionX::setup();
+    let vault = fx.liquidity_vault(FEE_BPS, MAX_UTILIZATION_BPS);
+    vault.deposit(&lp(&fx, 1_000), &1_000);
+    let order_id = fx.random_bytes();
+    fx.resolver.borrow(&order_id, &800);
+
+    // however the fill went, the LPs get the principal and the fee
+    assert_eq!(vault.owed(&fx.resolver.address, &order_id), 808);
+    vault.set_fee(&200);
+    assert_eq!(vault.owed(&fx.resolver.address, &order_id), 816);
+    assert_eq!(fx.resolver.repay(&order_id), 816);
+    assert_eq!(vault.total_assets(), 1_016);
+    assert_eq!(vault.share_value(&1_000), 1_016);
+    assert_eq!(
+        vault.try_set_fee(&10_001),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+
+    assert_eq!(
+        vault.try_deposit(&lp(&fx, 1), &0),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+}
+
+#[test]
+fn overdue_loans_are_written_off() {
+    let fx = FusionX::setup();
+    let vault = fx.liquidity_vault(FEE_BPS, MAX_UTILIZATION_BPS);
+    let lp = lp(&fx, 1_000);
+    vault.deposit(&lp, &1_000);
+    let order_id = fx.random_bytes();
+    fx.resolver.borrow(&order_id, &500);
+    let due = fx.now() + LOAN_DURATION;
+    // borrowing more for the order doesn't push the due time out
+    fx.set_time(fx.now() + 60);
+    fx.resolver.borrow(&order_id, &300);
+    assert_eq!(vault.get_loan_due(&fx.resolver.address, &order_id), due);
+
+    fx.set_time(due);
+    assert_eq!(
+        vault.try_write_off(&fx.resolver.address, &order_id),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+
+    // past due anyone can write it off, the LPs take the loss
+    fx.set_time(due + 1);
+    assert_eq!(vault.write_off(&fx.resolver.address, &order_id), 800);
+    assert_eq!(
+        vault.try_get_loan(&fx.resolver.address, &order_id),
+        Err(Ok(Error::LoanNotFound.into()))
+    );
+    assert_eq!(vault.borrowed(), 0);
+    assert_eq!(vault.total_assets(), 200);
+    assert_eq!(vault.share_value(&1_000), 200);
+    assert!(!vault.is_borrower(&fx.resolver.address));
+    assert_eq!(
+        fx.resolver.try_borrow(&fx.random_bytes(), &1),
+        Err(Ok(Error::NotBorrower.into()))
+    );
+    assert_eq!(
+        fx.resolver.try_repay(&order_id),
+        Err(Ok(Error::LoanNotFound.into()))
+    );
+    assert_eq!(vault.withdraw(&lp, &1_000), 200);
+}
@@ -0,0 +1,216 @@
+use fusionx_tests::*;
+use fusionx_types::Error;
+use mocktoken::FaucetConfig;
+use soroban_sdk::testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke};
+use soroban_sdk::{token, vec, Address, IntoVal, String, Symbol};
+
+#[test]
+fn metadata_and_admin() {
+    let fx = FusionX::setup();
+    let token = token::Client::new(&fx.env, &fx.token_in.address);
+    assert_eq!(token.decimals(), TOKEN_IN_DECIMALS);
+    assert_eq!(token.name(), String::from_str(&fx.env, "MyToken"));
+    assert_eq!(token.symbol(), String::from_str(&fx.env, "MTK"));
+
+    let admin = token::StellarAssetClient::new(&fx.env, &fx.token_in.address);
+    assert_eq!(admin.admin(), fx.owner);
+    let new_admin = Address::generate(&fx.env);
+    admin.set_admin(&new_admin);
+    assert_eq!(fx.token_in.admin(), new_admin);
+}
+
+#[test]
+fn only_the_admin_mints_and_mints_add_up() {
+    let fx = FusionX::setup();
+    let to = Address::generate(&fx.env);
+    let mint = |amount: i128| {
+        fx.token_in
+            .mock_auths(&[MockAuth {
+                address: &fx.owner,
+                invoke: &MockAuthInvoke {
+                    contract: &fx.token_in.address,
+                    fn_name: "mint",
+                    args: (&to, amount).into_val(&fx.env),
+                    sub_invokes: &[],
+                },
+            }])
+            .mint(&to, &amount);
+    };
+
+    mint(100);
+    assert_eq!(
+        fx.env.events().all(),
+        vec![
+            &fx.env,
+            (
+                fx.token_in.address.clone(),
+                (Symbol::new(&fx.env, "mint"), &fx.owner, &to).into_val(&fx.env),
+                100i128.into_val(&fx.env),
+            )
+        ]
+    );
+    mint(50);
+    assert_eq!(fx.token_in.balance(&to), 150);
+    assert_eq!(fx.token_in.total_supply(), 150);
+
+    // nobody else can mint
+    fx.env.set_auths(&[]);
+    assert!(fx.token_in.try_mint(&to, &1).is_err());
+    fx.env.mock_all_auths();
+    assert_eq!(
+        fx.token_in.try_mint(&to, &0),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+}
+
+#[test]
+fn burns_reduce_the_supply() {
+    let fx = FusionX::setup();
+    let token = token::Client::new(&fx.env, &fx.token_in.address);
+    let holder = Address::generate(&fx.env);
+    let spender = Address::generate(&fx.env);
+    fx.token_in.mint(&holder, &1_000);
+
+    token.burn(&holder, &100);
+    token.approve(&holder, &spender, &200, &(fx.env.ledger().sequence() + 10));
+    token.burn_from(&spender, &holder, &200);
+    assert_eq!(token.balance(&holder), 700);
+    assert_eq!(fx.token_in.total_supply(), 700);
+    assert_eq!(token.allowance(&holder, &spender), 0);
+
+    assert_eq!(
+        token.try_burn(&holder, &701),
+        Err(Ok(Error::InsufficientBalance.into()))
+    );
+}
+
+#[test]
+fn allowances_expire_and_are_spent() {
+    let fx = FusionX::setup();
+    let token = token::Client::new(&fx.env, &fx.token_in.address);
+    let (from, spender, to) = (
+        Address::generate(&fx.env),
+        Address::generate(&fx.env),
+        Address::generate(&fx.env),
+    );
+    fx.token_in.mint(&from, &1_000);
+    fx.env.ledger().with_mut(|l| l.sequence_number = 100);
+
+    assert_eq!(
+        token.try_approve(&from, &spender, &500, &99),
+        Err(Ok(Error::InvalidExpirationLedger.into()))
+    );
+    token.approve(&from, &spender, &500, &110);
+    token.transfer_from(&spender, &from, &to, &300);
+    assert_eq!(token.allowance(&from, &spender), 200);
+    assert_eq!(token.balance(&to), 300);
+    assert_eq!(
+        token.try_transfer_from(&spender, &from, &to, &201),
+        Err(Ok(Error::InsufficientAllowance.into()))
+    );
+
+    fx.env.ledger().with_mut(|l| l.sequence_number = 111);
+    assert_eq!(token.allowance(&from, &spender), 0);
+    assert_eq!(
+        token.try_transfer_from(&spender, &from, &to, &1),
+        Err(Ok(Error::InsufficientAllowance.into()))
+    );
+    assert_eq!(
+        token.try_transfer(&from, &to, &-1),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    // zero is a valid amount to move, as with a Stellar Asset Contract
+    token.transfer(&from, &to, &0);
+    token.transfer_from(&spender, &from, &to, &0);
+}
+
+#[test]
+fn faucet_drips_within_limits() {
+    let fx = FusionX::setup();
+    let tester = Address::generate(&fx.env);
+    assert_eq!(
+        fx.token_in.try_faucet(&tester, &1),
+        Err(Ok(Error::FaucetDisabled.into()))
+    );
+
+    let config = FaucetConfig {
+        max_amount: 1_000,
+        cooldown: 60 * 60,
+    };
+    fx.token_in.set_faucet(&config);
+    assert_eq!(fx.token_in.get_faucet(), config);
+
+    fx.token_in.faucet(&tester, &1_000);
+    assert_eq!(
+        fx.env.events().all(),
+        vec![
+            &fx.env,
+            (
+                fx.token_in.address.clone(),
+                (Symbol::new(&fx.env, "faucet"), &tester).into_val(&fx.env),
+                1_000i128.into_val(&fx.env),
+            )
+        ]
+    );
+    assert_eq!(fx.token_in.balance(&tester), 1_000);
+    assert_eq!(fx.token_in.total_supply(), 1_000);
+    assert_eq!(fx.token_in.next_drip(&tester), START_TIME + 60 * 60);
+
+    // once per cooldown per address, and never more than the cap
+    assert_eq!(
+        fx.token_in.try_faucet(&tester, &1),
+        Err(Ok(Error::FaucetCooldown.into()))
+    );
+    let other = Address::generate(&fx.env);
+    assert_eq!(
+        fx.token_in.try_faucet(&other, &1_001),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    fx.token_in.faucet(&other, &500);
+
+    fx.set_time(START_TIME + 60 * 60);
+    fx.token_in.faucet(&tester, &1_000);
+    assert_eq!(fx.token_in.balance(&tester), 2_000);
+
+    fx.token_in.disable_faucet();
+    fx.set_time(START_TIME + 2 * 60 * 60);
+    assert_eq!(
+        fx.token_in.try_faucet(&tester, &1),
+        Err(Ok(Error::FaucetDisabled.into()))
+    );
+}
+
+#[test]
+fn only_the_admin_configures_the_faucet() {
+    let fx = FusionX::setup();
+    let config = FaucetConfig {
+        max_amount: 1_000,
+        cooldown: 0,
+    };
+    fx.env.set_auths(&[]);
+    assert!(fx.token_in.try_set_faucet(&config).is_err());
+    fx.env.mock_all_auths();
+    assert_eq!(
+        fx.token_in.try_set_faucet(&FaucetConfig {
+            max_amount: 0,
+            cooldown: 0,
+        }),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+
+    // drips need the recipient's auth, not the admin's
+    fx.token_in.set_faucet(&config);
+    let tester = Address::generate(&fx.env);
+    fx.token_in
+        .mock_auths(&[MockAuth {
+            address: &tester,
+            invoke: &MockAuthInvoke {
+                contract: &fx.token_in.address,
+                fn_name: "faucet",
+                args: (&tester, 10i128).into_val(&fx.env),
+                sub_invokes: &[],
+            },
+        }])
+        .faucet(&tester, &10);
+    assert_eq!(fx.token_in.balance(&tester), 10);
+}
@@ -0,0 +1,355 @@
+use ed25519_dalek::{Signer, SigningKey};
+use fusionx_tests::*;
+use fusionx_types::Error;
+use serde_json::Value;
+use soroban_sdk::testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke};
+use soroban_sdk::{vec, Address, Bytes, BytesN, Env, IntoVal};
+use wrappedtoken::{WrappedTokens, WrappedTokensClient};
+
+// shared with permit/index.js, which checks the same vectors off chain
+const VECTORS: &str = include_str!("../../../../permit/test-vectors.json");
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+
+struct Vector<'a> {
+    env: Env,
+    contract_id: String,
+    wrapped: WrappedTokensClient<'a>,
+    token: Address,
+    owner: Address,
+    spender: Address,
+    amount: u128,
+    nonce: u64,
+    deadline: u64,
+    public_key: BytesN<32>,
+    domain_separator: BytesN<32>,
+    digest: BytesN<32>,
+    signature: BytesN<64>,
+}
+
+impl Vector<'_> {
+    /// Registers WrappedTokens at the vector's address on the vector's network.
+    fn load(json: &Value) -> Self {
+        let env = Env::default();
+        let field = |name: &str| json[name].as_str().unwrap();
+        let address = |name: &str| Address::from_str(&env, field(name));
+
+        let passphrase = Bytes::from_slice(&env, field("networkPassphrase").as_bytes());
+        let network_id = env.crypto().sha256(&passphrase).to_array();
+        env.ledger().set_network_id(network_id);
+        let contract = address("contractId");
+        env.register_at(&contract, WrappedTokens, ());
+
+        Vector {
+            contract_id: field("contractId").into(),
+            wrapped: WrappedTokensClient::new(&env, &contract),
+            token: address("token"),
+            owner: address("owner"),
+            spender: address("spender"),
+            amount: field("amount").parse().unwrap(),
+            nonce: field("nonce").parse().unwrap(),
+            deadline: field("deadline").parse().unwrap(),
+            public_key: BytesN::from_array(&env, &hex(field("publicKey"))),
+            domain_separator: BytesN::from_array(&env, &hex(field("domainSeparator"))),
+            digest: BytesN::from_array(&env, &hex(field("digest"))),
+            signature: BytesN::from_array(&env, &hex(field("signature"))),
+            env,
+        }
+    }
+
+    fn digest(&self, amount: u128, spender: &Address) -> BytesN<32> {
+        self.wrapped.permit_digest(
+            &self.token,
+            &self.owner,
+            spender,
+            &amount,
+            &self.nonce,
+            &self.deadline,
+        )
+    }
+
+    /// `Err(Some(_))` for contract errors, `Err(None)` for host errors such
+    /// as a failed signature check.
+    fn try_permit(&self, amount: u128, spender: &Address) -> Result<(), Option<Error>> {
+        let result = self.wrapped.try_permit(
+            &self.token,
+            &self.owner,
+            spender,
+            &amount,
+            &self.nonce,
+            &self.deadline,
+            &self.public_key,
+            &self.signature,
+        );
+        match result {
+            Ok(_) => Ok(()),
+            Err(Ok(error)) => Err([Error::NonceUsed, Error::PermitExpired]
+                .into_iter()
+                .find(|e| soroban_sdk::Error::from(*e) == error)),
+            Err(Err(_)) => Err(None),
+        }
+    }
+}
+
+fn vectors() -> Vec<Vector<'static>> {
+    let json: Value = serde_json::from_str(VECTORS).unwrap();
+    json.as_array().unwrap().iter().map(Vector::load).collect()
+}
+
+fn hex<const N: usize>(s: &str) -> [u8; N] {
+    let mut out = [0u8; N];
+    assert_eq!(s.len(), 2 * N);
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+    }
+    out
+}
+
+#[test]
+fn digest_matches_off_chain_encoding() {
+    for v in vectors() {
+        assert_eq!(v.wrapped.domain_separator(), v.domain_separator);
+        assert_eq!(v.digest(v.amount, &v.spender), v.digest);
+    }
+}
+
+#[test]
+fn digest_is_bound_to_network_and_contract() {
+    let v = &vectors()[0];
+    // same contract id on another network
+    let other_network = Env::default();
+    let contract = Address::from_str(&other_network, &v.contract_id);
+    other_network.register_at(&contract, WrappedTokens, ());
+    let other = WrappedTokensClient::new(&other_network, &contract);
+    assert_ne!(
+        other.domain_separator().to_array(),
+        v.domain_separator.to_array()
+    );
+
+    // another contract on the same network
+    let twin = v.env.register(WrappedTokens, ());
+    let twin = WrappedTokensClient::new(&v.env, &twin);
+    assert_ne!(twin.domain_separator(), v.domain_separator);
+}
+
+#[test]
+fn permit_accepts_vector_signatures_once() {
+    for v in vectors() {
+        v.env.ledger().with_mut(|l| l.timestamp = v.deadline);
+        assert_eq!(v.try_permit(v.amount, &v.spender), Ok(()));
+        assert_eq!(
+            v.wrapped.get_allowance(&v.token, &v.owner, &v.spender),
+            v.amount
+        );
+        assert!(v.wrapped.is_nonce_used(&v.owner, &v.nonce));
+        assert_eq!(
+            v.try_permit(v.amount, &v.spender),
+            Err(Some(Error::NonceUsed))
+        );
+    }
+}
+
+#[test]
+fn permit_rejects_tampered_fields() {
+    for v in vectors() {
+        v.env.ledger().with_mut(|l| l.timestamp = 0);
+        let other = Address::from_str(
+            &v.env,
+            "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+        );
+        assert_eq!(v.try_permit(v.amount - 1, &v.spender), Err(None));
+        assert_eq!(v.try_permit(v.amount, &other), Err(None));
+        assert_eq!(v.wrapped.get_allowance(&v.token, &v.owner, &other), 0);
+    }
+}
+
+#[test]
+fn permit_rejects_expired_deadline() {
+    let v = &vectors()[0];
+    v.env.ledger().with_mut(|l| l.timestamp = v.deadline + 1);
+    assert_eq!(
+        v.try_permit(v.amount, &v.spender),
+        Err(Some(Error::PermitExpired))
+    );
+}
+
+#[test]
+fn permit_rejects_keys_that_are_not_the_owners() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let attacker = SigningKey::generate(&mut rand::thread_rng());
+    let deadline = fx.now() + PERMIT_VALIDITY;
+    let digest = fx.wrapped.permit_digest(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &0,
+        &deadline,
+    );
+    let permit = |key: &SigningKey, signer: &SigningKey| {
+        let signature = signer.sign(&digest.to_array());
+        fx.wrapped.try_permit(
+            &fx.token_in.address,
+            &maker.address,
+            &fx.owner,
+            &AMOUNT,
+            &0,
+            &deadline,
+            &BytesN::from_array(&fx.env, &key.verifying_key().to_bytes()),
+            &BytesN::from_array(&fx.env, &signature.to_bytes()),
+        )
+    };
+
+    // a valid signature by a key that is not the maker's
+    assert_eq!(
+        permit(&attacker, &attacker),
+        Err(Ok(Error::InvalidPublicKey.into()))
+    );
+    // the maker's key with someone else's signature
+    assert!(permit(&maker.key, &attacker).is_err());
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.owner),
+        0
+    );
+
+    assert_eq!(permit(&maker.key, &maker.key), Ok(Ok(())));
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.owner),
+        AMOUNT
+    );
+}
+
+#[test]
+fn contract_owners_authorize_the_digest() {
+    let env = Env::default();
+    let wrapped = WrappedTokensClient::new(&env, &env.register(WrappedTokens, ()));
+    let (token, owner, spender) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let digest = wrapped.permit_digest(&token, &owner, &spender, &AMOUNT, &0, &u64::MAX);
+    let (public_key, signature) = (
+        BytesN::from_array(&env, &[0; 32]),
+        BytesN::from_array(&env, &[0; 64]),
+    );
+    let permit = || {
+        wrapped.try_permit(
+            &token,
+            &owner,
+            &spender,
+            &AMOUNT,
+            &0,
+            &u64::MAX,
+            &public_key,
+            &signature,
+        )
+    };
+
+    // no auth from the owner contract
+    assert!(permit().is_err());
+
+    // the owner's __check_auth sees the digest as the only argument
+    env.mock_auths(&[MockAuth {
+        address: &owner,
+        invoke: &MockAuthInvoke {
+            contract: &wrapped.address,
+            fn_name: "permit",
+            args: vec![&env, digest.into_val(&env)],
+            sub_invokes: &[],
+        },
+    }]);
+    assert_eq!(permit(), Ok(Ok(())));
+    assert_eq!(wrapped.get_allowance(&token, &owner, &spender), AMOUNT);
+}
+
+/// Signs a permit of `AMOUNT` from the maker to the FusionX owner.
+fn sign_permit(fx: &FusionX, maker: &Maker, nonce: u64) -> (u64, BytesN<64>) {
+    let deadline = fx.now() + PERMIT_VALIDITY;
+    let digest = fx.wrapped.permit_digest(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &deadline,
+    );
+    let signature = maker.key.sign(&digest.to_array());
+    (deadline, BytesN::from_array(&fx.env, &signature.to_bytes()))
+}
+
+fn try_permit(fx: &FusionX, maker: &Maker, nonce: u64) -> Result<(), Error> {
+    let (deadline, signature) = sign_permit(fx, maker, nonce);
+    match fx.wrapped.try_permit(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &deadline,
+        &BytesN::from_array(&fx.env, &maker.key.verifying_key().to_bytes()),
+        &signature,
+    ) {
+        Ok(_) => Ok(()),
+        Err(Ok(error)) => Err(error.try_into().unwrap()),
+        Err(Err(_)) => panic!("unexpected host error"),
+    }
+}
+
+#[test]
+fn nonces_are_single_use_in_any_order() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+
+    for nonce in [2, 0, 1, 200, u64::MAX] {
+        assert_eq!(try_permit(&fx, &maker, nonce), Ok(()));
+        assert_eq!(try_permit(&fx, &maker, nonce), Err(Error::NonceUsed));
+    }
+    assert!(!fx.wrapped.is_nonce_used(&maker.address, &3));
+
+    // sequential nonces share one bitmap word
+    assert_eq!(fx.wrapped.nonce_bitmap(&maker.address, &0), 0b111);
+    assert_eq!(
+        fx.wrapped.nonce_bitmap(&maker.address, &1),
+        1 << (200 - 128)
+    );
+    assert_eq!(
+        fx.wrapped.nonce_bitmap(&maker.address, &(u64::MAX >> 7)),
+        1 << 127
+    );
+
+    // nonces are per owner
+    let other = fx.maker(AMOUNT, AMOUNT);
+    assert_eq!(try_permit(&fx, &other, 0), Ok(()));
+}
+
+#[test]
+fn makers_can_invalidate_unsubmitted_permits() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+
+    fx.wrapped.invalidate_nonce(&maker.address, &5);
+    assert_eq!(
+        fx.env.auths()[0].0,
+        maker.address,
+        "invalidation needs the owner's auth"
+    );
+    assert_eq!(try_permit(&fx, &maker, 5), Err(Error::NonceUsed));
+
+    // revoke 128..=130 and 255 at once, leaving 131
+    let mask = 0b111 | 1 << 127;
+    fx.wrapped
+        .invalidate_unordered_nonces(&maker.address, &1, &mask);
+    assert_eq!(fx.wrapped.nonce_bitmap(&maker.address, &1), mask);
+    for nonce in [128, 129, 130, 255] {
+        assert_eq!(try_permit(&fx, &maker, nonce), Err(Error::NonceUsed));
+    }
+    assert_eq!(try_permit(&fx, &maker, 131), Ok(()));
+    assert_eq!(fx.wrapped.nonce_bitmap(&maker.address, &1), mask | 1 << 3);
+
+    // invalidating is idempotent
+    fx.wrapped.invalidate_nonce(&maker.address, &5);
+    assert_eq!(fx.wrapped.nonce_bitmap(&maker.address, &0), 1 << 5);
+}
@@ -0,0 +1,375 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use fusionx_tests::*;
+use fusionx_types::{Error, PermitSigner, WebAuthnSignature};
+use k256::ecdsa::SigningKey as EvmKey;
+use p256::ecdsa::{signature::Signer, Signature as P256Signature, SigningKey as PasskeyKey};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{Address, Bytes, BytesN};
+
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+fn evm_address(key: &EvmKey) -> [u8; 20] {
+    let point = key.verifying_key().to_encoded_point(false);
+    keccak(&point.as_bytes()[1..])[12..].try_into().unwrap()
+}
+
+fn uint256(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Registers `key` as the maker's EVM signer.
+fn evm_signer(fx: &FusionX, maker: &Maker) -> EvmKey {
+    let key = EvmKey::random(&mut rand::thread_rng());
+    let signer = PermitSigner::Secp256k1(BytesN::from_array(&fx.env, &evm_address(&key)));
+    fx.wrapped.set_permit_signer(&maker.address, &signer);
+    key
+}
+
+/// Registers `key` as the maker's passkey.
+fn passkey_signer(fx: &FusionX, maker: &Maker) -> PasskeyKey {
+    let key = PasskeyKey::random(&mut rand::thread_rng());
+    let point = key.verifying_key().to_encoded_point(false);
+    let public_key = BytesN::from_array(&fx.env, &point.as_bytes().try_into().unwrap());
+    fx.wrapped
+        .set_permit_signer(&maker.address, &PermitSigner::Secp256r1(public_key));
+    key
+}
+
+/// eth_signTypedData_v4 of a permit of `AMOUNT` to the FusionX owner.
+fn sign_evm_permit(fx: &FusionX, maker: &Maker, key: &EvmKey, nonce: u64) -> (BytesN<64>, u32) {
+    let digest = fx.wrapped.evm_permit_digest(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &(fx.now() + PERMIT_VALIDITY),
+    );
+    let (signature, recovery_id) = key.sign_prehash_recoverable(&digest.to_array()).unwrap();
+    (
+        BytesN::from_array(&fx.env, &signature.to_bytes().into()),
+        recovery_id.to_byte() as u32,
+    )
+}
+
+fn try_evm_permit(fx: &FusionX, maker: &Maker, key: &EvmKey, nonce: u64) -> Result<(), Error> {
+    let (signature, recovery_id) = sign_evm_permit(fx, maker, key, nonce);
+    match fx.wrapped.try_permit_secp256k1(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &(fx.now() + PERMIT_VALIDITY),
+        &signature,
+        &recovery_id,
+    ) {
+        Ok(_) => Ok(()),
+        Err(Ok(error)) => Err(error.try_into().unwrap()),
+        Err(Err(_)) => panic!("unexpected host error"),
+    }
+}
+
+/// WebAuthn assertion of a permit of `AMOUNT` to the FusionX owner, with
+/// `client_data` built from the base64url challenge.
+fn sign_passkey_permit(
+    fx: &FusionX,
+    maker: &Maker,
+    key: &PasskeyKey,
+    nonce: u64,
+    client_data: impl Fn(&str) -> String,
+) -> WebAuthnSignature {
+    sign_passkey_assertion(fx, maker, key, nonce, client_data, 0x05)
+}
+
+/// Same with the authenticator data `flags`, 0x01 user present and 0x04
+/// user verified.
+fn sign_passkey_assertion(
+    fx: &FusionX,
+    maker: &Maker,
+    key: &PasskeyKey,
+    nonce: u64,
+    client_data: impl Fn(&str) -> String,
+    flags: u8,
+) -> WebAuthnSignature {
+    let digest = fx.wrapped.permit_digest(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &(fx.now() + PERMIT_VALIDITY),
+    );
+    let client_data_json = client_data(&URL_SAFE_NO_PAD.encode(digest.to_array()));
+    // rp id hash, flags, sign count
+    let mut authenticator_data = [0u8; 37];
+    authenticator_data[32] = flags;
+
+    let mut signed = authenticator_data.to_vec();
+    signed.extend_from_slice(&Sha256::digest(client_data_json.as_bytes()));
+    let signature: P256Signature = key.sign(&signed);
+    let signature = signature.normalize_s().unwrap_or(signature);
+    WebAuthnSignature {
+        authenticator_data: Bytes::from_slice(&fx.env, &authenticator_data),
+        client_data_json: Bytes::from_slice(&fx.env, client_data_json.as_bytes()),
+        signature: BytesN::from_array(&fx.env, &signature.to_bytes().into()),
+    }
+}
+
+fn try_passkey_permit(
+    fx: &FusionX,
+    maker: &Maker,
+    nonce: u64,
+    signature: &WebAuthnSignature,
+) -> Result<(), Option<Error>> {
+    match fx.wrapped.try_permit_secp256r1(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &(fx.now() + PERMIT_VALIDITY),
+        signature,
+    ) {
+        Ok(_) => Ok(()),
+        // None for host errors such as a failed signature check
+        Err(Ok(error)) => Err(error.try_into().ok()),
+        Err(Err(_)) => Err(None),
+    }
+}
+
+fn browser_client_data(challenge: &str) -> String {
+    format!(
+        r#"{{"type":"webauthn.get","challenge":"{challenge}","origin":"https://app.fusionx.example","crossOrigin":false}}"#
+    )
+}
+
+#[test]
+fn evm_digest_is_eip712_typed_data() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let (nonce, deadline) = (7, fx.now() + PERMIT_VALIDITY);
+
+    let strkey = |address: &Address| keccak(address.to_string().to_string().as_bytes());
+    let domain = [
+        keccak(b"EIP712Domain(string name,string version,bytes32 salt)"),
+        keccak(b"FusionX WrappedTokens"),
+        keccak(b"1"),
+        fx.wrapped.domain_separator().to_array(),
+    ]
+    .concat();
+    let permit = [
+        keccak(b"Permit(string token,string owner,string spender,uint256 amount,uint256 nonce,uint256 deadline)"),
+        strkey(&fx.token_in.address),
+        strkey(&maker.address),
+        strkey(&fx.owner),
+        uint256(AMOUNT),
+        uint256(nonce.into()),
+        uint256(deadline.into()),
+    ]
+    .concat();
+    let expected = keccak(&[&[0x19, 0x01], &keccak(&domain)[..], &keccak(&permit)[..]].concat());
+
+    let digest = fx.wrapped.evm_permit_digest(
+        &fx.token_in.address,
+        &maker.address,
+        &fx.owner,
+        &AMOUNT,
+        &nonce,
+        &deadline,
+    );
+    assert_eq!(digest.to_array(), expected);
+}
+
+#[test]
+fn evm_wallets_sign_permits_for_their_owner() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let key = EvmKey::random(&mut rand::thread_rng());
+    assert_eq!(
+        try_evm_permit(&fx, &maker, &key, 0),
+        Err(Error::PermitSignerNotSet)
+    );
+
+    let key = evm_signer(&fx, &maker);
+    let other = EvmKey::random(&mut rand::thread_rng());
+    assert_eq!(
+        try_evm_permit(&fx, &maker, &other, 0),
+        Err(Error::InvalidPublicKey)
+    );
+    assert_eq!(try_evm_permit(&fx, &maker, &key, 0), Ok(()));
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.owner),
+        AMOUNT
+    );
+
+    // nonces are shared with ed25519 permits
+    assert_eq!(try_evm_permit(&fx, &maker, &key, 0), Err(Error::NonceUsed));
+    assert!(fx.wrapped.is_nonce_used(&maker.address, &0));
+
+    // a passkey signature is not accepted for an EVM signer
+    let passkey = PasskeyKey::random(&mut rand::thread_rng());
+    let signature = sign_passkey_permit(&fx, &maker, &passkey, 1, browser_client_data);
+    assert_eq!(
+        try_passkey_permit(&fx, &maker, 1, &signature),
+        Err(Some(Error::InvalidPublicKey))
+    );
+}
+
+#[test]
+fn passkeys_sign_permits_through_webauthn() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let key = passkey_signer(&fx, &maker);
+
+    // registration assertions and other challenges are rejected
+    let create = |challenge: &str| browser_client_data(challenge).replace(".get", ".create");
+    let signature = sign_passkey_permit(&fx, &maker, &key, 0, create);
+    assert_eq!(
+        try_passkey_permit(&fx, &maker, 0, &signature),
+        Err(Some(Error::InvalidClientData))
+    );
+    let other_challenge = |_: &str| browser_client_data(&URL_SAFE_NO_PAD.encode([0u8; 32]));
+    let signature = sign_passkey_permit(&fx, &maker, &key, 0, other_challenge);
+    assert_eq!(
+        try_passkey_permit(&fx, &maker, 0, &signature),
+        Err(Some(Error::InvalidClientData))
+    );
+
+    // the fields are read from the json, not searched for in it
+    let in_origin = |challenge: &str| {
+        format!(
+            r#"{{"type":"webauthn.create","origin":"https://x.example/?\"type\":\"webauthn.get\",\"challenge\":\"{challenge}\"","challenge":"{}"}}"#,
+            URL_SAFE_NO_PAD.encode([0u8; 32])
+        )
+    };
+    let nested = |challenge: &str| {
+        format!(
+            r#"{{"type":"webauthn.create","extra":{{"type":"webauthn.get","challenge":"{challenge}"}}}}"#
+        )
+    };
+    let repeated = |challenge: &str| {
+        format!(r#"{{"type":"webauthn.get","challenge":"{challenge}","challenge":"x"}}"#)
+    };
+    let spaced =
+        |challenge: &str| format!(r#"{{ "type" : "webauthn.get", "challenge" : "{challenge}" }}"#);
+    for client_data in [in_origin, nested, repeated] {
+        let signature = sign_passkey_permit(&fx, &maker, &key, 0, client_data);
+        assert_eq!(
+            try_passkey_permit(&fx, &maker, 0, &signature),
+            Err(Some(Error::InvalidClientData))
+        );
+    }
+
+    // the user has to be both present and verified
+    for flags in [0x00, 0x01, 0x04] {
+        let signature = sign_passkey_assertion(&fx, &maker, &key, 0, browser_client_data, flags);
+        assert_eq!(
+            try_passkey_permit(&fx, &maker, 0, &signature),
+            Err(Some(Error::InvalidAuthenticatorData))
+        );
+    }
+
+    // another passkey
+    let other = PasskeyKey::random(&mut rand::thread_rng());
+    let signature = sign_passkey_permit(&fx, &maker, &other, 0, browser_client_data);
+    assert_eq!(try_passkey_permit(&fx, &maker, 0, &signature), Err(None));
+
+    let signature = sign_passkey_permit(&fx, &maker, &key, 0, browser_client_data);
+    assert_eq!(try_passkey_permit(&fx, &maker, 0, &signature), Ok(()));
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.owner),
+        AMOUNT
+    );
+    assert_eq!(
+        try_passkey_permit(&fx, &maker, 0, &signature),
+        Err(Some(Error::NonceUsed))
+    );
+    let signature = sign_passkey_permit(&fx, &maker, &key, 1, spaced);
+    assert_eq!(try_passkey_permit(&fx, &maker, 1, &signature), Ok(()));
+}
+
+#[test]
+fn permit_signers_are_set_by_the_owner() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let key = evm_signer(&fx, &maker);
+    assert_eq!(
+        fx.env.auths()[0].0,
+        maker.address,
+        "setting a signer needs the owner's auth"
+    );
+    assert_eq!(
+        fx.wrapped.get_permit_signer(&maker.address),
+        PermitSigner::Secp256k1(BytesN::from_array(&fx.env, &evm_address(&key)))
+    );
+
+    // a new signer replaces the old one
+    let passkey = passkey_signer(&fx, &maker);
+    assert_eq!(
+        try_evm_permit(&fx, &maker, &key, 0),
+        Err(Error::InvalidPublicKey)
+    );
+    let signature = sign_passkey_permit(&fx, &maker, &passkey, 0, browser_client_data);
+    assert_eq!(try_passkey_permit(&fx, &maker, 0, &signature), Ok(()));
+
+    fx.wrapped.remove_permit_signer(&maker.address);
+    assert_eq!(
+        fx.wrapped.try_get_permit_signer(&maker.address),
+        Err(Ok(Error::PermitSignerNotSet.into()))
+    );
+
+    fx.env.set_auths(&[]);
+    let signer = PermitSigner::Secp256k1(BytesN::from_array(&fx.env, &[1; 20]));
+    assert!(fx
+        .wrapped
+        .try_set_permit_signer(&maker.address, &signer)
+        .is_err());
+    let stranger = Address::generate(&fx.env);
+    assert!(fx.wrapped.try_remove_permit_signer(&stranger).is_err());
+}
+
+#[test]
+fn evm_makers_place_orders_through_the_relayer() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let key = evm_signer(&fx, &maker);
+    let order = fx.order(&maker, AMOUNT, 1, 1);
+
+    let (nonce, deadline) = (42, fx.now() + PERMIT_VALIDITY);
+    let digest = fx.wrapped.evm_permit_digest(
+        &order.input.tokenIn,
+        &maker.address,
+        &fx.relayer.address,
+        &order.input.amountIn,
+        &nonce,
+        &deadline,
+    );
+    let (signature, recovery_id) = key.sign_prehash_recoverable(&digest.to_array()).unwrap();
+    fx.relayer.place_order_secp256k1(
+        &order.input,
+        &nonce,
+        &deadline,
+        &BytesN::from_array(&fx.env, &signature.to_bytes().into()),
+        &(recovery_id.to_byte() as u32),
+    );
+
+    assert_eq!(
+        fx.auction.get_order(&order.input.orderId).maker,
+        maker.address
+    );
+    fx.set_time(START_TIME + AUCTION_START_BUFFER);
+    let src = fx.fill(&order);
+    assert_eq!(fx.token_in_balance(&src.address), AMOUNT as i128);
+}
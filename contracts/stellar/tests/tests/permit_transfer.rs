@@ -0,0 +1,234 @@
+use ed25519_dalek::Signer;
+use fusionx_tests::*;
+use fusionx_types::{Error, TokenTransfer};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{token, vec, Address, BytesN, Vec};
+
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+
+/// Signs `transfers` from the maker with the FusionX owner as spender.
+fn sign_transfers(
+    fx: &FusionX,
+    maker: &Maker,
+    transfers: &Vec<TokenTransfer>,
+    nonce: u64,
+) -> (u64, BytesN<64>) {
+    let deadline = fx.now() + PERMIT_VALIDITY;
+    let digest =
+        fx.wrapped
+            .permit_transfer_digest(&maker.address, &fx.owner, transfers, &nonce, &deadline);
+    let signature = maker.key.sign(&digest.to_array());
+    (deadline, BytesN::from_array(&fx.env, &signature.to_bytes()))
+}
+
+fn public_key(fx: &FusionX, maker: &Maker) -> BytesN<32> {
+    BytesN::from_array(&fx.env, &maker.key.verifying_key().to_bytes())
+}
+
+#[test]
+fn transfers_move_the_signed_amount_once() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let to = Address::generate(&fx.env);
+    let transfer = TokenTransfer {
+        token: fx.token_in.address.clone(),
+        to: to.clone(),
+        amount: AMOUNT / 4,
+    };
+    let (deadline, signature) = sign_transfers(&fx, &maker, &vec![&fx.env, transfer], 0);
+    let transfer = |amount: u128, to: &Address| {
+        fx.wrapped.try_permit_transfer_from(
+            &fx.token_in.address,
+            &maker.address,
+            &fx.owner,
+            to,
+            &amount,
+            &0,
+            &deadline,
+            &public_key(&fx, &maker),
+            &signature,
+        )
+    };
+
+    // another amount or recipient fails the signature check
+    assert!(transfer(AMOUNT / 2, &to).is_err());
+    assert!(transfer(AMOUNT / 4, &fx.owner).is_err());
+
+    assert_eq!(transfer(AMOUNT / 4, &to), Ok(Ok(())));
+    assert_eq!(fx.token_in_balance(&to), (AMOUNT / 4) as i128);
+    assert_eq!(
+        fx.wrapped.get_balance(&fx.token_in.address, &maker.address),
+        AMOUNT / 4 * 3
+    );
+    // nothing is left to spend
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.owner),
+        0
+    );
+    assert_eq!(transfer(AMOUNT / 4, &to), Err(Ok(Error::NonceUsed.into())));
+}
+
+#[test]
+fn transfers_need_the_spender_and_a_live_deadline() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let to = Address::generate(&fx.env);
+    let transfers = vec![
+        &fx.env,
+        TokenTransfer {
+            token: fx.token_in.address.clone(),
+            to,
+            amount: AMOUNT,
+        },
+    ];
+    let (deadline, signature) = sign_transfers(&fx, &maker, &transfers, 0);
+    let transfer = || {
+        fx.wrapped.try_permit_batch_transfer_from(
+            &maker.address,
+            &fx.owner,
+            &transfers,
+            &0,
+            &deadline,
+            &public_key(&fx, &maker),
+            &signature,
+        )
+    };
+
+    fx.env.set_auths(&[]);
+    assert!(transfer().is_err(), "the spender has to authorize");
+    fx.env.mock_all_auths();
+
+    fx.set_time(deadline + 1);
+    assert_eq!(transfer(), Err(Ok(Error::PermitExpired.into())));
+    fx.set_time(deadline);
+    assert_eq!(transfer(), Ok(Ok(())));
+}
+
+#[test]
+fn batch_transfers_cover_several_tokens_and_recipients() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let xlm = token::Client::new(&fx.env, &fx.xlm);
+    fx.trust(&maker.address, &fx.xlm);
+    fx.mint_xlm(&maker.address, 1_000);
+    let expiration = fx.env.ledger().sequence() + 100;
+    xlm.approve(&maker.address, &fx.wrapped.address, &1_000, &expiration);
+    fx.wrapped.deposit(&fx.xlm, &1_000, &maker.address);
+
+    let escrows = [Address::generate(&fx.env), Address::generate(&fx.env)];
+    let transfers = vec![
+        &fx.env,
+        TokenTransfer {
+            token: fx.token_in.address.clone(),
+            to: escrows[0].clone(),
+            amount: AMOUNT / 2,
+        },
+        TokenTransfer {
+            token: fx.token_in.address.clone(),
+            to: escrows[1].clone(),
+            amount: AMOUNT / 2,
+        },
+        TokenTransfer {
+            token: fx.xlm.clone(),
+            to: escrows[1].clone(),
+            amount: 1_000,
+        },
+    ];
+    let (deadline, signature) = sign_transfers(&fx, &maker, &transfers, 7);
+    fx.wrapped.permit_batch_transfer_from(
+        &maker.address,
+        &fx.owner,
+        &transfers,
+        &7,
+        &deadline,
+        &public_key(&fx, &maker),
+        &signature,
+    );
+
+    for escrow in &escrows {
+        assert_eq!(fx.token_in_balance(escrow), (AMOUNT / 2) as i128);
+    }
+    assert_eq!(fx.xlm_balance(&escrows[1]), 1_000);
+    assert_eq!(
+        fx.wrapped.get_balance(&fx.token_in.address, &maker.address),
+        0
+    );
+    assert_eq!(fx.wrapped.get_balance(&fx.xlm, &maker.address), 0);
+    assert!(fx.wrapped.is_nonce_used(&maker.address, &7));
+}
+
+#[test]
+fn signed_transfer_orders_fill_straight_into_the_escrow() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let order = fx.order(&maker, AMOUNT, 1, 1);
+    let nonce = fx.place_order_with_transfer(&maker, &order);
+    assert_eq!(
+        fx.wrapped
+            .get_allowance(&fx.token_in.address, &maker.address, &fx.relayer.address),
+        0
+    );
+
+    fx.set_time(START_TIME + AUCTION_START_BUFFER);
+    let src = fx.fill(&order);
+    assert_eq!(
+        src.address,
+        fx.factory.get_escrow_address(&order.input.orderId)
+    );
+    assert_eq!(fx.token_in_balance(&src.address), AMOUNT as i128);
+    assert_eq!(fx.token_in_balance(&fx.relayer.address), 0);
+    assert_eq!(
+        fx.wrapped.get_balance(&fx.token_in.address, &maker.address),
+        0
+    );
+    assert!(fx.wrapped.is_nonce_used(&maker.address, &nonce));
+}
+
+#[test]
+fn signed_transfer_orders_are_checked_when_placed() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let order = fx.order(&maker, AMOUNT, 1, 1);
+    let escrow = fx.factory.get_escrow_address(&order.input.orderId);
+    let live = fx.now() + PERMIT_VALIDITY;
+    let place = |to: &Address, amount: u128, deadline: u64| {
+        let transfers = vec![
+            &fx.env,
+            TokenTransfer {
+                token: fx.token_in.address.clone(),
+                to: to.clone(),
+                amount,
+            },
+        ];
+        let digest = fx.wrapped.permit_transfer_digest(
+            &maker.address,
+            &fx.relayer.address,
+            &transfers,
+            &0,
+            &deadline,
+        );
+        let signature = maker.key.sign(&digest.to_array());
+        fx.relayer.try_place_order_with_transfer(
+            &order.input,
+            &0,
+            &deadline,
+            &public_key(&fx, &maker),
+            &BytesN::from_array(&fx.env, &signature.to_bytes()),
+        )
+    };
+
+    // signed for another recipient or amount than the order's escrow pulls
+    assert!(place(&Address::generate(&fx.env), AMOUNT, live).is_err());
+    assert!(place(&escrow, AMOUNT - 1, live).is_err());
+    assert_eq!(
+        place(&escrow, AMOUNT, fx.now() - 1),
+        Err(Ok(Error::PermitExpired.into()))
+    );
+    fx.wrapped
+        .invalidate_unordered_nonces(&maker.address, &0, &1);
+    assert_eq!(
+        place(&escrow, AMOUNT, live),
+        Err(Ok(Error::NonceUsed.into()))
+    );
+}
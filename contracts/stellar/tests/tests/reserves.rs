@@ -0,0 +1,87 @@
+use fusionx_tests::*;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{token, Address};
+use wrappedtoken::Reserves;
+
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+
+#[test]
+fn supply_follows_deposits_withdrawals_and_burns() {
+    let fx = FusionX::setup();
+    let token = fx.token_in.address.clone();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let other = fx.maker(AMOUNT, AMOUNT / 2);
+    assert_eq!(fx.wrapped.total_supply(&token), AMOUNT / 2 * 3);
+
+    // moving balances around doesn't change the supply
+    fx.wrapped
+        .transfer(&token, &(AMOUNT / 4), &maker.address, &other.address);
+    assert_eq!(fx.wrapped.total_supply(&token), AMOUNT / 2 * 3);
+
+    fx.wrapped.withdraw(&token, &AMOUNT, &maker.address);
+    assert_eq!(fx.wrapped.total_supply(&token), AMOUNT / 2);
+    assert_eq!(
+        fx.wrapped.reserves(&token),
+        Reserves {
+            held: AMOUNT / 2,
+            supply: AMOUNT / 2,
+            surplus: 0,
+        }
+    );
+
+    // burnt wrapped balances leave their underlying behind as surplus
+    let asset = token::Client::new(&fx.env, &fx.wrapped.deploy_wrapped_asset(&token));
+    asset.burn(&maker.address, &((AMOUNT / 4) as i128));
+    assert_eq!(
+        fx.wrapped.reserves(&token),
+        Reserves {
+            held: AMOUNT / 2,
+            supply: AMOUNT / 4,
+            surplus: (AMOUNT / 4) as i128,
+        }
+    );
+}
+
+#[test]
+fn reserves_report_surplus_and_deficit() {
+    let fx = FusionX::setup();
+    let token = fx.token_in.address.clone();
+    fx.maker(AMOUNT, AMOUNT);
+
+    // underlying sent without a deposit isn't owed to anyone
+    let donor = fx.maker(AMOUNT, 1);
+    fx.token_in
+        .transfer(&donor.address, &fx.wrapped.address, &1_000);
+    assert_eq!(fx.wrapped.reserves(&token).surplus, 1_000);
+
+    // underlying leaving without a withdrawal, e.g. a clawback
+    let thief = Address::generate(&fx.env);
+    fx.token_in.transfer(&fx.wrapped.address, &thief, &3_000);
+    assert_eq!(
+        fx.wrapped.reserves(&token),
+        Reserves {
+            held: AMOUNT + 1 - 2_000,
+            supply: AMOUNT + 1,
+            surplus: -2_000,
+        }
+    );
+}
+
+#[test]
+fn signed_transfers_reduce_the_supply() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let order = fx.order(&maker, AMOUNT, 1, 1);
+    fx.place_order_with_transfer(&maker, &order);
+
+    fx.set_time(START_TIME + AUCTION_START_BUFFER);
+    fx.fill(&order);
+    assert_eq!(
+        fx.wrapped.reserves(&fx.token_in.address),
+        Reserves {
+            held: 0,
+            supply: 0,
+            surplus: 0,
+        }
+    );
+}
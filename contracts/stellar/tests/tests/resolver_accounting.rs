@@ -0,0 +1,331 @@
+use fusionx_tests::*;
+use fusionx_types::interfaces::{EscrowDestClient, EscrowSrcClient};
+use fusionx_types::Error;
+use resolver::accounting::{self, LegStatus, OrderRecord, Pair, PnlTotals, PAGE_SIZE, PERIOD};
+use soroban_sdk::testutils::{MockAuth, MockAuthInvoke};
+use soroban_sdk::{map, vec, IntoVal, Val};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+
+fn filled<'a>(fx: &FusionX<'a>) -> (TestOrder, EscrowSrcClient<'a>, EscrowDestClient<'a>) {
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let src = fx.fill(&order);
+    let dest = fx.deploy_dest(&order);
+    (order, src, dest)
+}
+
+/// Mocks only the owner's signature on the next call to the resolver, every
+/// auth below it has to come from the contract itself.
+fn as_owner(fx: &FusionX, fn_name: &str, args: soroban_sdk::Vec<Val>) {
+    fx.env.mock_auths(&[MockAuth {
+        address: &fx.owner,
+        invoke: &MockAuthInvoke {
+            contract: &fx.resolver.address,
+            fn_name,
+            args,
+            sub_invokes: &[],
+        },
+    }]);
+}
+
+fn pair(fx: &FusionX) -> Pair {
+    Pair {
+        token_in: Some(fx.token_in.address.clone()),
+        token_out: Some(fx.token_out.clone()),
+    }
+}
+
+#[test]
+fn settled_swaps_are_booked_per_pair_and_period() {
+    let fx = FusionX::setup();
+    let (order, src, dest) = filled(&fx);
+    let amount_out = fx.auction.get_order(&order.input.orderId).amountOut as i128;
+    let mut record = OrderRecord {
+        token_in: Some(fx.token_in.address.clone()),
+        token_out: Some(fx.token_out.clone()),
+        amount_in: 0,
+        amount_out,
+        deposits_posted: 2 * SECURITY_DEPOSIT,
+        deposits_returned: 0,
+        src: LegStatus::Locked,
+        dest: LegStatus::Locked,
+        closed_at: None,
+    };
+    assert_eq!(fx.resolver.get_order_record(&order.input.orderId), record);
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_dest(&fx.owner, &dest.address, &order.secret);
+    // still open while the src side is locked
+    assert_eq!(
+        fx.resolver.get_order_record(&order.input.orderId).closed_at,
+        None
+    );
+    assert_eq!(
+        fx.resolver
+            .pair_pnl(&pair(&fx).token_in, &pair(&fx).token_out),
+        PnlTotals::default()
+    );
+
+    let closed_at = FILL_TIME + SRC_FINALITY_LOCK;
+    fx.set_time(closed_at);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    record.amount_in = AMOUNT_IN as i128;
+    record.deposits_returned = 2 * SECURITY_DEPOSIT;
+    record.src = LegStatus::Withdrawn;
+    record.dest = LegStatus::Withdrawn;
+    record.closed_at = Some(closed_at);
+    assert_eq!(fx.resolver.get_order_record(&order.input.orderId), record);
+
+    let totals = PnlTotals {
+        orders: 1,
+        amount_in: AMOUNT_IN as i128,
+        amount_out,
+        deposits_posted: 2 * SECURITY_DEPOSIT,
+        deposits_returned: 2 * SECURITY_DEPOSIT,
+    };
+    assert_eq!(
+        fx.resolver
+            .pair_pnl(&pair(&fx).token_in, &pair(&fx).token_out),
+        totals
+    );
+    let period = closed_at / PERIOD;
+    assert_eq!(
+        fx.resolver.period_pnl(&period),
+        map![&fx.env, (pair(&fx), totals)]
+    );
+    assert_eq!(fx.resolver.period_order_count(&period), 1);
+    assert_eq!(
+        fx.resolver.period_orders(&period, &0),
+        vec![&fx.env, order.input.orderId.clone()]
+    );
+    assert_eq!(fx.resolver.period_orders(&(period + 1), &0), vec![&fx.env]);
+}
+
+#[test]
+fn cancelled_swaps_book_the_refunds() {
+    let fx = FusionX::setup();
+    let (order, src, dest) = filled(&fx);
+
+    fx.set_time(FILL_TIME + DEST_ANYONE_UNLOCK_PERIOD);
+    fx.resolver.cancel_dest(&fx.owner, &dest.address);
+    fx.set_time(FILL_TIME + SRC_ANYONE_UNLOCK_PERIOD);
+    fx.resolver.cancel_src(&fx.owner, &src.address);
+
+    let record = fx.resolver.get_order_record(&order.input.orderId);
+    assert_eq!(
+        (record.src, record.dest),
+        (LegStatus::Cancelled, LegStatus::Cancelled)
+    );
+    assert_eq!((record.amount_in, record.amount_out), (0, 0));
+    assert_eq!(
+        fx.resolver
+            .pair_pnl(&pair(&fx).token_in, &pair(&fx).token_out),
+        PnlTotals {
+            orders: 1,
+            amount_in: 0,
+            amount_out: 0,
+            deposits_posted: 2 * SECURITY_DEPOSIT,
+            deposits_returned: 2 * SECURITY_DEPOSIT,
+        }
+    );
+}
+
+#[test]
+fn public_settles_by_others_are_synced_from_the_escrows() {
+    let fx = FusionX::setup();
+    let (order, src, dest) = filled(&fx);
+    let order_id = order.input.orderId.clone();
+    let other = fx.other_resolver();
+
+    // we missed both exclusive windows, another resolver settles and keeps
+    // the deposits
+    fx.set_time(FILL_TIME + DEST_RESOLVER_UNLOCK_PERIOD);
+    dest.public_withdraw(&order.secret, &other);
+    let closed_at = FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD;
+    fx.set_time(closed_at);
+    src.public_withdraw(&order.secret, &other);
+    assert_eq!(
+        fx.resolver.get_order_record(&order_id).src,
+        LegStatus::Locked
+    );
+
+    let record = fx.resolver.sync_order(&order_id);
+    assert_eq!(
+        (record.src, record.dest),
+        (LegStatus::Withdrawn, LegStatus::Withdrawn)
+    );
+    assert_eq!(record.amount_in, AMOUNT_IN as i128);
+    assert_eq!(record.deposits_returned, 0);
+    assert_eq!(record.closed_at, Some(closed_at));
+    // syncing again books nothing twice
+    assert_eq!(fx.resolver.sync_order(&order_id), record);
+    assert_eq!(
+        fx.resolver
+            .pair_pnl(&pair(&fx).token_in, &pair(&fx).token_out)
+            .orders,
+        1
+    );
+}
+
+#[test]
+fn a_late_dest_escrow_reopens_the_order() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let src = fx.fill(&order);
+
+    // the src side settles alone and closes the order for now
+    let first_close = FILL_TIME + SRC_FINALITY_LOCK;
+    fx.set_time(first_close);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(
+        fx.resolver.get_order_record(&order.input.orderId).closed_at,
+        Some(first_close)
+    );
+    assert_eq!(fx.resolver.period_pnl(&(first_close / PERIOD)).len(), 1);
+
+    // the dest escrow turns up the next day and takes it out of that period
+    let deployed_at = first_close + PERIOD;
+    fx.set_time(deployed_at);
+    let dest = fx.deploy_dest(&order);
+    let record = fx.resolver.get_order_record(&order.input.orderId);
+    assert_eq!(record.closed_at, None);
+    assert_eq!(record.dest, LegStatus::Locked);
+    assert_eq!(
+        fx.resolver.period_pnl(&(first_close / PERIOD)),
+        map![&fx.env]
+    );
+    assert_eq!(
+        fx.resolver
+            .pair_pnl(&pair(&fx).token_in, &pair(&fx).token_out),
+        PnlTotals::default()
+    );
+
+    let closed_at = deployed_at + DEST_FINALITY_LOCK;
+    fx.set_time(closed_at);
+    fx.resolver
+        .withdraw_dest(&fx.owner, &dest.address, &order.secret);
+    let record = fx.resolver.get_order_record(&order.input.orderId);
+    assert_eq!(record.closed_at, Some(closed_at));
+    assert_eq!(
+        fx.resolver.period_pnl(&(closed_at / PERIOD)).get(pair(&fx)),
+        Some(PnlTotals {
+            orders: 1,
+            amount_in: AMOUNT_IN as i128,
+            amount_out: record.amount_out,
+            deposits_posted: 2 * SECURITY_DEPOSIT,
+            deposits_returned: 2 * SECURITY_DEPOSIT,
+        })
+    );
+    assert_eq!(
+        fx.resolver
+            .pair_pnl(&pair(&fx).token_in, &pair(&fx).token_out)
+            .orders,
+        1
+    );
+}
+
+#[test]
+fn escrows_of_other_fills_are_not_booked() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    // another resolver fills, ours only steps in once its window is over
+    let other = fx.other_resolver();
+    fx.mint_xlm(&other, SECURITY_DEPOSIT);
+    fx.auction
+        .fillOrder(&other, &order.input.orderId, &MAX_AMOUNT_OUT);
+    let src = fx.factory.get_escrow_from_orderid(&order.input.orderId);
+
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    fx.resolver
+        .public_withdraw_src(&fx.owner, &src, &order.secret);
+    assert_eq!(
+        fx.resolver.try_get_order_record(&order.input.orderId),
+        Err(Ok(Error::OrderNotFound.into()))
+    );
+    assert_eq!(
+        fx.resolver.try_sync_order(&order.input.orderId),
+        Err(Ok(Error::OrderNotFound.into()))
+    );
+}
+
+#[test]
+fn period_index_is_paged() {
+    let fx = FusionX::setup();
+    let ids: Vec<_> = (0..=PAGE_SIZE).map(|_| fx.random_bytes()).collect();
+    fx.env.as_contract(&fx.resolver.address, || {
+        for id in &ids {
+            accounting::update(&fx.env, id, |record| record.src = LegStatus::Withdrawn);
+        }
+    });
+
+    let period = START_TIME / PERIOD;
+    assert_eq!(fx.resolver.period_order_count(&period), PAGE_SIZE + 1);
+    assert_eq!(fx.resolver.period_orders(&period, &0).len(), PAGE_SIZE);
+    assert_eq!(
+        fx.resolver.period_orders(&period, &1),
+        vec![&fx.env, ids[PAGE_SIZE as usize].clone()]
+    );
+}
+
+#[test]
+fn the_contract_fills_as_executive_resolver_on_the_owners_auth() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let order_id = order.input.orderId.clone();
+    let this = fx.resolver.address.clone();
+
+    as_owner(
+        &fx,
+        "deploy_escrow_src",
+        (this.clone(), order_id.clone(), MAX_AMOUNT_OUT).into_val(&fx.env),
+    );
+    fx.resolver
+        .deploy_escrow_src(&this, &order_id, &MAX_AMOUNT_OUT);
+    let amount_out = fx.auction.get_order(&order_id).amountOut;
+    let dest_args = (
+        this.clone(),
+        order_id.clone(),
+        order.input.hashLock.clone(),
+        fx.token_out.clone(),
+        amount_out,
+        maker.address.clone(),
+    );
+    as_owner(&fx, "deploy_escrow_dest", dest_args.into_val(&fx.env));
+    fx.resolver.deploy_escrow_dest(
+        &this,
+        &order_id,
+        &order.input.hashLock,
+        &fx.token_out,
+        &amount_out,
+        &maker.address,
+    );
+
+    let record = fx.resolver.get_order_record(&order_id);
+    assert_eq!(
+        (record.src, record.dest),
+        (LegStatus::Locked, LegStatus::Locked)
+    );
+    assert_eq!(record.deposits_posted, 2 * SECURITY_DEPOSIT);
+    assert_eq!(
+        fx.token_out_balance(&fx.dest_factory.get_escrow_from_orderid(&order_id)),
+        amount_out as i128
+    );
+}
@@ -0,0 +1,204 @@
+use fusionx_tests::*;
+use fusionx_types::{Error, SignalSecretShare};
+use resolver::{Action, DestDeployment};
+use soroban_sdk::testutils::{Address as _, MockAuth, MockAuthInvoke};
+use soroban_sdk::{vec, Address, IntoVal, Symbol};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+
+/// A placed order whose auction has started.
+fn placed(fx: &FusionX) -> (Maker, TestOrder) {
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    (maker, order)
+}
+
+fn deploy_dest(fx: &FusionX, order: &TestOrder) -> Action {
+    Action::DeployDest(DestDeployment {
+        caller: fx.resolver.address.clone(),
+        order_id: order.input.orderId.clone(),
+        hash_lock: order.input.hashLock.clone(),
+        token_out: fx.token_out.clone(),
+        amount_out: fx.auction.get_amount_out(&order.input.orderId),
+        maker: order.input.maker.clone(),
+    })
+}
+
+#[test]
+fn fills_and_settles_in_two_batches() {
+    let fx = FusionX::setup();
+    let (maker, order) = placed(&fx);
+    let amount_out = fx.auction.get_amount_out(&order.input.orderId);
+    let escrow_src = fx.random_bytes();
+    let escrow_dest = fx.random_bytes();
+
+    fx.resolver.execute_batch(&vec![
+        &fx.env,
+        Action::Fill(
+            fx.resolver.address.clone(),
+            order.input.orderId.clone(),
+            MAX_AMOUNT_OUT,
+        ),
+        deploy_dest(&fx, &order),
+        Action::Notify(
+            order.input.orderId.clone(),
+            escrow_src.clone(),
+            escrow_dest.clone(),
+            fx.resolver.address.clone(),
+        ),
+    ]);
+    assert_eq!(
+        fx.events::<SignalSecretShare>(&fx.relayer.address, ("signal", "secret")),
+        [SignalSecretShare {
+            escrow_src,
+            escrow_dest,
+            order_id: order.input.orderId.clone(),
+            resolver: fx.resolver.address.clone(),
+        }]
+    );
+    let src = fx.factory.get_escrow_from_orderid(&order.input.orderId);
+    let dest = fx
+        .dest_factory
+        .get_escrow_from_orderid(&order.input.orderId);
+    assert_eq!(fx.token_in_balance(&src), AMOUNT_IN as i128);
+    assert_eq!(fx.token_out_balance(&dest), amount_out as i128);
+
+    // both withdrawals and moving the proceeds out in one go
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK.max(DEST_FINALITY_LOCK));
+    let treasury = Address::generate(&fx.env);
+    fx.resolver.execute_batch(&vec![
+        &fx.env,
+        Action::WithdrawDest(dest, order.secret.clone()),
+        Action::WithdrawSrc(src, order.secret.clone()),
+        Action::Transfer(
+            fx.token_in.address.clone(),
+            treasury.clone(),
+            AMOUNT_IN as i128,
+        ),
+    ]);
+    assert_eq!(fx.token_out_balance(&maker.address), amount_out as i128);
+    assert_eq!(fx.token_in_balance(&treasury), AMOUNT_IN as i128);
+}
+
+#[test]
+fn fill_batches_run_on_the_owners_signature_alone() {
+    let fx = FusionX::setup();
+    let (_, order) = placed(&fx);
+    let amount_out = fx.auction.get_amount_out(&order.input.orderId);
+    let batch = vec![
+        &fx.env,
+        Action::Fill(
+            fx.resolver.address.clone(),
+            order.input.orderId.clone(),
+            MAX_AMOUNT_OUT,
+        ),
+        deploy_dest(&fx, &order),
+    ];
+
+    // the factory calls below the batch are authorized by the contract
+    fx.env.mock_auths(&[MockAuth {
+        address: &fx.owner,
+        invoke: &MockAuthInvoke {
+            contract: &fx.resolver.address,
+            fn_name: "execute_batch",
+            args: (batch.clone(),).into_val(&fx.env),
+            sub_invokes: &[],
+        },
+    }]);
+    fx.resolver.execute_batch(&batch);
+    assert_eq!(
+        fx.auction.get_order(&order.input.orderId).resolverAssigned,
+        Some(fx.resolver.address.clone())
+    );
+    assert_eq!(
+        fx.token_out_balance(
+            &fx.dest_factory
+                .get_escrow_from_orderid(&order.input.orderId)
+        ),
+        amount_out as i128
+    );
+}
+
+#[test]
+fn a_failing_action_reverts_the_whole_batch() {
+    let fx = FusionX::setup();
+    let (_, order) = placed(&fx);
+
+    // the src escrow can't be cancelled right after the fill
+    assert_eq!(
+        fx.resolver.try_execute_batch(&vec![
+            &fx.env,
+            Action::Fill(
+                fx.resolver.address.clone(),
+                order.input.orderId.clone(),
+                MAX_AMOUNT_OUT,
+            ),
+            deploy_dest(&fx, &order),
+            Action::CancelSrc(fx.factory.get_escrow_address(&order.input.orderId)),
+        ]),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+    assert_eq!(
+        fx.auction.get_order(&order.input.orderId).resolverAssigned,
+        None
+    );
+    assert_eq!(
+        fx.dest_factory
+            .try_get_escrow_from_orderid(&order.input.orderId),
+        Err(Ok(Error::OrderNotFound.into()))
+    );
+}
+
+#[test]
+fn arbitrary_calls_need_the_allowlist() {
+    let fx = FusionX::setup();
+    let to = Address::generate(&fx.env);
+    let transfer = Symbol::new(&fx.env, "transfer");
+    let call = Action::Call(
+        fx.xlm.clone(),
+        transfer.clone(),
+        (&fx.resolver.address, &to, SECURITY_DEPOSIT).into_val(&fx.env),
+    );
+
+    assert_eq!(
+        fx.resolver.try_execute_batch(&vec![&fx.env, call.clone()]),
+        Err(Ok(Error::CallNotAllowed.into()))
+    );
+    fx.resolver.set_call_allowed(&fx.xlm, &transfer, &true);
+    assert!(fx.resolver.is_call_allowed(&fx.xlm, &transfer));
+    fx.resolver.execute_batch(&vec![&fx.env, call.clone()]);
+    assert_eq!(fx.xlm_balance(&to), SECURITY_DEPOSIT);
+
+    fx.resolver.set_call_allowed(&fx.xlm, &transfer, &false);
+    assert_eq!(
+        fx.resolver.try_execute_batch(&vec![&fx.env, call]),
+        Err(Ok(Error::CallNotAllowed.into()))
+    );
+}
+
+#[test]
+fn only_the_owner_runs_batches() {
+    let fx = FusionX::setup();
+    let (_, order) = placed(&fx);
+    fx.env.set_auths(&[]);
+    assert!(fx
+        .resolver
+        .try_execute_batch(&vec![
+            &fx.env,
+            Action::Fill(
+                fx.resolver.address.clone(),
+                order.input.orderId.clone(),
+                MAX_AMOUNT_OUT,
+            ),
+        ])
+        .is_err());
+    assert!(fx
+        .resolver
+        .try_set_call_allowed(&fx.xlm, &Symbol::new(&fx.env, "transfer"), &true)
+        .is_err());
+}
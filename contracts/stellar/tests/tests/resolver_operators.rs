@@ -0,0 +1,91 @@
+use fusionx_tests::*;
+use fusionx_types::Error;
+use soroban_sdk::testutils::{Address as _, MockAuth, MockAuthInvoke};
+use soroban_sdk::{vec, Address, IntoVal};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+
+#[test]
+fn owner_manages_the_operator_list() {
+    let fx = FusionX::setup();
+    let (bot_a, bot_b) = (Address::generate(&fx.env), Address::generate(&fx.env));
+    assert_eq!(fx.resolver.get_operators(), vec![&fx.env]);
+    assert!(fx.resolver.is_operator(&fx.owner));
+
+    fx.resolver.add_operator(&bot_a);
+    fx.resolver.add_operator(&bot_b);
+    fx.resolver.add_operator(&bot_a);
+    assert_eq!(
+        fx.resolver.get_operators(),
+        vec![&fx.env, bot_a.clone(), bot_b.clone()]
+    );
+
+    fx.resolver.remove_operator(&bot_a);
+    assert_eq!(fx.resolver.get_operators(), vec![&fx.env, bot_b.clone()]);
+    assert!(!fx.resolver.is_operator(&bot_a));
+    // the owner doesn't change
+    assert_eq!(fx.resolver.get_owner(), fx.owner);
+
+    fx.env.set_auths(&[]);
+    assert!(fx.resolver.try_add_operator(&bot_a).is_err());
+    assert!(fx.resolver.try_remove_operator(&bot_b).is_err());
+}
+
+#[test]
+fn only_operators_settle_and_cancel_escrows() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let src = fx.fill(&order);
+    let dest = fx.deploy_dest(&order);
+    let bot = Address::generate(&fx.env);
+    let stranger = Address::generate(&fx.env);
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_dest(&stranger, &dest.address, &order.secret),
+        Err(Ok(Error::NotOperator.into()))
+    );
+    assert_eq!(
+        fx.resolver.try_cancel_src(&stranger, &src.address),
+        Err(Ok(Error::NotOperator.into()))
+    );
+
+    // an operator has to sign for itself
+    fx.resolver.add_operator(&bot);
+    fx.env.set_auths(&[]);
+    assert!(fx
+        .resolver
+        .try_withdraw_dest(&bot, &dest.address, &order.secret)
+        .is_err());
+    fx.resolver
+        .mock_auths(&[MockAuth {
+            address: &bot,
+            invoke: &MockAuthInvoke {
+                contract: &fx.resolver.address,
+                fn_name: "withdraw_dest",
+                args: (&bot, &dest.address, &order.secret).into_val(&fx.env),
+                sub_invokes: &[],
+            },
+        }])
+        .withdraw_dest(&bot, &dest.address, &order.secret);
+    fx.env.mock_all_auths();
+
+    // removed operators lose access straight away
+    fx.resolver.remove_operator(&bot);
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw_src(&bot, &src.address, &order.secret),
+        Err(Ok(Error::NotOperator.into()))
+    );
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(fx.token_in_balance(&fx.resolver.address), AMOUNT_IN as i128);
+}
@@ -0,0 +1,345 @@
+use fusionx_tests::*;
+use fusionx_types::interfaces::EscrowSrcClient;
+use fusionx_types::{Error, Immutables, SettlementKind, StakeSlashed};
+use relayer::staking::{SlashTotals, Stake, StakingConfig};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{token, Address};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+const MIN_STAKE: i128 = 1_000_000;
+const UNBONDING_PERIOD: u64 = 7 * 24 * 60 * 60;
+const SLASH_AMOUNT: i128 = 300_000;
+
+/// Turns on staking in a fresh token the owner can mint.
+fn staking(fx: &FusionX) -> token::StellarAssetClient<'static> {
+    let stake_token = fx
+        .env
+        .register_stellar_asset_contract_v2(fx.owner.clone())
+        .address();
+    fx.relayer.set_staking_config(&StakingConfig {
+        token: stake_token.clone(),
+        min_stake: MIN_STAKE,
+        unbonding_period: UNBONDING_PERIOD,
+        slash_amount: SLASH_AMOUNT,
+    });
+    token::StellarAssetClient::new(&fx.env, &stake_token)
+}
+
+/// A resolver that is not whitelisted, bonded with `amount`.
+fn staker(fx: &FusionX, stake_token: &token::StellarAssetClient, amount: i128) -> Address {
+    let staker = Address::generate(&fx.env);
+    stake_token.mint(&staker, &amount);
+    fx.relayer.bond(&staker, &staker, &amount);
+    fx.mint_xlm(&staker, SECURITY_DEPOSIT);
+    staker
+}
+
+fn placed_order(fx: &FusionX) -> TestOrder {
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    order
+}
+
+#[test]
+fn bonded_stake_registers_a_resolver() {
+    let fx = FusionX::setup();
+    let stake_token = staking(&fx);
+    let staker = staker(&fx, &stake_token, MIN_STAKE - 1);
+    assert!(!fx.relayer.is_resolver(&staker));
+    stake_token.mint(&staker, &1);
+    fx.relayer.bond(&staker, &staker, &1);
+    assert!(fx.relayer.is_resolver(&staker));
+
+    // fills like a whitelisted resolver
+    let order = placed_order(&fx);
+    fx.set_time(FILL_TIME);
+    fx.auction
+        .fillOrder(&staker, &order.input.orderId, &MAX_AMOUNT_OUT);
+
+    // unbonding stake stops counting right away and pays out after the delay
+    fx.relayer.unbond(&staker, &1);
+    assert!(!fx.relayer.is_resolver(&staker));
+    assert_eq!(
+        fx.relayer.get_stake(&staker),
+        Stake {
+            bonded: MIN_STAKE - 1,
+            unbonding: 1,
+            unlock_at: FILL_TIME + UNBONDING_PERIOD,
+        }
+    );
+    assert_eq!(
+        fx.relayer.try_withdraw_unbonded(&staker),
+        Err(Ok(Error::InvalidTime.into()))
+    );
+    fx.set_time(FILL_TIME + UNBONDING_PERIOD);
+    assert_eq!(fx.relayer.withdraw_unbonded(&staker), 1);
+    assert_eq!(
+        token::Client::new(&fx.env, &stake_token.address).balance(&staker),
+        1
+    );
+    assert_eq!(
+        fx.relayer.try_unbond(&staker, &MIN_STAKE),
+        Err(Ok(Error::InsufficientBalance.into()))
+    );
+}
+
+#[test]
+fn the_staking_token_only_changes_without_stake() {
+    let fx = FusionX::setup();
+    let stake_token = staking(&fx);
+    let staker = staker(&fx, &stake_token, MIN_STAKE);
+    let mut config = fx.relayer.get_staking_config();
+    config.token = fx.xlm.clone();
+
+    // the stake would be paid back in the new token
+    assert_eq!(
+        fx.relayer.try_set_staking_config(&config),
+        Err(Ok(Error::StakeOutstanding.into()))
+    );
+    fx.relayer.unbond(&staker, &MIN_STAKE);
+    assert_eq!(fx.relayer.get_total_stake(), MIN_STAKE);
+    assert_eq!(
+        fx.relayer.try_set_staking_config(&config),
+        Err(Ok(Error::StakeOutstanding.into()))
+    );
+    // the rest of the config can change any time
+    fx.relayer.set_staking_config(&StakingConfig {
+        slash_amount: 0,
+        ..fx.relayer.get_staking_config()
+    });
+
+    fx.set_time(fx.now() + UNBONDING_PERIOD);
+    fx.relayer.withdraw_unbonded(&staker);
+    assert_eq!(fx.relayer.get_total_stake(), 0);
+    fx.relayer.set_staking_config(&config);
+    assert_eq!(fx.relayer.get_staking_config().token, fx.xlm);
+}
+
+#[test]
+fn resolver_contracts_bond_out_of_their_treasury() {
+    let fx = FusionX::setup();
+    let stake_token = staking(&fx);
+    fx.relayer.remove_resolver(&fx.resolver.address);
+    assert!(!fx.relayer.is_resolver(&fx.resolver.address));
+
+    stake_token.mint(&fx.resolver.address, &MIN_STAKE);
+    fx.resolver.bond(&stake_token.address, &MIN_STAKE);
+    assert!(fx.relayer.is_resolver(&fx.resolver.address));
+    assert_eq!(fx.resolver.inventory(&stake_token.address), 0);
+
+    assert_eq!(
+        fx.resolver.try_bond(&stake_token.address, &1),
+        Err(Ok(Error::InsufficientBalance.into()))
+    );
+
+    fx.resolver.unbond(&MIN_STAKE);
+    fx.set_time(START_TIME + UNBONDING_PERIOD);
+    assert_eq!(fx.resolver.withdraw_unbonded(), MIN_STAKE);
+    assert_eq!(fx.resolver.inventory(&stake_token.address), MIN_STAKE);
+}
+
+#[test]
+fn bonding_native_tokens_leaves_the_reserve() {
+    let fx = FusionX::setup();
+    fx.relayer.set_staking_config(&StakingConfig {
+        token: fx.xlm.clone(),
+        min_stake: MIN_STAKE,
+        unbonding_period: UNBONDING_PERIOD,
+        slash_amount: SLASH_AMOUNT,
+    });
+    let treasury = fx.xlm_balance(&fx.resolver.address);
+    fx.resolver.set_native_reserve(&(treasury - MIN_STAKE));
+
+    assert_eq!(
+        fx.resolver.try_bond(&fx.xlm, &(MIN_STAKE + 1)),
+        Err(Ok(Error::BelowReserve.into()))
+    );
+    fx.resolver.bond(&fx.xlm, &MIN_STAKE);
+    assert_eq!(fx.relayer.get_stake(&fx.resolver.address).bonded, MIN_STAKE);
+    assert_eq!(fx.resolver.withdrawable(&fx.xlm), 0);
+}
+
+#[test]
+fn missing_the_exclusive_window_is_slashed() {
+    let fx = FusionX::setup();
+    let stake_token = staking(&fx);
+    let staker = staker(&fx, &stake_token, 2 * MIN_STAKE);
+    let order = placed_order(&fx);
+    fx.set_time(FILL_TIME);
+    fx.auction
+        .fillOrder(&staker, &order.input.orderId, &MAX_AMOUNT_OUT);
+    let src = fx.factory.get_escrow_from_orderid(&order.input.orderId);
+    assert_eq!(
+        fx.relayer.try_slash(&fx.factory.address, &src),
+        Err(Ok(Error::NoMisbehaviour.into()))
+    );
+
+    // the staker leaves its withdrawal to the public window, then tries to
+    // take its stake out before anyone notices
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    fx.resolver
+        .public_withdraw_src(&fx.owner, &src, &order.secret);
+    fx.relayer.unbond(&staker, &(2 * MIN_STAKE));
+
+    assert_eq!(
+        fx.relayer.try_slash(&Address::generate(&fx.env), &src),
+        Err(Ok(Error::UntrustedFactory.into()))
+    );
+    // the maker whose order was held up gets the slashed stake
+    fx.trust(&order.input.maker, &stake_token.address);
+    assert_eq!(fx.relayer.slash(&fx.factory.address, &src), SLASH_AMOUNT);
+    assert_eq!(
+        fx.events::<StakeSlashed>(&fx.relayer.address, ("stake", "slash")),
+        [StakeSlashed {
+            resolver: staker.clone(),
+            escrow: src.clone(),
+            order_id: order.input.orderId.clone(),
+            kind: SettlementKind::PublicWithdrawn,
+            amount: SLASH_AMOUNT,
+            maker: order.input.maker.clone(),
+            paid: true,
+        }]
+    );
+    assert_eq!(
+        token::Client::new(&fx.env, &stake_token.address).balance(&order.input.maker),
+        SLASH_AMOUNT
+    );
+    assert_eq!(
+        token::Client::new(&fx.env, &stake_token.address).balance(&fx.owner),
+        0
+    );
+    assert_eq!(
+        fx.relayer.get_stake(&staker).unbonding,
+        2 * MIN_STAKE - SLASH_AMOUNT
+    );
+    assert_eq!(
+        fx.relayer.get_slashes(&staker),
+        SlashTotals {
+            count: 1,
+            amount: SLASH_AMOUNT,
+        }
+    );
+    assert_eq!(
+        fx.relayer.try_slash(&fx.factory.address, &src),
+        Err(Ok(Error::AlreadySlashed.into()))
+    );
+}
+
+#[test]
+fn stake_the_maker_cant_take_stays_locked() {
+    let fx = FusionX::setup();
+    let stake_token = staking(&fx);
+    let staker = staker(&fx, &stake_token, MIN_STAKE);
+    let order = placed_order(&fx);
+    fx.set_time(FILL_TIME);
+    fx.auction
+        .fillOrder(&staker, &order.input.orderId, &MAX_AMOUNT_OUT);
+    let src = fx.factory.get_escrow_from_orderid(&order.input.orderId);
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    fx.resolver
+        .public_withdraw_src(&fx.owner, &src, &order.secret);
+
+    // no trustline to the stake token, the slash still goes through
+    assert_eq!(fx.relayer.slash(&fx.factory.address, &src), SLASH_AMOUNT);
+    assert!(!fx.events::<StakeSlashed>(&fx.relayer.address, ("stake", "slash"))[0].paid);
+    assert_eq!(
+        fx.relayer.get_stake(&staker).bonded,
+        MIN_STAKE - SLASH_AMOUNT
+    );
+    assert_eq!(
+        token::Client::new(&fx.env, &stake_token.address).balance(&fx.relayer.address),
+        MIN_STAKE
+    );
+    assert_eq!(fx.relayer.get_total_stake(), MIN_STAKE - SLASH_AMOUNT);
+}
+
+#[test]
+fn settling_in_the_exclusive_window_is_not_slashable() {
+    let fx = FusionX::setup();
+    staking(&fx);
+    let order = placed_order(&fx);
+    fx.set_time(FILL_TIME);
+    let src = fx.fill(&order);
+    let dest = fx.deploy_dest(&order);
+
+    fx.set_time(FILL_TIME + DEST_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_dest(&fx.owner, &dest.address, &order.secret);
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+
+    assert_eq!(
+        src.get_settlement().unwrap().kind,
+        SettlementKind::Withdrawn
+    );
+    assert_eq!(
+        fx.relayer.try_slash(&fx.factory.address, &src.address),
+        Err(Ok(Error::NoMisbehaviour.into()))
+    );
+    assert_eq!(
+        fx.relayer
+            .try_slash(&fx.dest_factory.address, &dest.address),
+        Err(Ok(Error::NoMisbehaviour.into()))
+    );
+    // escrows of one factory don't prove anything through another
+    assert_eq!(
+        fx.relayer.try_slash(&fx.factory.address, &dest.address),
+        Err(Ok(Error::EscrowNotFound.into()))
+    );
+}
+
+#[test]
+fn escrows_cant_be_rewritten_to_frame_a_resolver() {
+    let fx = FusionX::setup();
+    let stake_token = staking(&fx);
+    let victim = staker(&fx, &stake_token, MIN_STAKE);
+    let order = placed_order(&fx);
+    fx.set_time(FILL_TIME);
+    fx.auction
+        .fillOrder(&victim, &order.input.orderId, &MAX_AMOUNT_OUT);
+    let src = EscrowSrcClient::new(
+        &fx.env,
+        &fx.factory.get_escrow_from_orderid(&order.input.orderId),
+    );
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    src.withdraw(&order.secret, &victim);
+
+    // with the secret public, an attacker refunds the escrow and tries to
+    // settle it again through the public path, or to point it at someone else
+    let attacker = fx.other_resolver();
+    assert_eq!(
+        src.try_initialize(&Immutables {
+            order_id: order.input.orderId.clone(),
+            hash_lock: order.input.hashLock.clone(),
+            token: fx.token_in.address.clone(),
+            amount: 0,
+            maker: attacker.clone(),
+            executive_resolver: victim.clone(),
+            relayer: fx.relayer.address.clone(),
+            native_token: fx.xlm.clone(),
+        }),
+        Err(Ok(Error::AlreadyInitialized.into()))
+    );
+    fx.set_time(FILL_TIME + SRC_RESOLVER_UNLOCK_PERIOD);
+    fx.token_in.mint(&src.address, &(AMOUNT_IN as i128));
+    fx.mint_xlm(&src.address, SECURITY_DEPOSIT);
+    assert_eq!(
+        src.try_public_withdraw(&order.secret, &attacker),
+        Err(Ok(Error::AlreadySettled.into()))
+    );
+
+    assert_eq!(
+        src.get_settlement().unwrap().kind,
+        SettlementKind::Withdrawn
+    );
+    assert_eq!(
+        fx.relayer.try_slash(&fx.factory.address, &src.address),
+        Err(Ok(Error::NoMisbehaviour.into()))
+    );
+    assert_eq!(fx.relayer.get_stake(&victim).bonded, MIN_STAKE);
+}
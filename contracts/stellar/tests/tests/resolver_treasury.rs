@@ -0,0 +1,107 @@
+use fusionx_tests::*;
+use fusionx_types::Error;
+use resolver::Action;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{vec, Address};
+
+const AMOUNT_IN: u128 = 1_000_000_000_000_000_000;
+const MIN_AMOUNT_OUT: u128 = 900_000_000;
+const MAX_AMOUNT_OUT: u128 = 1_000_000_000;
+const FILL_TIME: u64 = START_TIME + AUCTION_START_BUFFER;
+const TOKEN_OUT_INVENTORY: i128 = 1_000_000_000_000;
+
+#[test]
+fn deposits_and_withdrawals_move_inventory() {
+    let fx = FusionX::setup();
+    let funder = Address::generate(&fx.env);
+    fx.mint_token_out(&funder, 1_000);
+
+    fx.resolver.deposit(&fx.token_out, &funder, &1_000);
+    assert_eq!(fx.token_out_balance(&funder), 0);
+    assert_eq!(
+        fx.resolver.inventory(&fx.token_out),
+        TOKEN_OUT_INVENTORY + 1_000
+    );
+
+    let to = Address::generate(&fx.env);
+    fx.resolver.withdraw(&fx.token_out, &to, &400);
+    assert_eq!(fx.token_out_balance(&to), 400);
+    assert_eq!(
+        fx.resolver
+            .try_withdraw(&fx.token_out, &to, &(TOKEN_OUT_INVENTORY + 601)),
+        Err(Ok(Error::InsufficientBalance.into()))
+    );
+
+    assert_eq!(
+        fx.resolver.sweep(&fx.token_out, &to),
+        TOKEN_OUT_INVENTORY + 600
+    );
+    assert_eq!(fx.resolver.inventory(&fx.token_out), 0);
+    assert_eq!(fx.resolver.sweep(&fx.token_out, &to), 0);
+}
+
+#[test]
+fn escrow_payouts_land_in_the_treasury() {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT_IN, AMOUNT_IN);
+    let order = fx.order(&maker, AMOUNT_IN, MIN_AMOUNT_OUT, MAX_AMOUNT_OUT);
+    fx.place_order(&maker, &order);
+    fx.set_time(FILL_TIME);
+    let src = fx.fill(&order);
+    assert_eq!(fx.resolver.security_deposits(), (9 * SECURITY_DEPOSIT, 9));
+
+    fx.set_time(FILL_TIME + SRC_FINALITY_LOCK);
+    fx.resolver
+        .withdraw_src(&fx.owner, &src.address, &order.secret);
+    assert_eq!(
+        fx.resolver.inventory(&fx.token_in.address),
+        AMOUNT_IN as i128
+    );
+    // the deposit came back with the tokens
+    assert_eq!(fx.resolver.security_deposits(), (10 * SECURITY_DEPOSIT, 10));
+
+    let treasury = Address::generate(&fx.env);
+    fx.resolver.sweep(&fx.token_in.address, &treasury);
+    assert_eq!(fx.token_in_balance(&treasury), AMOUNT_IN as i128);
+}
+
+#[test]
+fn the_native_reserve_stays_for_security_deposits() {
+    let fx = FusionX::setup();
+    let to = Address::generate(&fx.env);
+    assert_eq!(
+        fx.resolver.try_set_native_reserve(&-1),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+    fx.resolver.set_native_reserve(&(4 * SECURITY_DEPOSIT));
+    assert_eq!(fx.resolver.get_native_reserve(), 4 * SECURITY_DEPOSIT);
+    assert_eq!(fx.resolver.withdrawable(&fx.xlm), 6 * SECURITY_DEPOSIT);
+
+    assert_eq!(
+        fx.resolver
+            .try_withdraw(&fx.xlm, &to, &(6 * SECURITY_DEPOSIT + 1)),
+        Err(Ok(Error::BelowReserve.into()))
+    );
+    // batched transfers respect it too
+    assert_eq!(
+        fx.resolver.try_execute_batch(&vec![
+            &fx.env,
+            Action::Transfer(fx.xlm.clone(), to.clone(), 7 * SECURITY_DEPOSIT),
+        ]),
+        Err(Ok(Error::BelowReserve.into()))
+    );
+    assert_eq!(fx.resolver.sweep(&fx.xlm, &to), 6 * SECURITY_DEPOSIT);
+    assert_eq!(fx.resolver.security_deposits(), (4 * SECURITY_DEPOSIT, 4));
+    assert_eq!(fx.xlm_balance(&to), 6 * SECURITY_DEPOSIT);
+}
+
+#[test]
+fn only_the_owner_moves_funds() {
+    let fx = FusionX::setup();
+    let to = Address::generate(&fx.env);
+    fx.env.set_auths(&[]);
+    assert!(fx.resolver.try_withdraw(&fx.xlm, &to, &1).is_err());
+    assert!(fx.resolver.try_sweep(&fx.token_out, &to).is_err());
+    assert!(fx.resolver.try_set_native_reserve(&0).is_err());
+    assert!(fx.resolver.try_deposit(&fx.xlm, &fx.owner, &1).is_err());
+}
@@ -0,0 +1,170 @@
+use fusionx_tests::*;
+use fusionx_types::Error;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::{token, vec, Address, IntoVal, String, Symbol};
+
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+
+/// A maker with `AMOUNT` wrapped MockToken and the SEP-41 token for it.
+fn setup<'a>() -> (FusionX<'a>, Maker, token::Client<'a>) {
+    let fx = FusionX::setup();
+    let maker = fx.maker(AMOUNT, AMOUNT);
+    let asset = fx.wrapped.deploy_wrapped_asset(&fx.token_in.address);
+    let client = token::Client::new(&fx.env, &asset);
+    (fx, maker, client)
+}
+
+#[test]
+fn wrapped_asset_is_deployed_once_per_token() {
+    let fx = FusionX::setup();
+    assert_eq!(
+        fx.wrapped.try_get_wrapped_asset(&fx.token_in.address),
+        Err(Ok(Error::WrappedAssetNotDeployed.into()))
+    );
+
+    let asset = fx.wrapped.deploy_wrapped_asset(&fx.token_in.address);
+    assert_eq!(fx.wrapped.get_wrapped_asset(&fx.token_in.address), asset);
+    assert_eq!(fx.wrapped.deploy_wrapped_asset(&fx.token_in.address), asset);
+    assert_ne!(fx.wrapped.deploy_wrapped_asset(&fx.xlm), asset);
+}
+
+#[test]
+fn transfers_move_wrapped_balances() {
+    let (fx, maker, asset) = setup();
+    let to = Address::generate(&fx.env);
+    let amount = AMOUNT as i128;
+
+    assert_eq!(asset.balance(&maker.address), amount);
+    asset.transfer(&maker.address, &to, &(amount / 4));
+    assert_eq!(
+        fx.env.events().all(),
+        vec![
+            &fx.env,
+            (
+                asset.address.clone(),
+                (Symbol::new(&fx.env, "transfer"), &maker.address, &to).into_val(&fx.env),
+                (amount / 4).into_val(&fx.env),
+            )
+        ]
+    );
+    assert_eq!(asset.balance(&to), amount / 4);
+    assert_eq!(
+        fx.wrapped.get_balance(&fx.token_in.address, &maker.address),
+        AMOUNT / 4 * 3
+    );
+
+    // the underlying can still be unwrapped from WrappedTokens
+    fx.wrapped
+        .withdraw(&fx.token_in.address, &(AMOUNT / 4), &to);
+    assert_eq!(asset.balance(&to), 0);
+    assert_eq!(fx.token_in.balance(&to), amount / 4);
+
+    assert_eq!(
+        asset.try_transfer(&maker.address, &to, &(amount + 1)),
+        Err(Ok(Error::InsufficientBalance.into()))
+    );
+    assert_eq!(
+        asset.try_transfer(&maker.address, &to, &-1),
+        Err(Ok(Error::InvalidAmount.into()))
+    );
+}
+
+#[test]
+fn allowances_expire_and_are_spent() {
+    let (fx, maker, asset) = setup();
+    let spender = Address::generate(&fx.env);
+    let to = Address::generate(&fx.env);
+    let ledger = 100;
+    fx.env.ledger().with_mut(|l| l.sequence_number = ledger);
+    let amount = AMOUNT as i128;
+
+    assert_eq!(
+        asset.try_approve(&maker.address, &spender, &amount, &(ledger - 1)),
+        Err(Ok(Error::InvalidExpirationLedger.into()))
+    );
+    asset.approve(&maker.address, &spender, &amount, &(ledger + 10));
+    assert_eq!(asset.allowance(&maker.address, &spender), amount);
+
+    asset.transfer_from(&spender, &maker.address, &to, &(amount / 2));
+    assert_eq!(asset.allowance(&maker.address, &spender), amount / 2);
+    assert_eq!(asset.balance(&to), amount / 2);
+    assert_eq!(
+        asset.try_transfer_from(&spender, &maker.address, &to, &(amount / 2 + 1)),
+        Err(Ok(Error::InsufficientAllowance.into()))
+    );
+
+    // spending keeps the expiration
+    fx.env
+        .ledger()
+        .with_mut(|l| l.sequence_number = ledger + 11);
+    assert_eq!(asset.allowance(&maker.address, &spender), 0);
+    assert_eq!(
+        asset.try_transfer_from(&spender, &maker.address, &to, &1),
+        Err(Ok(Error::InsufficientAllowance.into()))
+    );
+}
+
+#[test]
+fn burns_destroy_wrapped_balances() {
+    let (fx, maker, asset) = setup();
+    let spender = Address::generate(&fx.env);
+    let amount = AMOUNT as i128;
+
+    asset.burn(&maker.address, &(amount / 2));
+    assert_eq!(asset.balance(&maker.address), amount / 2);
+
+    asset.approve(
+        &maker.address,
+        &spender,
+        &amount,
+        &fx.env.ledger().sequence(),
+    );
+    asset.burn_from(&spender, &maker.address, &(amount / 2));
+    assert_eq!(asset.balance(&maker.address), 0);
+    assert_eq!(asset.allowance(&maker.address, &spender), amount / 2);
+
+    // the burnt underlying stays locked
+    assert_eq!(fx.token_in.balance(&fx.wrapped.address), amount);
+}
+
+#[test]
+fn permit_allowances_are_spendable_as_sep41() {
+    let (fx, maker, asset) = setup();
+    let order = fx.order(&maker, AMOUNT, 1, 1);
+    fx.place_order(&maker, &order);
+
+    // the relayer's permit allowance never expires
+    assert_eq!(
+        asset.allowance(&maker.address, &fx.relayer.address),
+        AMOUNT as i128
+    );
+    fx.env.ledger().with_mut(|l| l.sequence_number += 1_000);
+    assert_eq!(
+        asset.allowance(&maker.address, &fx.relayer.address),
+        AMOUNT as i128
+    );
+}
+
+#[test]
+fn only_the_wrapped_asset_moves_balances() {
+    let (fx, maker, asset) = setup();
+    let thief = Address::generate(&fx.env);
+    fx.env.set_auths(&[]);
+    assert!(fx
+        .wrapped
+        .try_asset_transfer(&fx.token_in.address, &maker.address, &thief, &AMOUNT)
+        .is_err());
+    assert_eq!(asset.balance(&thief), 0);
+}
+
+#[test]
+fn metadata_comes_from_the_underlying() {
+    let fx = FusionX::setup();
+    let underlying = token::Client::new(&fx.env, &fx.xlm);
+    let asset = token::Client::new(&fx.env, &fx.wrapped.deploy_wrapped_asset(&fx.xlm));
+
+    assert_eq!(asset.decimals(), underlying.decimals());
+    assert_eq!(asset.symbol(), String::from_str(&fx.env, "waaa"));
+    let name = format!("Wrapped {}", underlying.name().to_string());
+    assert_eq!(asset.name(), String::from_str(&fx.env, &name));
+}
@@ -1,17 +1,52 @@
-const { Keypair, hash } = require('@stellar/stellar-sdk');
+const { Address, Keypair, Networks, hash } = require('@stellar/stellar-sdk');
 const { Buffer } = require('buffer');
 
 // Contract parameters
+const wrappedTokens = "CAHP43PLRNMDOQEPSS66C5MBRWQYM2UV3SOQSUW7VWO73R47V7CO272T";
 const token = "CA43QY2RGDPFVYX4V7Y544HTNBN4GTQGTUBD6YX5SEJIG42LBTTPZFQG";
 const owner = "GB3I5T27E4VXU7PTTIWPBFJTKPUT4SIXKCMK3V6FKU6QXL7Q4TWCZTWQ";
 const spender = "GB3I5T27E4VXU7PTTIWPBFJTKPUT4SIXKCMK3V6FKU6QXL7Q4TWCZTWQ";
 const amount = "100000000000000000"; // 0.1 tokens
+const nonce = "0";
+const deadline = String(Math.floor(Date.now() / 1000) + 60 * 60); // valid for an hour
 
-// Step 1: Create deterministic hash
-function createPermitHash(token, owner, spender, amount) {
-    const message = `${token}:${owner}:${spender}:${amount}`;
-    const messageBuffer = Buffer.from(message, 'utf8');
-    return hash(messageBuffer);
+// Must match PERMIT_DOMAIN in contracts/stellar/contracts/WrappedTokens
+const PERMIT_DOMAIN = 'FusionX WrappedTokens permit v1';
+
+function addressXdr(address) {
+    return Address.fromString(address).toScVal().toXDR();
+}
+
+function uintBE(value, bytes) {
+    const out = Buffer.alloc(bytes);
+    let x = BigInt(value);
+    for (let i = bytes - 1; i >= 0; i--) {
+        out[i] = Number(x & 0xffn);
+        x >>= 8n;
+    }
+    return out;
+}
+
+// sha256(PERMIT_DOMAIN || sha256(passphrase) || xdr(contract))
+function createDomainSeparator(contractId, networkPassphrase) {
+    return hash(Buffer.concat([
+        Buffer.from(PERMIT_DOMAIN, 'utf8'),
+        hash(Buffer.from(networkPassphrase, 'utf8')),
+        addressXdr(contractId),
+    ]));
+}
+
+// Step 1: Create the digest WrappedTokens.permit_digest computes on chain
+function createPermitDigest({ contractId, networkPassphrase, token, owner, spender, amount, nonce, deadline }) {
+    return hash(Buffer.concat([
+        createDomainSeparator(contractId, networkPassphrase),
+        addressXdr(token),
+        addressXdr(owner),
+        addressXdr(spender),
+        uintBE(amount, 16),
+        uintBE(nonce, 8),
+        uintBE(deadline, 8),
+    ]));
 }
 
 // Step 2: Sign the hash
@@ -38,10 +73,16 @@ async function generatePermitSignature() {
     console.log(`Owner: ${owner}`);
     console.log(`Spender: ${spender}`);
     console.log(`Amount: ${amount}`);
+    console.log(`Nonce: ${nonce}`);
+    console.log(`Deadline: ${deadline}`);
     console.log('');
     
     // Step 1: Create hash
-    const permitHash = createPermitHash(token, owner, spender, amount);
+    const permitHash = createPermitDigest({
+        contractId: wrappedTokens,
+        networkPassphrase: Networks.TESTNET,
+        token, owner, spender, amount, nonce, deadline,
+    });
     console.log('=== Step 1: Hash ===');
     console.log(`Hash (hex): ${permitHash.toString('hex')}`);
     console.log(`Hash (base64): ${permitHash.toString('base64')}`);
@@ -58,7 +99,7 @@ async function generatePermitSignature() {
     // Step 3: Generate CLI command
     console.log('=== Step 3: CLI Command ===');
     console.log(`stellar contract invoke \\`);
-    console.log(`  --id CAHP43PLRNMDOQEPSS66C5MBRWQYM2UV3SOQSUW7VWO73R47V7CO272T \\`);
+    console.log(`  --id ${wrappedTokens} \\`);
     console.log(`  --source alice \\`);
     console.log(`  --network testnet \\`);
     console.log(`  -- permit \\`);
@@ -66,9 +107,10 @@ async function generatePermitSignature() {
     console.log(`  --owner ${owner} \\`);
     console.log(`  --spender ${spender} \\`);
     console.log(`  --amount ${amount} \\`);
+    console.log(`  --nonce ${nonce} \\`);
+    console.log(`  --deadline ${deadline} \\`);
     console.log(`  --public_key ${signatureData.publicKeyBytes.toString('hex')} \\`);
     console.log(`  --signature ${signatureData.signatureBytes.toString('hex')} \\`);
-    console.log(`  --hash ${permitHash.toString('hex')} \\`);
     console.log(`  --send=yes`);
 }
 
@@ -76,7 +118,7 @@ async function generatePermitSignature() {
 function browserExample() {
     // This would work in a web app where user connects their wallet
     return {
-        createHash: createPermitHash,
+        createHash: createPermitDigest,
         signWithWallet: async (walletSigner, hash) => {
             // Integrate with Freighter, xBull, or other Stellar wallets
             const signature = await walletSigner.sign(hash);
@@ -85,15 +127,34 @@ function browserExample() {
     };
 }
 
+// Checks the encoding against test-vectors.json, shared with the contract tests
+function verifyTestVectors() {
+    const vectors = require('./test-vectors.json');
+    for (const vector of vectors) {
+        const domain = createDomainSeparator(vector.contractId, vector.networkPassphrase);
+        const digest = createPermitDigest(vector);
+        if (domain.toString('hex') !== vector.domainSeparator || digest.toString('hex') !== vector.digest) {
+            throw new Error(`permit encoding mismatch for ${JSON.stringify(vector)}`);
+        }
+    }
+    console.log(`${vectors.length} permit test vectors ok`);
+}
+
 // Export for use
 module.exports = {
-    createPermitHash,
+    createDomainSeparator,
+    createPermitDigest,
     signPermit,
+    verifyTestVectors,
     generatePermitSignature,
     browserExample
 };
 
 // Run example if this file is executed directly
 if (require.main === module) {
-    generatePermitSignature().catch(console.error);
+    if (process.argv.includes('--test-vectors')) {
+        verifyTestVectors();
+    } else {
+        generatePermitSignature().catch(console.error);
+    }
 }
\ No newline at end of file
@@ -4,6 +4,7 @@ const StellarSdk = require("@stellar/stellar-sdk");
 const { Buffer } = require("buffer");
 
 const {
+  Address,
   Keypair,
   TransactionBuilder,
   Networks,
@@ -27,18 +28,54 @@ const server = new rpc.Server("https://soroban-testnet.stellar.org");
 const networkPassphrase = Networks.TESTNET;
 const aliceKeypair = Keypair.fromSecret(ALICE_PRIVATE_KEY);
 
+// Must match PERMIT_DOMAIN in contracts/stellar/contracts/WrappedTokens
+const PERMIT_DOMAIN = "FusionX WrappedTokens permit v1";
+const PERMIT_VALIDITY_SECONDS = 60 * 60;
+
+function addressXdr(address) {
+  return Address.fromString(address).toScVal().toXDR();
+}
+
+function uintBE(value, bytes) {
+  const out = Buffer.alloc(bytes);
+  let x = BigInt(value);
+  for (let i = bytes - 1; i >= 0; i--) {
+    out[i] = Number(x & 0xffn);
+    x >>= 8n;
+  }
+  return out;
+}
+
 /**
- * Create permit hash for wrapped token
+ * Create the permit digest WrappedTokens.permit_digest computes on chain,
+ * see permit/test-vectors.json
  * @param {string} token - Token contract address
  * @param {string} owner - Owner address
  * @param {string} spender - Spender address (relayer)
  * @param {string|number} amount - Amount to permit
+ * @param {string|number} nonce - Permit nonce
+ * @param {string|number} deadline - Last ledger timestamp the permit is valid at
  * @returns {Buffer} - Hash for signing
  */
-function createPermitHash(token, owner, spender, amount) {
-  const message = `${token}:${owner}:${spender}:${amount}`;
-  const messageBuffer = Buffer.from(message, "utf8");
-  return hash(messageBuffer);
+function createPermitDigest(token, owner, spender, amount, nonce, deadline) {
+  const domainSeparator = hash(
+    Buffer.concat([
+      Buffer.from(PERMIT_DOMAIN, "utf8"),
+      hash(Buffer.from(networkPassphrase, "utf8")),
+      addressXdr(WRAPPED_TOKEN_CONTRACT_ADDRESS),
+    ])
+  );
+  return hash(
+    Buffer.concat([
+      domainSeparator,
+      addressXdr(token),
+      addressXdr(owner),
+      addressXdr(spender),
+      uintBE(amount, 16),
+      uintBE(nonce, 8),
+      uintBE(deadline, 8),
+    ])
+  );
 }
 
 /**
@@ -110,16 +147,23 @@ async function placeStellarOrder(
     // Step 1: Create permit signature for wrapped token
     console.log("\n🔐 Creating permit signature...");
 
-    const permitHash = createPermitHash(
+    const nonce = orderData.nonce ?? Date.now();
+    const deadline =
+      orderData.deadline ??
+      Math.floor(Date.now() / 1000) + PERMIT_VALIDITY_SECONDS;
+    const permitHash = createPermitDigest(
       orderData.tokenIn,
       orderData.maker,
       RELAYER_CONTRACT_ADDRESS,
-      orderData.amountIn
+      orderData.amountIn,
+      nonce,
+      deadline
     );
 
     const signatureData = signPermit(makerPrivateKey, permitHash);
 
-    console.log(`Permit Hash: ${permitHash.toString("hex")}`);
+    console.log(`Permit Digest: ${permitHash.toString("hex")}`);
+    console.log(`Nonce: ${nonce}, Deadline: ${deadline}`);
     console.log(`Public Key: ${signatureData.publicKey}`);
     console.log(`Signature: ${signatureData.signatureBytes.toString("hex")}`);
 
@@ -139,7 +183,7 @@ async function placeStellarOrder(
     const account = await server.getAccount(ownerKeypair.publicKey());
     const relayerContract = new Contract(RELAYER_CONTRACT_ADDRESS);
 
-    // Contract function: place_order(env, order_input, public_key, signature, hash)
+    // Contract function: place_order(env, order_input, nonce, deadline, public_key, signature)
     const operation = relayerContract.call(
       "place_order",
       // OrderInput struct
@@ -147,7 +191,7 @@ async function placeStellarOrder(
         {
           orderId: nativeToScVal(orderInput.orderId, { type: "bytes" }),
           maker: nativeToScVal(orderInput.maker, { type: "address" }),
-          tokenIn: nativeToScVal(orderInput.tokenIn, { type: "bytes" }),
+          tokenIn: nativeToScVal(orderData.tokenIn, { type: "address" }),
           tokenOut: nativeToScVal(orderInput.tokenOut, { type: "bytes" }),
           amountIn: nativeToScVal(orderInput.amountIn, { type: "u128" }),
           minAmountOut: nativeToScVal(orderInput.minAmountOut, {
@@ -161,9 +205,10 @@ async function placeStellarOrder(
         { type: "instance" }
       ),
       // Permit signature data
+      nativeToScVal(BigInt(nonce), { type: "u64" }), // nonce
+      nativeToScVal(BigInt(deadline), { type: "u64" }), // deadline
       nativeToScVal(signatureData.publicKeyBytes, { type: "bytes" }), // public_key (BytesN<32>)
-      nativeToScVal(signatureData.signatureBytes, { type: "bytes" }), // signature (BytesN<64>)
-      nativeToScVal(permitHash, { type: "bytes" }) // hash (BytesN<32>)
+      nativeToScVal(signatureData.signatureBytes, { type: "bytes" }) // signature (BytesN<64>)
     );
 
     // Set owner as source for authorization (only_owner check)
@@ -243,19 +288,21 @@ async function placeOrderWithAlice(amountIn, minAmountOut, maxAmountOut) {
  * Generate CLI command for comparison
  * @param {Object} orderData - Order data
  * @param {Object} signatureData - Signature data
- * @param {Buffer} permitHash - Permit hash
+ * @param {string|number} nonce - Permit nonce
+ * @param {string|number} deadline - Permit deadline
  * @returns {string} - CLI command
  */
-function generateCLICommand(orderData, signatureData, permitHash) {
+function generateCLICommand(orderData, signatureData, nonce, deadline) {
   return `soroban contract invoke \\
   --id ${RELAYER_CONTRACT_ADDRESS} \\
   --source alice \\
   --network testnet \\
   -- place_order \\
   --order_input '${JSON.stringify(orderData)}' \\
+  --nonce ${nonce} \\
+  --deadline ${deadline} \\
   --public_key ${signatureData.publicKeyBytes.toString("hex")} \\
-  --signature ${signatureData.signatureBytes.toString("hex")} \\
-  --hash ${permitHash.toString("hex")}`;
+  --signature ${signatureData.signatureBytes.toString("hex")}`;
 }
 
 // Helper function to convert tokens to wei-like units (18 decimals)
@@ -273,7 +320,7 @@ module.exports = placeStellarOrder;
 module.exports.placeStellarOrder = placeStellarOrder;
 module.exports.placeOrderWithAlice = placeOrderWithAlice;
 module.exports.createOrderData = createOrderData;
-module.exports.createPermitHash = createPermitHash;
+module.exports.createPermitDigest = createPermitDigest;
 module.exports.signPermit = signPermit;
 module.exports.contractAddressToBytes32 = contractAddressToBytes32;
 module.exports.generateBytes32 = generateBytes32;
@@ -20,12 +20,14 @@ const aliceKeypair = Keypair.fromSecret(ALICE_PRIVATE_KEY);
  * Deploy escrow source contract via resolver
  * @param {string} callerAddress - Address of the caller (executive resolver)
  * @param {string} orderId - Order ID (32 bytes hex string)
+ * @param {string|number} maxAmountOut - Most the fill may commit to on the destination side
  * @param {string} ownerPrivateKey - Owner's private key (defaults to Alice)
  * @returns {Promise<string>} - Transaction hash
  */
 async function deployStellarSrc(
   callerAddress,
   orderId,
+  maxAmountOut,
   ownerPrivateKey = ALICE_PRIVATE_KEY
 ) {
   try {
@@ -34,6 +36,7 @@ async function deployStellarSrc(
     console.log(`Deploying escrow source via resolver...`);
     console.log(`Caller: ${callerAddress}`);
     console.log(`Order ID: ${orderId}`);
+    console.log(`Max Amount Out: ${maxAmountOut}`);
     console.log(`Owner: ${ownerKeypair.publicKey()}`);
 
     // Validate order ID format (should be 32 bytes)
@@ -45,11 +48,12 @@ async function deployStellarSrc(
     const account = await server.getAccount(ownerKeypair.publicKey());
     const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
 
-    // Contract function: deploy_escrow_src(env: Env, caller: Address, order_id: BytesN<32>)
+    // Contract function: deploy_escrow_src(env: Env, caller: Address, order_id: BytesN<32>, max_amount_out: u128)
     const operation = resolverContract.call(
       "deploy_escrow_src",
       nativeToScVal(callerAddress, { type: "address" }), // caller parameter
-      nativeToScVal(orderIdBuffer, { type: "bytes" }) // order_id parameter (BytesN<32>)
+      nativeToScVal(orderIdBuffer, { type: "bytes" }), // order_id parameter (BytesN<32>)
+      nativeToScVal(BigInt(maxAmountOut), { type: "u128" }) // max_amount_out parameter
     );
 
     // Set owner as source for authorization (only_owner check)
@@ -99,11 +103,16 @@ function generateOrderId() {
 /**
  * Convenience function using Alice as caller
  * @param {string} orderId - Order ID (optional, generates random if not provided)
+ * @param {string|number} maxAmountOut - Most the fill may commit to on the destination side
  * @returns {Promise<string>} - Transaction hash
  */
-async function deployWithAlice(orderId) {
+async function deployWithAlice(orderId, maxAmountOut) {
   const orderIdToUse = orderId || generateOrderId();
-  return await deployStellarSrc(aliceKeypair.publicKey(), orderIdToUse);
+  return await deployStellarSrc(
+    aliceKeypair.publicKey(),
+    orderIdToUse,
+    maxAmountOut
+  );
 }
 
 /**
@@ -159,7 +168,8 @@ if (require.main === module) {
 
       // Deploy escrow source
       console.log("\n🏗️ Deploying escrow source...");
-      const hash = await deployStellarSrc(callerAddress, orderId);
+      const maxAmountOut = tokensToUnits(1);
+      const hash = await deployStellarSrc(callerAddress, orderId, maxAmountOut);
       console.log(`🎉 Deploy Success! Hash: ${hash}`);
 
       console.log("\n📝 What happened:");
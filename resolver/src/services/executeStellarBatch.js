@@ -0,0 +1,216 @@
+// Resolver
+
+const StellarSdk = require("@stellar/stellar-sdk");
+const { Buffer } = require("buffer");
+
+const {
+  Keypair,
+  TransactionBuilder,
+  Networks,
+  Contract,
+  nativeToScVal,
+  rpc,
+  xdr,
+} = StellarSdk;
+
+// Configuration
+const ALICE_PRIVATE_KEY =
+  "SABCJCNM2TQFPU7IBJZFMUMLYAXJ2GJE5RGP7AKAEBWDS7MRJM34DOS4";
+const RESOLVER_CONTRACT_ADDRESS =
+  "CCAYPNN44LTM5JJAAIDRJPUNSHOHCR56WY6UZYPDQ7B26UAT46NMNXPS";
+
+const server = new rpc.Server("https://soroban-testnet.stellar.org");
+const networkPassphrase = Networks.TESTNET;
+
+// Contract enum variants are encoded as a vec of the variant name and its fields
+function variant(name, ...fields) {
+  return xdr.ScVal.scvVec([xdr.ScVal.scvSymbol(name), ...fields]);
+}
+
+function address(value) {
+  return nativeToScVal(value, { type: "address" });
+}
+
+function bytes32(hex) {
+  const buffer = Buffer.from(hex.replace("0x", ""), "hex");
+  if (buffer.length !== 32) {
+    throw new Error("Expected exactly 32 bytes (64 hex characters)");
+  }
+  return nativeToScVal(buffer, { type: "bytes" });
+}
+
+function secretBytes(secret) {
+  return nativeToScVal(Buffer.from(secret, "utf8"), { type: "bytes" });
+}
+
+// Builders for the Resolver's Action enum, same arguments as the entrypoints
+const actions = {
+  fill: (caller, orderId, maxAmountOut) =>
+    variant(
+      "Fill",
+      address(caller),
+      bytes32(orderId),
+      nativeToScVal(BigInt(maxAmountOut), { type: "u128" })
+    ),
+  // DestDeployment struct, fields in the sorted order the contract expects
+  deployDest: (caller, orderId, hashLock, tokenOut, amountOut, maker) =>
+    variant(
+      "DeployDest",
+      xdr.ScVal.scvMap([
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("amount_out"),
+          val: nativeToScVal(BigInt(amountOut), { type: "u128" }),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("caller"),
+          val: address(caller),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("hash_lock"),
+          val: bytes32(hashLock),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("maker"),
+          val: address(maker),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("order_id"),
+          val: bytes32(orderId),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("token_out"),
+          val: address(tokenOut),
+        }),
+      ])
+    ),
+  notify: (orderId, escrowSrc, escrowDest, caller) =>
+    variant(
+      "Notify",
+      bytes32(orderId),
+      bytes32(escrowSrc),
+      bytes32(escrowDest),
+      address(caller)
+    ),
+  withdrawSrc: (escrow, secret) =>
+    variant("WithdrawSrc", address(escrow), secretBytes(secret)),
+  publicWithdrawSrc: (escrow, secret) =>
+    variant("PublicWithdrawSrc", address(escrow), secretBytes(secret)),
+  cancelSrc: (escrow) => variant("CancelSrc", address(escrow)),
+  publicCancelSrc: (escrow) => variant("PublicCancelSrc", address(escrow)),
+  withdrawDest: (escrow, secret) =>
+    variant("WithdrawDest", address(escrow), secretBytes(secret)),
+  publicWithdrawDest: (escrow, secret) =>
+    variant("PublicWithdrawDest", address(escrow), secretBytes(secret)),
+  cancelDest: (escrow) => variant("CancelDest", address(escrow)),
+  transfer: (token, to, amount) =>
+    variant(
+      "Transfer",
+      address(token),
+      address(to),
+      nativeToScVal(BigInt(amount), { type: "i128" })
+    ),
+  // Swap struct through the router set with set_router (Soroswap on testnet),
+  // path is token sold first, token bought last
+  swap: (path, amountIn, minAmountOut, deadline) =>
+    variant(
+      "Swap",
+      xdr.ScVal.scvMap([
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("amount_in"),
+          val: nativeToScVal(BigInt(amountIn), { type: "i128" }),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("deadline"),
+          val: nativeToScVal(BigInt(deadline), { type: "u64" }),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("min_amount_out"),
+          val: nativeToScVal(BigInt(minAmountOut), { type: "i128" }),
+        }),
+        new xdr.ScMapEntry({
+          key: xdr.ScVal.scvSymbol("path"),
+          val: xdr.ScVal.scvVec(path.map(address)),
+        }),
+      ])
+    ),
+  // borrow from the vault set with set_vault before deployDest, repay once
+  // the source side paid out, proceeds in the vault's asset
+  borrow: (orderId, amount) =>
+    variant(
+      "Borrow",
+      bytes32(orderId),
+      nativeToScVal(BigInt(amount), { type: "i128" })
+    ),
+  repay: (orderId, proceeds) =>
+    variant(
+      "Repay",
+      bytes32(orderId),
+      nativeToScVal(BigInt(proceeds), { type: "i128" })
+    ),
+  // only runs for (contract, fn) pairs the owner allowed with set_call_allowed
+  call: (contract, fn, args) =>
+    variant(
+      "Call",
+      address(contract),
+      xdr.ScVal.scvSymbol(fn),
+      xdr.ScVal.scvVec(args)
+    ),
+};
+
+/**
+ * Run several Resolver actions in one transaction, all or nothing
+ * @param {Array} batch - Actions built with the `actions` helpers
+ * @param {string} ownerPrivateKey - Resolver owner's private key (defaults to Alice)
+ * @returns {Promise<string>} - Transaction hash
+ */
+async function executeStellarBatch(batch, ownerPrivateKey = ALICE_PRIVATE_KEY) {
+  try {
+    const ownerKeypair = Keypair.fromSecret(ownerPrivateKey);
+
+    console.log(`Executing a batch of ${batch.length} resolver actions...`);
+    console.log(`Owner: ${ownerKeypair.publicKey()}`);
+
+    const account = await server.getAccount(ownerKeypair.publicKey());
+    const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
+
+    // Contract function: execute_batch(env: Env, actions: Vec<Action>)
+    const operation = resolverContract.call(
+      "execute_batch",
+      xdr.ScVal.scvVec(batch)
+    );
+
+    // Set owner as source for authorization (only_owner check)
+    operation.source = ownerKeypair.publicKey();
+
+    const transaction = new TransactionBuilder(account, {
+      fee: "10000000",
+      networkPassphrase: networkPassphrase,
+    })
+      .addOperation(operation)
+      .setTimeout(300)
+      .build();
+
+    console.log("Simulating batch transaction...");
+    const simulationResponse = await server.simulateTransaction(transaction);
+
+    if (simulationResponse.error) {
+      throw new Error(`Batch simulation failed: ${simulationResponse.error}`);
+    }
+
+    console.log("✅ Batch simulation successful");
+    const preparedTransaction = await server.prepareTransaction(transaction);
+    preparedTransaction.sign(ownerKeypair);
+
+    const response = await server.sendTransaction(preparedTransaction);
+
+    console.log(`✅ Batch transaction submitted: ${response.hash}`);
+    return response.hash;
+  } catch (error) {
+    console.error("❌ Batch failed:", error.message);
+    throw error;
+  }
+}
+
+module.exports = executeStellarBatch;
+module.exports.executeStellarBatch = executeStellarBatch;
+module.exports.actions = actions;
@@ -0,0 +1,133 @@
+// Resolver
+
+const StellarSdk = require("@stellar/stellar-sdk");
+
+const { Keypair, TransactionBuilder, Networks, Contract, nativeToScVal, rpc } =
+  StellarSdk;
+
+// Configuration
+const ALICE_PRIVATE_KEY =
+  "SABCJCNM2TQFPU7IBJZFMUMLYAXJ2GJE5RGP7AKAEBWDS7MRJM34DOS4";
+const RESOLVER_CONTRACT_ADDRESS =
+  "CCAYPNN44LTM5JJAAIDRJPUNSHOHCR56WY6UZYPDQ7B26UAT46NMNXPS";
+
+const server = new rpc.Server("https://soroban-testnet.stellar.org");
+const networkPassphrase = Networks.TESTNET;
+
+/**
+ * Simulate, sign and submit one call to the resolver contract
+ * @param {string} fn - Contract function name
+ * @param {Array} args - ScVal arguments
+ * @param {string} ownerPrivateKey - Resolver owner's private key
+ * @returns {Promise<string>} - Transaction hash
+ */
+async function callResolver(fn, args, ownerPrivateKey) {
+  const ownerKeypair = Keypair.fromSecret(ownerPrivateKey);
+  const account = await server.getAccount(ownerKeypair.publicKey());
+  const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
+
+  const operation = resolverContract.call(fn, ...args);
+  // Set owner as source for authorization (only_owner check)
+  operation.source = ownerKeypair.publicKey();
+
+  const transaction = new TransactionBuilder(account, {
+    fee: "10000000",
+    networkPassphrase: networkPassphrase,
+  })
+    .addOperation(operation)
+    .setTimeout(300)
+    .build();
+
+  console.log(`Simulating ${fn} transaction...`);
+  const simulationResponse = await server.simulateTransaction(transaction);
+
+  if (simulationResponse.error) {
+    throw new Error(`${fn} simulation failed: ${simulationResponse.error}`);
+  }
+
+  const preparedTransaction = await server.prepareTransaction(transaction);
+  preparedTransaction.sign(ownerKeypair);
+
+  const response = await server.sendTransaction(preparedTransaction);
+
+  console.log(`✅ ${fn} transaction submitted: ${response.hash}`);
+  return response.hash;
+}
+
+/**
+ * Fund the resolver's treasury from the owner's account
+ * @param {string} tokenAddress - Token contract address
+ * @param {string|number} amount - Amount in token units
+ * @param {string} ownerPrivateKey - Resolver owner's private key (defaults to Alice)
+ * @returns {Promise<string>} - Transaction hash
+ */
+async function depositToResolver(
+  tokenAddress,
+  amount,
+  ownerPrivateKey = ALICE_PRIVATE_KEY
+) {
+  const from = Keypair.fromSecret(ownerPrivateKey).publicKey();
+  // Contract function: deposit(env: Env, token: Address, from: Address, amount: i128)
+  return callResolver(
+    "deposit",
+    [
+      nativeToScVal(tokenAddress, { type: "address" }),
+      nativeToScVal(from, { type: "address" }),
+      nativeToScVal(BigInt(amount), { type: "i128" }),
+    ],
+    ownerPrivateKey
+  );
+}
+
+/**
+ * Pay tokens out of the resolver's treasury
+ * @param {string} tokenAddress - Token contract address
+ * @param {string} toAddress - Recipient address
+ * @param {string|number} amount - Amount in token units
+ * @param {string} ownerPrivateKey - Resolver owner's private key (defaults to Alice)
+ * @returns {Promise<string>} - Transaction hash
+ */
+async function withdrawFromResolver(
+  tokenAddress,
+  toAddress,
+  amount,
+  ownerPrivateKey = ALICE_PRIVATE_KEY
+) {
+  // Contract function: withdraw(env: Env, token: Address, to: Address, amount: i128)
+  return callResolver(
+    "withdraw",
+    [
+      nativeToScVal(tokenAddress, { type: "address" }),
+      nativeToScVal(toAddress, { type: "address" }),
+      nativeToScVal(BigInt(amount), { type: "i128" }),
+    ],
+    ownerPrivateKey
+  );
+}
+
+/**
+ * Pay out everything withdrawable of a token, native tokens keep the reserve
+ * @param {string} tokenAddress - Token contract address
+ * @param {string} toAddress - Recipient address
+ * @param {string} ownerPrivateKey - Resolver owner's private key (defaults to Alice)
+ * @returns {Promise<string>} - Transaction hash
+ */
+async function sweepResolver(
+  tokenAddress,
+  toAddress,
+  ownerPrivateKey = ALICE_PRIVATE_KEY
+) {
+  // Contract function: sweep(env: Env, token: Address, to: Address) -> i128
+  return callResolver(
+    "sweep",
+    [
+      nativeToScVal(tokenAddress, { type: "address" }),
+      nativeToScVal(toAddress, { type: "address" }),
+    ],
+    ownerPrivateKey
+  );
+}
+
+module.exports.depositToResolver = depositToResolver;
+module.exports.withdrawFromResolver = withdrawFromResolver;
+module.exports.sweepResolver = sweepResolver;
@@ -20,12 +20,14 @@ const aliceKeypair = Keypair.fromSecret(ALICE_PRIVATE_KEY);
  * Withdraw tokens from escrow using secret via resolver
  * @param {string} escrowAddress - Escrow contract address
  * @param {string} secret - Secret to unlock the escrow (plain text)
- * @param {string} ownerPrivateKey - Resolver owner's private key (defaults to Alice)
+ * @param {"src"|"dest"} side - Which side of the swap the escrow is on
+ * @param {string} ownerPrivateKey - Resolver operator's private key (defaults to Alice)
  * @returns {Promise<string>} - Transaction hash
  */
 async function withdrawStellarToken(
   escrowAddress,
   secret,
+  side,
   ownerPrivateKey = ALICE_PRIVATE_KEY
 ) {
   try {
@@ -43,14 +45,15 @@ async function withdrawStellarToken(
     const account = await server.getAccount(ownerKeypair.publicKey());
     const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
 
-    // Contract function: withdraw(env: Env, escrow: Address, secret: BytesN<32>)
+    // Contract function: withdraw_{side}(env: Env, operator: Address, escrow: Address, secret: BytesN<32>)
     const operation = resolverContract.call(
-      "withdraw",
+      escrowFunction("withdraw", side),
+      nativeToScVal(ownerKeypair.publicKey(), { type: "address" }), // operator
       nativeToScVal(escrowAddress, { type: "address" }), // escrow parameter
       nativeToScVal(Buffer.from(secret, "utf8"), { type: "bytes" }) // secret parameter (BytesN<32>)
     );
 
-    // Set owner as source (resolver uses its own address as caller internally)
+    // Set operator as source for authorization (operator.require_auth())
     operation.source = ownerKeypair.publicKey();
 
     const transaction = new TransactionBuilder(account, {
@@ -88,12 +91,14 @@ async function withdrawStellarToken(
  * Public withdraw - allows any resolver to withdraw after timeout
  * @param {string} escrowAddress - Escrow contract address
  * @param {string} secret - Secret to unlock the escrow
- * @param {string} ownerPrivateKey - Resolver owner's private key
+ * @param {"src"|"dest"} side - Which side of the swap the escrow is on
+ * @param {string} ownerPrivateKey - Resolver operator's private key
  * @returns {Promise<string>} - Transaction hash
  */
 async function publicWithdrawStellarToken(
   escrowAddress,
   secret,
+  side,
   ownerPrivateKey = ALICE_PRIVATE_KEY
 ) {
   try {
@@ -106,9 +111,10 @@ async function publicWithdrawStellarToken(
     const account = await server.getAccount(ownerKeypair.publicKey());
     const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
 
-    // Contract function: public_withdraw(env: Env, escrow: Address, secret: BytesN<32>)
+    // Contract function: public_withdraw_{side}(env: Env, operator: Address, escrow: Address, secret: BytesN<32>)
     const operation = resolverContract.call(
-      "public_withdraw",
+      escrowFunction("public_withdraw", side),
+      nativeToScVal(ownerKeypair.publicKey(), { type: "address" }), // operator
       nativeToScVal(escrowAddress, { type: "address" }),
       nativeToScVal(Buffer.from(secret, "utf8"), { type: "bytes" })
     );
@@ -147,27 +153,30 @@ async function publicWithdrawStellarToken(
 }
 
 /**
- * Cancel escrow (returns funds to maker)
+ * Cancel escrow (returns funds to whoever locked them)
  * @param {string} escrowAddress - Escrow contract address
- * @param {string} ownerPrivateKey - Resolver owner's private key
+ * @param {"src"|"dest"} side - Which side of the swap the escrow is on
+ * @param {string} ownerPrivateKey - Resolver operator's private key
  * @returns {Promise<string>} - Transaction hash
  */
 async function cancelStellarEscrow(
   escrowAddress,
+  side,
   ownerPrivateKey = ALICE_PRIVATE_KEY
 ) {
   try {
     const ownerKeypair = Keypair.fromSecret(ownerPrivateKey);
 
-    console.log(`Canceling escrow (returning funds to maker)...`);
+    console.log(`Canceling ${side} escrow...`);
     console.log(`Escrow Address: ${escrowAddress}`);
 
     const account = await server.getAccount(ownerKeypair.publicKey());
     const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
 
-    // Contract function: cancel(env: Env, escrow: Address)
+    // Contract function: cancel_{side}(env: Env, operator: Address, escrow: Address)
     const operation = resolverContract.call(
-      "cancel",
+      escrowFunction("cancel", side),
+      nativeToScVal(ownerKeypair.publicKey(), { type: "address" }), // operator
       nativeToScVal(escrowAddress, { type: "address" })
     );
 
@@ -203,9 +212,10 @@ async function cancelStellarEscrow(
 }
 
 /**
- * Public cancel escrow (allows any resolver to cancel after timeout)
+ * Public cancel escrow (allows any resolver to cancel after timeout).
+ * Only source escrows can be publicly cancelled.
  * @param {string} escrowAddress - Escrow contract address
- * @param {string} ownerPrivateKey - Resolver owner's private key
+ * @param {string} ownerPrivateKey - Resolver operator's private key
  * @returns {Promise<string>} - Transaction hash
  */
 async function publicCancelStellarEscrow(
@@ -221,9 +231,10 @@ async function publicCancelStellarEscrow(
     const account = await server.getAccount(ownerKeypair.publicKey());
     const resolverContract = new Contract(RESOLVER_CONTRACT_ADDRESS);
 
-    // Contract function: public_cancel(env: Env, escrow: Address)
+    // Contract function: public_cancel_src(env: Env, operator: Address, escrow: Address)
     const operation = resolverContract.call(
-      "public_cancel",
+      "public_cancel_src",
+      nativeToScVal(ownerKeypair.publicKey(), { type: "address" }), // operator
       nativeToScVal(escrowAddress, { type: "address" })
     );
 
@@ -260,6 +271,19 @@ async function publicCancelStellarEscrow(
   }
 }
 
+/**
+ * Resolver entrypoint for an escrow operation on the given side
+ * @param {string} operation - withdraw, public_withdraw or cancel
+ * @param {"src"|"dest"} side - Which side of the swap the escrow is on
+ * @returns {string} - Contract function name
+ */
+function escrowFunction(operation, side) {
+  if (side !== "src" && side !== "dest") {
+    throw new Error(`Unknown escrow side: ${side}`);
+  }
+  return `${operation}_${side}`;
+}
+
 /**
  * Create hash from secret (for verification)
  * @param {string} secret - Secret string
@@ -286,10 +310,16 @@ function verifySecretHash(secret, hashLock) {
  * Convenience function to withdraw with Alice's resolver
  * @param {string} escrowAddress - Escrow contract address
  * @param {string} secret - Secret to unlock
+ * @param {"src"|"dest"} side - Which side of the swap the escrow is on
  * @returns {Promise<string>} - Transaction hash
  */
-async function withdrawWithAlice(escrowAddress, secret) {
-  return await withdrawStellarToken(escrowAddress, secret, ALICE_PRIVATE_KEY);
+async function withdrawWithAlice(escrowAddress, secret, side) {
+  return await withdrawStellarToken(
+    escrowAddress,
+    secret,
+    side,
+    ALICE_PRIVATE_KEY
+  );
 }
 
 // Helper function to convert tokens to wei-like units (18 decimals)
@@ -345,22 +375,22 @@ if (require.main === module) {
       );
       console.log("Example usage:");
       console.log(
-        `await withdrawStellarToken('ACTUAL_ESCROW_ADDRESS', '${secret}');`
+        `await withdrawStellarToken('ACTUAL_ESCROW_ADDRESS', '${secret}', 'dest');`
       );
 
       // Demonstrate the different withdraw/cancel functions available
       console.log("\n📚 Available Functions:");
       console.log(
-        "1. withdrawStellarToken(escrow, secret) - Normal withdraw with secret"
+        "1. withdrawStellarToken(escrow, secret, side) - Normal withdraw with secret"
       );
       console.log(
-        "2. publicWithdrawStellarToken(escrow, secret) - Public withdraw after timeout"
+        "2. publicWithdrawStellarToken(escrow, secret, side) - Public withdraw after timeout"
       );
       console.log(
-        "3. cancelStellarEscrow(escrow) - Cancel and return funds to maker"
+        "3. cancelStellarEscrow(escrow, side) - Cancel and return the locked funds"
       );
       console.log(
-        "4. publicCancelStellarEscrow(escrow) - Public cancel after timeout"
+        "4. publicCancelStellarEscrow(escrow) - Public cancel of a src escrow after timeout"
       );
 
       // For demo purposes, we'll skip the actual transaction since we don't have a real escrow
@@ -372,7 +402,7 @@ if (require.main === module) {
 
       console.log("\n🔧 Troubleshooting:");
       console.log("1. Make sure resolver contract is initialized");
-      console.log("2. Check that Alice is the owner of resolver");
+      console.log("2. Check that Alice is the owner or an operator of resolver");
       console.log("3. Verify escrow contract address is correct");
       console.log("4. Ensure secret matches the hash lock used in escrow");
       console.log("5. Check that escrow has tokens to withdraw");
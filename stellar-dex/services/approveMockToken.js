@@ -14,6 +14,8 @@ const MOCK_TOKEN_CONTRACT_ADDRESS =
 
 const server = new rpc.Server("https://soroban-testnet.stellar.org");
 const networkPassphrase = Networks.TESTNET;
+// ledgers the approval stays valid for, about a day at 5s per ledger
+const APPROVAL_LEDGERS = 17280;
 const aliceKeypair = Keypair.fromSecret(ALICE_PRIVATE_KEY);
 
 /**
@@ -37,15 +39,17 @@ async function approveMockToken(
     const account = await server.getAccount(callerKeypair.publicKey());
     const contract = new Contract(MOCK_TOKEN_CONTRACT_ADDRESS);
 
-    // Contract function: approve(env: Env, amount: u128, to: Address, caller: Address)
+    // SEP-41 approve(from: Address, spender: Address, amount: i128, expiration_ledger: u32)
+    const { sequence } = await server.getLatestLedger();
     const operation = contract.call(
       "approve",
-      nativeToScVal(BigInt(amount), { type: "u128" }), // amount parameter
-      nativeToScVal(spenderAddress, { type: "address" }), // to parameter (spender)
-      nativeToScVal(callerKeypair.publicKey(), { type: "address" }) // caller parameter
+      nativeToScVal(callerKeypair.publicKey(), { type: "address" }), // from
+      nativeToScVal(spenderAddress, { type: "address" }), // spender
+      nativeToScVal(BigInt(amount), { type: "i128" }), // amount
+      nativeToScVal(sequence + APPROVAL_LEDGERS, { type: "u32" }) // expiration_ledger
     );
 
-    // Set caller as source for authorization (caller.require_auth())
+    // Set caller as source for authorization (from.require_auth())
     operation.source = callerKeypair.publicKey();
 
     const transaction = new TransactionBuilder(account, {
@@ -0,0 +1,95 @@
+// Mocktoken
+
+const StellarSdk = require('@stellar/stellar-sdk');
+
+const {
+  Keypair,
+  TransactionBuilder,
+  Networks,
+  Contract,
+  nativeToScVal,
+  rpc
+} = StellarSdk;
+
+// Configuration
+const ALICE_PRIVATE_KEY = 'SABCJCNM2TQFPU7IBJZFMUMLYAXJ2GJE5RGP7AKAEBWDS7MRJM34DOS4';
+const MOCK_TOKEN_CONTRACT_ADDRESS = 'CCNITQBI3QTUQU5P55SJKBWCZDKTBB5FADYGZQGGZCAR5D7KGNT63O55';
+
+const server = new rpc.Server('https://soroban-testnet.stellar.org');
+const networkPassphrase = Networks.TESTNET;
+
+/**
+ * Drip mock tokens from the faucet, no admin key needed. The token admin sets
+ * the largest drip and the cooldown between drips of one address.
+ * @param {string|number} value - Amount to drip (in wei-like units, 18 decimals)
+ * @param {string} callerPrivateKey - Private key of the recipient (defaults to Alice)
+ * @returns {Promise<string>} - Transaction hash
+ */
+async function faucetMockToken(value, callerPrivateKey = ALICE_PRIVATE_KEY) {
+  try {
+    const callerKeypair = Keypair.fromSecret(callerPrivateKey);
+    const toAddress = callerKeypair.publicKey();
+    console.log(`Requesting ${value} token units for ${toAddress}...`);
+    
+    const account = await server.getAccount(toAddress);
+    const contract = new Contract(MOCK_TOKEN_CONTRACT_ADDRESS);
+    
+    // faucet(to: Address, amount: i128)
+    const operation = contract.call(
+      'faucet',
+      nativeToScVal(toAddress, { type: 'address' }), // to
+      nativeToScVal(BigInt(value), { type: 'i128' })   // amount
+    );
+    
+    // The recipient signs, to.require_auth()
+    const transaction = new TransactionBuilder(account, {
+      fee: '10000000',
+      networkPassphrase: networkPassphrase,
+    })
+      .addOperation(operation)
+      .setTimeout(300)
+      .build();
+
+    console.log('Simulating transaction...');
+    const simulationResponse = await server.simulateTransaction(transaction);
+    
+    if (simulationResponse.error) {
+      throw new Error(`Simulation failed: ${simulationResponse.error}`);
+    }
+    
+    console.log('Preparing and submitting transaction...');
+    const preparedTransaction = await server.prepareTransaction(transaction);
+    preparedTransaction.sign(callerKeypair);
+    
+    const response = await server.sendTransaction(preparedTransaction);
+    
+    console.log(`✅ Transaction submitted: ${response.hash}`);
+    return response.hash;
+    
+  } catch (error) {
+    console.error('❌ Faucet failed:', error.message);
+    throw error;
+  }
+}
+
+// Helper function to convert tokens to wei-like units (18 decimals)
+function tokensToUnits(tokens) {
+  return BigInt(tokens) * BigInt(10**18);
+}
+
+// Export the functions
+module.exports = faucetMockToken;
+module.exports.faucetMockToken = faucetMockToken;
+module.exports.tokensToUnits = tokensToUnits;
+
+// If run directly, execute with example parameters
+if (require.main === module) {
+  const tokens = 100; // 100 tokens
+  const value = tokensToUnits(tokens); // Convert to 18 decimal units
+  
+  console.log('🚰 Running faucetMockToken standalone...');
+  
+  faucetMockToken(value.toString())
+    .then(hash => console.log(`🎉 Success! Hash: ${hash}`))
+    .catch(error => console.error('💥 Error:', error.message));
+}
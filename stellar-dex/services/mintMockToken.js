@@ -21,7 +21,7 @@ const networkPassphrase = Networks.TESTNET;
 const aliceKeypair = Keypair.fromSecret(ALICE_PRIVATE_KEY);
 
 /**
- * Mint mock tokens to a specified address
+ * Mint mock tokens to a specified address, Alice has to be the token admin
  * @param {string} toAddress - Recipient address
  * @param {string|number} value - Amount to mint (in wei-like units, 18 decimals)
  * @returns {Promise<string>} - Transaction hash
@@ -33,14 +33,14 @@ async function mintMockToken(toAddress, value) {
     const account = await server.getAccount(aliceKeypair.publicKey());
     const contract = new Contract(MOCK_TOKEN_CONTRACT_ADDRESS);
     
-    // Use correct parameter names from contract: 'to' and 'value'
+    // mint(to: Address, amount: i128)
     const operation = contract.call(
       'mint',
-      nativeToScVal(toAddress, { type: 'address' }), // to parameter
-      nativeToScVal(BigInt(value), { type: 'u128' })   // value parameter (u128, not i128)
+      nativeToScVal(toAddress, { type: 'address' }), // to
+      nativeToScVal(BigInt(value), { type: 'i128' })   // amount
     );
     
-    // No need for authorization - mint function has no require_auth()
+    // Only the token admin can mint, Alice signs as the admin
     const transaction = new TransactionBuilder(account, {
       fee: '10000000',
       networkPassphrase: networkPassphrase,
@@ -5,6 +5,7 @@ const StellarSdk = require("@stellar/stellar-sdk");
 const { Buffer } = require("buffer");
 
 const {
+  Address,
   Keypair,
   TransactionBuilder,
   Networks,
@@ -26,11 +27,49 @@ const server = new rpc.Server("https://soroban-testnet.stellar.org");
 const networkPassphrase = Networks.TESTNET;
 const aliceKeypair = Keypair.fromSecret(ALICE_PRIVATE_KEY);
 
-// Step 1: Create deterministic hash (following your exact pattern)
-function createPermitHash(token, owner, spender, amount) {
-  const message = `${token}:${owner}:${spender}:${amount}`;
-  const messageBuffer = Buffer.from(message, "utf8");
-  return hash(messageBuffer);
+// Must match PERMIT_DOMAIN in contracts/stellar/contracts/WrappedTokens
+const PERMIT_DOMAIN = "FusionX WrappedTokens permit v1";
+const PERMIT_VALIDITY_SECONDS = 60 * 60;
+
+function addressXdr(address) {
+  return Address.fromString(address).toScVal().toXDR();
+}
+
+function uintBE(value, bytes) {
+  const out = Buffer.alloc(bytes);
+  let x = BigInt(value);
+  for (let i = bytes - 1; i >= 0; i--) {
+    out[i] = Number(x & 0xffn);
+    x >>= 8n;
+  }
+  return out;
+}
+
+function defaultDeadline() {
+  return Math.floor(Date.now() / 1000) + PERMIT_VALIDITY_SECONDS;
+}
+
+// Step 1: Create the digest WrappedTokens.permit_digest computes on chain,
+// see permit/test-vectors.json
+function createPermitDigest(token, owner, spender, amount, nonce, deadline) {
+  const domainSeparator = hash(
+    Buffer.concat([
+      Buffer.from(PERMIT_DOMAIN, "utf8"),
+      hash(Buffer.from(networkPassphrase, "utf8")),
+      addressXdr(WRAPPED_TOKEN_CONTRACT_ADDRESS),
+    ])
+  );
+  return hash(
+    Buffer.concat([
+      domainSeparator,
+      addressXdr(token),
+      addressXdr(owner),
+      addressXdr(spender),
+      uintBE(amount, 16),
+      uintBE(nonce, 8),
+      uintBE(deadline, 8),
+    ])
+  );
 }
 
 // Step 2: Sign the hash (following your exact pattern)
@@ -54,6 +93,8 @@ function signPermit(ownerSecretKey, permitHash) {
  * @param {string} spenderAddress - Spender address
  * @param {string|number} amount - Amount to permit
  * @param {string} ownerSecretKey - Owner's secret key for signing
+ * @param {string|number} nonce - Permit nonce
+ * @param {string|number} deadline - Last ledger timestamp the permit is valid at
  * @returns {Promise<string>} - Transaction hash
  */
 async function permitMockToken(
@@ -61,7 +102,9 @@ async function permitMockToken(
   ownerAddress,
   spenderAddress,
   amount,
-  ownerSecretKey = ALICE_PRIVATE_KEY
+  ownerSecretKey = ALICE_PRIVATE_KEY,
+  nonce = Date.now(),
+  deadline = defaultDeadline()
 ) {
   try {
     console.log("=== Permit Parameters ===");
@@ -69,14 +112,18 @@ async function permitMockToken(
     console.log(`Owner: ${ownerAddress}`);
     console.log(`Spender: ${spenderAddress}`);
     console.log(`Amount: ${amount}`);
+    console.log(`Nonce: ${nonce}`);
+    console.log(`Deadline: ${deadline}`);
     console.log("");
 
     // Step 1: Create hash
-    const permitHash = createPermitHash(
+    const permitHash = createPermitDigest(
       tokenAddress,
       ownerAddress,
       spenderAddress,
-      amount
+      amount,
+      nonce,
+      deadline
     );
     console.log("=== Step 1: Hash ===");
     console.log(`Hash (hex): ${permitHash.toString("hex")}`);
@@ -106,13 +153,14 @@ async function permitMockToken(
     console.log(`  --owner ${ownerAddress} \\`);
     console.log(`  --spender ${spenderAddress} \\`);
     console.log(`  --amount ${amount} \\`);
+    console.log(`  --nonce ${nonce} \\`);
+    console.log(`  --deadline ${deadline} \\`);
     console.log(
       `  --public_key ${signatureData.publicKeyBytes.toString("hex")} \\`
     );
     console.log(
-      `  --signature ${signatureData.signatureBytes.toString("hex")} \\`
+      `  --signature ${signatureData.signatureBytes.toString("hex")}`
     );
-    console.log(`  --hash ${permitHash.toString("hex")}`);
     console.log("");
 
     // Step 4: Execute permit transaction
@@ -121,16 +169,17 @@ async function permitMockToken(
     const account = await server.getAccount(aliceKeypair.publicKey());
     const wrappedContract = new Contract(WRAPPED_TOKEN_CONTRACT_ADDRESS);
 
-    // Contract function: permit(env, token, owner, spender, amount, public_key, signature, hash)
+    // Contract function: permit(env, token, owner, spender, amount, nonce, deadline, public_key, signature)
     const operation = wrappedContract.call(
       "permit",
       nativeToScVal(tokenAddress, { type: "address" }), // token
       nativeToScVal(ownerAddress, { type: "address" }), // owner
       nativeToScVal(spenderAddress, { type: "address" }), // spender
       nativeToScVal(BigInt(amount), { type: "u128" }), // amount
+      nativeToScVal(BigInt(nonce), { type: "u64" }), // nonce
+      nativeToScVal(BigInt(deadline), { type: "u64" }), // deadline
       nativeToScVal(signatureData.publicKeyBytes, { type: "bytes" }), // public_key (BytesN<32>)
-      nativeToScVal(signatureData.signatureBytes, { type: "bytes" }), // signature (BytesN<64>)
-      nativeToScVal(permitHash, { type: "bytes" }) // hash (BytesN<32>)
+      nativeToScVal(signatureData.signatureBytes, { type: "bytes" }) // signature (BytesN<64>)
     );
 
     const transaction = new TransactionBuilder(account, {
@@ -171,6 +220,8 @@ async function permitMockToken(
  * @param {string} spenderAddress - Spender address
  * @param {string|number} amount - Amount to permit
  * @param {string} ownerSecretKey - Owner's secret key
+ * @param {string|number} nonce - Permit nonce
+ * @param {string|number} deadline - Last ledger timestamp the permit is valid at
  * @returns {Object} - Signature data
  */
 function generatePermitSignature(
@@ -178,21 +229,27 @@ function generatePermitSignature(
   ownerAddress,
   spenderAddress,
   amount,
-  ownerSecretKey
+  ownerSecretKey,
+  nonce = Date.now(),
+  deadline = defaultDeadline()
 ) {
   console.log("=== Permit Parameters ===");
   console.log(`Token: ${tokenAddress}`);
   console.log(`Owner: ${ownerAddress}`);
   console.log(`Spender: ${spenderAddress}`);
   console.log(`Amount: ${amount}`);
+  console.log(`Nonce: ${nonce}`);
+  console.log(`Deadline: ${deadline}`);
   console.log("");
 
   // Step 1: Create hash
-  const permitHash = createPermitHash(
+  const permitHash = createPermitDigest(
     tokenAddress,
     ownerAddress,
     spenderAddress,
-    amount
+    amount,
+    nonce,
+    deadline
   );
   console.log("=== Step 1: Hash ===");
   console.log(`Hash (hex): ${permitHash.toString("hex")}`);
@@ -222,13 +279,14 @@ function generatePermitSignature(
   console.log(`  --owner ${ownerAddress} \\`);
   console.log(`  --spender ${spenderAddress} \\`);
   console.log(`  --amount ${amount} \\`);
+  console.log(`  --nonce ${nonce} \\`);
+  console.log(`  --deadline ${deadline} \\`);
   console.log(
     `  --public_key ${signatureData.publicKeyBytes.toString("hex")} \\`
   );
   console.log(
-    `  --signature ${signatureData.signatureBytes.toString("hex")} \\`
+    `  --signature ${signatureData.signatureBytes.toString("hex")}`
   );
-  console.log(`  --hash ${permitHash.toString("hex")}`);
 
   return {
     hash: permitHash,
@@ -238,11 +296,9 @@ function generatePermitSignature(
     publicKeyHex: signatureData.publicKeyBytes.toString("hex"),
     signature: signatureData.signature,
     signatureHex: signatureData.signatureBytes.toString("hex"),
-    cliCommand: `soroban contract invoke --id ${WRAPPED_TOKEN_CONTRACT_ADDRESS} --source alice --network testnet -- permit --token ${tokenAddress} --owner ${ownerAddress} --spender ${spenderAddress} --amount ${amount} --public_key ${signatureData.publicKeyBytes.toString(
-      "hex"
-    )} --signature ${signatureData.signatureBytes.toString(
+    cliCommand: `soroban contract invoke --id ${WRAPPED_TOKEN_CONTRACT_ADDRESS} --source alice --network testnet -- permit --token ${tokenAddress} --owner ${ownerAddress} --spender ${spenderAddress} --amount ${amount} --nonce ${nonce} --deadline ${deadline} --public_key ${signatureData.publicKeyBytes.toString(
       "hex"
-    )} --hash ${permitHash.toString("hex")}`,
+    )} --signature ${signatureData.signatureBytes.toString("hex")}`,
   };
 }
 
@@ -251,7 +307,7 @@ function generatePermitSignature(
  */
 function browserExample() {
   return {
-    createHash: createPermitHash,
+    createHash: createPermitDigest,
     signWithWallet: async (walletSigner, hash) => {
       // Integrate with Freighter, xBull, or other Stellar wallets
       const signature = await walletSigner.sign(hash);
@@ -273,7 +329,7 @@ function unitsToTokens(units) {
 // Export for use (following your pattern)
 module.exports = permitMockToken;
 module.exports.permitMockToken = permitMockToken;
-module.exports.createPermitHash = createPermitHash;
+module.exports.createPermitDigest = createPermitDigest;
 module.exports.signPermit = signPermit;
 module.exports.generatePermitSignature = generatePermitSignature;
 module.exports.browserExample = browserExample;
@@ -306,7 +362,7 @@ if (require.main === module) {
 
       console.log("\n🔧 Troubleshooting:");
       console.log("1. Make sure wrapped token contract is deployed");
-      console.log("2. Check that signature is not already used");
+      console.log("2. Check that the permit nonce is not already used");
       console.log("3. Verify contract addresses are correct");
       console.log("4. Ensure Alice has sufficient XLM for transaction fees");
     }
@@ -24,6 +24,12 @@ const RELAYER_CONTRACT_ADDRESS = 'CC5MRCCFFRNTBKYGNH6COX2WGJPNNAW2DYGLH3BYFUEQS4
 const RESOLVER_CONTRACT_ADDRESS = 'CCAYPNN44LTM5JJAAIDRJPUNSHOHCR56WY6UZYPDQ7B26UAT46NMNXPS';
 const ESCROW_FACTORY_CONTRACT_ADDRESS = 'CC3X2OOS45ZETELRNXEK5FTPCOSQ3TBURMZHX647OP7FLYTTWPKVNXJN';
 const DUTCH_AUCTION_CONTRACT_ADDRESS = 'CCYMG6TSUAQCKP7NHAZVEIP2ON5J2GNZ4VOSZVA2G3QEQDYNOZ6HD4QC';
+const NATIVE_TOKEN_CONTRACT_ADDRESS = 'CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC'; // XLM SAC on testnet
+// hex wasm hashes printed by `stellar contract upload` for escrowsrc.wasm / escrowdest.wasm
+const ESCROW_SRC_WASM_HASH = process.env.ESCROW_SRC_WASM_HASH;
+const ESCROW_DEST_WASM_HASH = process.env.ESCROW_DEST_WASM_HASH;
+// hex wasm hash printed by `stellar contract upload` for wrappedasset.wasm
+const WRAPPED_ASSET_WASM_HASH = process.env.WRAPPED_ASSET_WASM_HASH;
 
 // Configure for testnet (change to Networks.PUBLIC for mainnet)
 const server = new rpc.Server('https://soroban-testnet.stellar.org');
@@ -47,6 +53,7 @@ async function initializeContracts() {
     await initializeRelayer();
     await initializeEscrowFactory();
     await initializeResolver();
+    await initializeWrappedTokens();
     
     console.log('✅ Contract initialization phase completed!');
     
@@ -62,7 +69,8 @@ async function checkInitializationStatus() {
     { name: 'Dutch Auction', address: DUTCH_AUCTION_CONTRACT_ADDRESS, checkFunction: 'get_owner' },
     { name: 'Relayer', address: RELAYER_CONTRACT_ADDRESS, checkFunction: 'get_owner' },
     { name: 'Escrow Factory', address: ESCROW_FACTORY_CONTRACT_ADDRESS, checkFunction: 'get_relayer' },
-    { name: 'Resolver', address: RESOLVER_CONTRACT_ADDRESS, checkFunction: 'get_owner' }
+    { name: 'Resolver', address: RESOLVER_CONTRACT_ADDRESS, checkFunction: 'get_owner' },
+    { name: 'Wrapped Tokens', address: WRAPPED_TOKEN_CONTRACT_ADDRESS, checkFunction: 'get_wrapped_asset_wasm_hash' }
   ];
 
   for (const contractInfo of contracts) {
@@ -141,7 +149,8 @@ async function initializeRelayer() {
       contract.call(
         'initialize',
         nativeToScVal(aliceKeypair.publicKey(), { type: 'address' }), // owner
-        nativeToScVal(DUTCH_AUCTION_CONTRACT_ADDRESS, { type: 'address' }) // dutch_auction
+        nativeToScVal(DUTCH_AUCTION_CONTRACT_ADDRESS, { type: 'address' }), // dutch_auction
+        nativeToScVal(WRAPPED_TOKEN_CONTRACT_ADDRESS, { type: 'address' }) // wrapped_tokens
       )
     )
     .setTimeout(300)
@@ -178,7 +187,10 @@ async function initializeEscrowFactory() {
       contract.call(
         'initialize',
         nativeToScVal(DUTCH_AUCTION_CONTRACT_ADDRESS, { type: 'address' }), // dutch_auction
-        nativeToScVal(RELAYER_CONTRACT_ADDRESS, { type: 'address' }) // relayer
+        nativeToScVal(RELAYER_CONTRACT_ADDRESS, { type: 'address' }), // relayer
+        nativeToScVal(NATIVE_TOKEN_CONTRACT_ADDRESS, { type: 'address' }), // native_token
+        nativeToScVal(Buffer.from(ESCROW_SRC_WASM_HASH, 'hex'), { type: 'bytes' }), // escrow_src_wasm_hash
+        nativeToScVal(Buffer.from(ESCROW_DEST_WASM_HASH, 'hex'), { type: 'bytes' }) // escrow_dest_wasm_hash
       )
     )
     .setTimeout(300)
@@ -201,6 +213,42 @@ async function initializeEscrowFactory() {
   await submitTransaction(preparedTransaction, 'Escrow Factory initialization');
 }
 
+async function initializeWrappedTokens() {
+  console.log('\n5. Initializing Wrapped Tokens Contract...');
+  
+  const account = await server.getAccount(aliceKeypair.publicKey());
+  const contract = new Contract(WRAPPED_TOKEN_CONTRACT_ADDRESS);
+  
+  const transaction = new TransactionBuilder(account, {
+    fee: '10000000',
+    networkPassphrase: networkPassphrase,
+  })
+    .addOperation(
+      contract.call(
+        'initialize',
+        nativeToScVal(Buffer.from(WRAPPED_ASSET_WASM_HASH, 'hex'), { type: 'bytes' }) // wrapped_asset_wasm_hash
+      )
+    )
+    .setTimeout(300)
+    .build();
+
+  // Simulate first
+  console.log('🔄 Simulating transaction...');
+  const simulationResponse = await server.simulateTransaction(transaction);
+  
+  if (simulationResponse.error) {
+    console.error('❌ Simulation failed:', simulationResponse.error);
+    throw new Error('Simulation failed');
+  }
+  
+  console.log('✅ Simulation successful');
+  
+  // Prepare the transaction
+  const preparedTransaction = await server.prepareTransaction(transaction);
+  
+  await submitTransaction(preparedTransaction, 'Wrapped Tokens initialization');
+}
+
 async function initializeResolver() {
   console.log('\n4. Initializing Resolver Contract...');
   
@@ -334,6 +382,12 @@ async function setupContractRelationships() {
     
     // Add resolver to relayer
     await addResolverToRelayer();
+
+    // Let escrows of the factory prove resolver misbehaviour for slashing
+    await trustEscrowFactoryInRelayer();
+
+    // Register token decimals in relayer
+    await registerTokenDecimals();
     
     console.log('✅ Contract relationships set up successfully!');
     
@@ -408,6 +462,65 @@ async function addResolverToRelayer() {
   await submitTransaction(transaction, 'Add resolver to relayer');
 }
 
+async function trustEscrowFactoryInRelayer() {
+  console.log('Trusting escrow factory in relayer...');
+  
+  const account = await server.getAccount(aliceKeypair.publicKey());
+  const contract = new Contract(RELAYER_CONTRACT_ADDRESS);
+  
+  const transaction = new TransactionBuilder(account, {
+    fee: '1000000',
+    networkPassphrase: networkPassphrase,
+  })
+    .addOperation(
+      contract.call(
+        'set_trusted_factory',
+        nativeToScVal(Address.fromString(ESCROW_FACTORY_CONTRACT_ADDRESS)),
+        nativeToScVal(true)
+      )
+    )
+    .setTimeout(300)
+    .build();
+
+  await submitTransaction(transaction, 'Trust escrow factory in relayer');
+}
+
+async function registerTokenDecimals() {
+  console.log('Registering token decimals in relayer...');
+
+  const contract = new Contract(RELAYER_CONTRACT_ADDRESS);
+  const calls = [
+    contract.call(
+      'set_token_decimals',
+      nativeToScVal(Address.fromString(MOCK_TOKEN_CONTRACT_ADDRESS)),
+      nativeToScVal(18, { type: 'u32' })
+    ),
+  ];
+  // 32 byte hex of the destination chain token orders use as tokenOut
+  if (process.env.DEST_TOKEN && process.env.DEST_TOKEN_DECIMALS) {
+    calls.push(
+      contract.call(
+        'set_dest_token_decimals',
+        nativeToScVal(Buffer.from(process.env.DEST_TOKEN.replace('0x', ''), 'hex'), { type: 'bytes' }),
+        nativeToScVal(Number(process.env.DEST_TOKEN_DECIMALS), { type: 'u32' })
+      )
+    );
+  }
+
+  for (const call of calls) {
+    const account = await server.getAccount(aliceKeypair.publicKey());
+    const transaction = new TransactionBuilder(account, {
+      fee: '1000000',
+      networkPassphrase: networkPassphrase,
+    })
+      .addOperation(call)
+      .setTimeout(300)
+      .build();
+
+    await submitTransaction(transaction, 'Register token decimals in relayer');
+  }
+}
+
 // Main execution function
 async function main() {
   console.log('🚀 Starting Soroban Contract Initialization');